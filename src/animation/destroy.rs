@@ -8,16 +8,28 @@ use std::time::Duration;
 
 const MAX_FLASHES: u32 = 3;
 const FLASH_DURATION: Duration = Duration::from_millis(250);
+const FADE_DURATION: Duration = Duration::from_millis(750);
 const SWEEP_DURATION: Duration = Duration::from_millis(750);
 const PARTICLE_FADE_IN_DURATION: Duration = Duration::from_millis(750);
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum DestroyAnimationType {
     Flash,
+    Fade,
     Sweep,
     Particles { color: Color },
 }
 
+impl DestroyAnimationType {
+    /// Replace flashing with a fade
+    pub fn reduced_motion(self) -> Self {
+        match self {
+            DestroyAnimationType::Flash => DestroyAnimationType::Fade,
+            destroy_type => destroy_type,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 enum State {
     Finished,
@@ -38,6 +50,7 @@ impl State {
 fn first_state(destroy_type: DestroyAnimationType) -> State {
     match destroy_type {
         DestroyAnimationType::Flash => State::Animate(Duration::ZERO, TextureAnimate::SetAlpha),
+        DestroyAnimationType::Fade => State::Animate(Duration::ZERO, TextureAnimate::FadeAlpha { alpha: 0.0 }),
         DestroyAnimationType::Sweep => State::Animate(
             Duration::ZERO,
             TextureAnimate::FillAlphaRectangle { width: 0.0 },
@@ -82,6 +95,15 @@ impl DestroyAnimation {
         }
     }
 
+    fn next_fade(&mut self, duration: Duration) -> State {
+        let alpha = duration.as_secs_f64() / FADE_DURATION.as_secs_f64();
+        if alpha >= 1.0 {
+            State::Finished
+        } else {
+            State::Animate(duration, TextureAnimate::FadeAlpha { alpha })
+        }
+    }
+
     fn next_sweep(&mut self, duration: Duration) -> State {
         let width = duration.as_secs_f64() / SWEEP_DURATION.as_secs_f64();
         if width >= 1.0 {
@@ -112,6 +134,7 @@ impl DestroyAnimation {
     fn next(&mut self, duration: Duration) -> State {
         match self.destroy_type {
             DestroyAnimationType::Flash => self.next_flash(duration),
+            DestroyAnimationType::Fade => self.next_fade(duration),
             DestroyAnimationType::Sweep => self.next_sweep(duration),
             DestroyAnimationType::Particles { color } => self.next_particles(color),
         }
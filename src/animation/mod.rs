@@ -2,6 +2,7 @@ pub mod destroy;
 pub mod game_over;
 pub mod hard_drop;
 pub mod impact;
+pub mod transition;
 
 use crate::particles::prescribed::PrescribedParticles;
 use std::time::Duration;
@@ -10,6 +11,7 @@ use std::time::Duration;
 pub enum TextureAnimate {
     Nothing,
     SetAlpha,
+    FadeAlpha { alpha: f64 },
     FillAlphaRectangle { width: f64 },
     EmitParticles(PrescribedParticles),
 }
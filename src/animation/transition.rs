@@ -0,0 +1,187 @@
+use crate::config::ThemeTransition;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{BlendMode, Texture, WindowCanvas};
+
+const DISSOLVE_BLOCK_PIXELS: u32 = 16;
+const BLINDS: u32 = 12;
+const CRT_MIN_PIXELS: u32 = 2;
+const PALETTE_FADE_STEPS: f64 = 4.0;
+
+/// Draw the transition from a snapshot of the previous theme over the next theme,
+/// progress runs from 0 (all previous theme) to 1 (all next theme)
+pub fn draw_transition(
+    canvas: &mut WindowCanvas,
+    previous: &mut Texture,
+    transition: ThemeTransition,
+    progress: f64,
+) -> Result<(), String> {
+    let progress = progress.clamp(0.0, 1.0);
+    let (width, height) = canvas.output_size()?;
+    previous.set_alpha_mod(255);
+    match transition {
+        ThemeTransition::Fade | ThemeTransition::Random => {
+            previous.set_alpha_mod((255.0 * (1.0 - progress)) as u8);
+            canvas.copy(previous, None, None)?;
+        }
+        ThemeTransition::Wipe => {
+            let rect = wipe_rect(width, height, progress);
+            if let Some(rect) = rect {
+                canvas.copy(previous, rect, rect)?;
+            }
+        }
+        ThemeTransition::Dissolve => {
+            let columns = width.div_ceil(DISSOLVE_BLOCK_PIXELS);
+            let rows = height.div_ceil(DISSOLVE_BLOCK_PIXELS);
+            for j in 0..rows {
+                for i in 0..columns {
+                    if dissolve_threshold(j * columns + i) < progress {
+                        continue;
+                    }
+                    let rect = Rect::new(
+                        (i * DISSOLVE_BLOCK_PIXELS) as i32,
+                        (j * DISSOLVE_BLOCK_PIXELS) as i32,
+                        DISSOLVE_BLOCK_PIXELS,
+                        DISSOLVE_BLOCK_PIXELS,
+                    );
+                    canvas.copy(previous, rect, rect)?;
+                }
+            }
+        }
+        ThemeTransition::Blinds => {
+            for rect in blind_rects(width, height, progress) {
+                canvas.copy(previous, rect, rect)?;
+            }
+        }
+        ThemeTransition::Crt => {
+            canvas.set_draw_color(Color::BLACK);
+            if progress < 0.5 {
+                // previous theme collapses into a line in the middle of the screen
+                canvas.fill_rect(None)?;
+                canvas.copy(previous, None, crt_rect(width, height, 1.0 - 2.0 * progress))?;
+            } else {
+                // next theme expands out from the line
+                let visible = crt_rect(width, height, 2.0 * progress - 1.0);
+                canvas.fill_rect(Rect::new(0, 0, width, visible.y() as u32))?;
+                canvas.fill_rect(Rect::new(
+                    0,
+                    visible.bottom(),
+                    width,
+                    height.saturating_sub(visible.bottom() as u32),
+                ))?;
+            }
+        }
+        ThemeTransition::PaletteFade => {
+            // step through the palette to white and back again like the game boy
+            let alpha = if progress < 0.5 {
+                canvas.copy(previous, None, None)?;
+                palette_fade_alpha(2.0 * progress)
+            } else {
+                palette_fade_alpha(2.0 - 2.0 * progress)
+            };
+            let blend_mode = canvas.blend_mode();
+            canvas.set_blend_mode(BlendMode::Blend);
+            canvas.set_draw_color(Color::RGBA(255, 255, 255, alpha));
+            canvas.fill_rect(None)?;
+            canvas.set_blend_mode(blend_mode);
+        }
+    }
+    Ok(())
+}
+
+/// The part of the previous theme still visible during a left to right wipe
+fn wipe_rect(width: u32, height: u32, progress: f64) -> Option<Rect> {
+    let x = (width as f64 * progress).round() as u32;
+    if x >= width {
+        None
+    } else {
+        Some(Rect::new(x as i32, 0, width - x, height))
+    }
+}
+
+/// The part of each blind still showing the previous theme, closing from the top of each blind
+fn blind_rects(width: u32, height: u32, progress: f64) -> Vec<Rect> {
+    let blind_height = height.div_ceil(BLINDS);
+    let visible_height = (blind_height as f64 * (1.0 - progress)).round() as u32;
+    if visible_height == 0 {
+        return vec![];
+    }
+    (0..BLINDS)
+        .map(|i| {
+            let y = i * blind_height + blind_height - visible_height;
+            Rect::new(0, y as i32, width, visible_height)
+        })
+        .collect()
+}
+
+/// Centered rect of the screen squashed vertically then horizontally as the scale reaches 0
+fn crt_rect(width: u32, height: u32, scale: f64) -> Rect {
+    let scale = scale.clamp(0.0, 1.0);
+    // squash vertically in the first 80% then down to a dot
+    let vertical = ((scale - 0.2) / 0.8).max(0.0);
+    let horizontal = (scale / 0.2).min(1.0);
+    let w = ((width as f64 * horizontal).round() as u32).max(CRT_MIN_PIXELS);
+    let h = ((height as f64 * vertical).round() as u32).max(CRT_MIN_PIXELS);
+    let mut rect = Rect::new(0, 0, w, h);
+    rect.center_on(Rect::new(0, 0, width, height).center());
+    rect
+}
+
+/// Alpha of white over the screen in discrete steps, for a fade progress from 0 to 1
+fn palette_fade_alpha(progress: f64) -> u8 {
+    let step = (progress.clamp(0.0, 1.0) * PALETTE_FADE_STEPS).floor();
+    (255.0 * step / PALETTE_FADE_STEPS) as u8
+}
+
+/// Pseudo random but stable threshold in [0, 1) for each dissolve block
+fn dissolve_threshold(index: u32) -> f64 {
+    let mut x = index.wrapping_mul(0x9e3779b9);
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x85ebca6b);
+    x ^= x >> 13;
+    x as f64 / u32::MAX as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wipe() {
+        assert_eq!(wipe_rect(100, 50, 0.0), Some(Rect::new(0, 0, 100, 50)));
+        assert_eq!(wipe_rect(100, 50, 0.25), Some(Rect::new(25, 0, 75, 50)));
+        assert_eq!(wipe_rect(100, 50, 1.0), None);
+    }
+
+    #[test]
+    fn blinds() {
+        let rects = blind_rects(100, 120, 0.5);
+        assert_eq!(rects.len(), BLINDS as usize);
+        assert_eq!(rects[0], Rect::new(0, 5, 100, 5));
+        assert_eq!(rects[1], Rect::new(0, 15, 100, 5));
+        assert!(blind_rects(100, 120, 1.0).is_empty());
+    }
+
+    #[test]
+    fn crt() {
+        assert_eq!(crt_rect(100, 50, 1.0), Rect::new(0, 0, 100, 50));
+        assert_eq!(crt_rect(100, 50, 0.2), Rect::new(0, 24, 100, 2));
+        assert_eq!(crt_rect(100, 50, 0.0), Rect::new(49, 24, 2, 2));
+    }
+
+    #[test]
+    fn palette_fade_steps() {
+        assert_eq!(palette_fade_alpha(0.0), 0);
+        assert_eq!(palette_fade_alpha(0.3), 63);
+        assert_eq!(palette_fade_alpha(0.99), 191);
+        assert_eq!(palette_fade_alpha(1.0), 255);
+    }
+
+    #[test]
+    fn dissolve_thresholds_are_spread() {
+        let thresholds: Vec<f64> = (0..1000).map(dissolve_threshold).collect();
+        assert!(thresholds.iter().all(|t| (0.0..=1.0).contains(t)));
+        let below_half = thresholds.iter().filter(|t| **t < 0.5).count();
+        assert!((400..600).contains(&below_half), "{}", below_half);
+    }
+}
@@ -0,0 +1,220 @@
+use crate::config::{GameConfig, MatchRules, VideoMode};
+use crate::game::ruleset::Ruleset;
+use crate::game::{MAX_B_TYPE_HEIGHT, MAX_START_LEVEL};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+pub const USAGE: &str = "usage: rustris [options]
+
+options:
+  -h, --help                 print this help & exit
+  -V, --version              print the version & exit
+      --check-config         validate the config file & exit, non zero if it has errors
+      --config <path>        config file to use, high scores & themes are kept next to it
+      --window <WxH>         play in a window of this size
+      --fullscreen <WxH>     play fullscreen at this resolution
+      --fullscreen-desktop   play fullscreen at the desktop resolution
+
+match options, any of these skip the menu & quit after the match:
+      --start                start a match with the defaults
+      --players <n>          number of players
+      --mode <mode>          battle, marathon, b-type[=lines], line-sprint[=lines] or score-sprint[=score]
+      --rules <rules>        guideline or classic
+      --level <n>            starting level, 0 to 19
+      --height <n>           starting garbage height of b-type, 0 to 5
+      --theme <name>         all, a built in theme or the name of a theme pack
+      --seed <n>             seed of the tetrominoes & garbage, the same seed deals the same match";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Command {
+    Run,
+    Help,
+    Version,
+    CheckConfig,
+}
+
+/// A match to start straight away, skipping the menu
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StartMatch {
+    pub game_config: GameConfig,
+    /// name of the themes to play, resolved once the theme packs are discovered
+    pub theme: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cli {
+    pub command: Command,
+    pub config_path: Option<PathBuf>,
+    pub video_mode: Option<VideoMode>,
+    pub start: Option<StartMatch>,
+}
+
+impl Cli {
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self, String> {
+        let mut result = Self {
+            command: Command::Run,
+            config_path: None,
+            video_mode: None,
+            start: None,
+        };
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+            match arg.as_str() {
+                "-h" | "--help" => result.command = Command::Help,
+                "-V" | "--version" => result.command = Command::Version,
+                "--check-config" => result.command = Command::CheckConfig,
+                "--config" => result.config_path = Some(PathBuf::from(value()?)),
+                "--window" => {
+                    let (width, height) = parse_size(&value()?)?;
+                    result.video_mode = Some(VideoMode::Window { width, height });
+                }
+                "--fullscreen" => {
+                    let (width, height) = parse_size(&value()?)?;
+                    result.video_mode = Some(VideoMode::FullScreen { width, height });
+                }
+                "--fullscreen-desktop" => result.video_mode = Some(VideoMode::FullScreenDesktop),
+                "--start" => {
+                    result.start_match();
+                }
+                "--players" => {
+                    let players = parse_number(&arg, &value()?, 1, u32::MAX)?;
+                    result.start_match().game_config.players = players;
+                }
+                "--mode" => {
+                    let rules = parse_mode(&value()?)?;
+                    result.start_match().game_config.rules = rules;
+                }
+                "--rules" => {
+                    let rules = value()?;
+                    let ruleset = Ruleset::from_str(&rules).map_err(|_| {
+                        format!("unknown rules {}, expected {}", rules, Ruleset::names().join(" or "))
+                    })?;
+                    result.start_match().game_config.ruleset = ruleset;
+                }
+                "--level" => {
+                    let level = parse_number(&arg, &value()?, 0, MAX_START_LEVEL)?;
+                    result.start_match().game_config.level = level;
+                }
+                "--height" => {
+                    let height = parse_number(&arg, &value()?, 0, MAX_B_TYPE_HEIGHT)?;
+                    result.start_match().game_config.height = height;
+                }
+                "--theme" => result.start_match().theme = Some(value()?),
+                "--seed" => {
+                    let seed = value()?;
+                    let seed = seed
+                        .parse::<u64>()
+                        .map_err(|_| format!("--seed must be a positive number, not {}", seed))?;
+                    result.start_match().game_config.seed = Some(seed);
+                }
+                _ => return Err(format!("unknown option {}\n\n{}", arg, USAGE)),
+            }
+        }
+        Ok(result)
+    }
+
+    fn start_match(&mut self) -> &mut StartMatch {
+        self.start.get_or_insert_with(|| StartMatch {
+            game_config: GameConfig::default(),
+            theme: None,
+        })
+    }
+}
+
+/// e.g. `1280x720`
+fn parse_size(value: &str) -> Result<(u32, u32), String> {
+    let invalid = || format!("size must be like 1280x720, not {}", value);
+    let (width, height) = value.split_once('x').ok_or_else(invalid)?;
+    match (width.parse::<u32>(), height.parse::<u32>()) {
+        (Ok(width), Ok(height)) if width > 0 && height > 0 => Ok((width, height)),
+        _ => Err(invalid()),
+    }
+}
+
+fn parse_number(option: &str, value: &str, min: u32, max: u32) -> Result<u32, String> {
+    match value.parse::<u32>() {
+        Ok(number) if (min..=max).contains(&number) => Ok(number),
+        _ if max == u32::MAX => Err(format!("{} must be at least {}, not {}", option, min, value)),
+        _ => Err(format!("{} must be from {} to {}, not {}", option, min, max, value)),
+    }
+}
+
+/// A mode name with an optional target, e.g. `line-sprint=20`
+fn parse_mode(value: &str) -> Result<MatchRules, String> {
+    let (name, target) = match value.split_once('=') {
+        Some((name, target)) => {
+            let target = target
+                .parse::<u32>()
+                .map_err(|_| format!("mode target must be a number, not {}", target))?;
+            (name, Some(target))
+        }
+        None => (value, None),
+    };
+    let rules = match (name, target) {
+        ("battle", None) => MatchRules::Battle,
+        ("marathon", None) => MatchRules::Marathon,
+        ("b-type", lines) => lines
+            .map(|lines| MatchRules::BType { lines })
+            .unwrap_or(MatchRules::DEFAULT_B_TYPE),
+        ("line-sprint", lines) => lines
+            .map(|lines| MatchRules::LineSprint { lines })
+            .unwrap_or(MatchRules::DEFAULT_LINE_SPRINT),
+        ("score-sprint", score) => score
+            .map(|score| MatchRules::ScoreSprint { score })
+            .unwrap_or(MatchRules::DEFAULT_SCORE_SPRINT),
+        ("battle" | "marathon", Some(_)) => {
+            return Err(format!("{} does not take a target", name))
+        }
+        _ => return Err(format!("unknown mode {}", value)),
+    };
+    Ok(rules)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Cli, String> {
+        Cli::parse(args.iter().map(|s| s.to_string()))
+    }
+
+    #[test]
+    fn no_args_runs_the_menu() {
+        let cli = parse(&[]).unwrap();
+        assert_eq!(cli.command, Command::Run);
+        assert_eq!(cli.start, None);
+        assert_eq!(cli.video_mode, None);
+    }
+
+    #[test]
+    fn match_options_start_a_match() {
+        let cli = parse(&[
+            "--players", "2", "--mode", "line-sprint=20", "--level", "5", "--theme", "nes",
+            "--seed", "42",
+        ])
+        .unwrap();
+        let start = cli.start.unwrap();
+        assert_eq!(start.game_config.players, 2);
+        assert_eq!(start.game_config.rules, MatchRules::LineSprint { lines: 20 });
+        assert_eq!(start.game_config.level, 5);
+        assert_eq!(start.game_config.seed, Some(42));
+        assert_eq!(start.theme, Some("nes".to_string()));
+    }
+
+    #[test]
+    fn video_mode_overrides() {
+        let cli = parse(&["--window", "800x600"]).unwrap();
+        assert_eq!(cli.video_mode, Some(VideoMode::Window { width: 800, height: 600 }));
+        assert!(parse(&["--fullscreen", "800"]).is_err());
+        assert!(parse(&["--window", "0x600"]).is_err());
+    }
+
+    #[test]
+    fn invalid_args_are_errors() {
+        assert!(parse(&["--level"]).unwrap_err().starts_with("--level needs a value"));
+        assert!(parse(&["--level", "20"]).is_err());
+        assert!(parse(&["--mode", "battle=5"]).is_err());
+        assert!(parse(&["--nope"]).unwrap_err().starts_with("unknown option --nope"));
+    }
+}
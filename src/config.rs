@@ -1,13 +1,18 @@
 use num_format::{Locale, ToFormattedString};
+use rand::seq::SliceRandom;
+use rand::thread_rng;
 use crate::game::random::RandomMode;
+use crate::game::ruleset::Ruleset;
 use crate::game_input::GameInputKey;
 use crate::menu_input::MenuInputKey;
-use sdl2::keyboard::Keycode;
+use sdl2::controller::{Axis, Button};
+use sdl2::keyboard::{Keycode, Scancode};
 use sdl2::mixer::MAX_VOLUME;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
-use confy::ConfyError;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use crate::migration::{LoadError, CONFIG_SCHEMA};
 use sdl2::sys;
 use strum::IntoEnumIterator;
 
@@ -18,105 +23,482 @@ pub enum VideoMode {
     FullScreenDesktop,
 }
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+impl VideoMode {
+    pub fn name(&self) -> String {
+        match self {
+            VideoMode::Window { width, height } => format!("window {}x{}", width, height),
+            VideoMode::FullScreen { width, height } => format!("fullscreen {}x{}", width, height),
+            VideoMode::FullScreenDesktop => "fullscreen desktop".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Config {
     pub video: VideoConfig,
     pub audio: AudioConfig,
     pub input: InputConfig,
     pub game: GameplayConfig,
+    #[serde(default)]
+    pub playlist: PlaylistConfig,
 }
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct MenuInputConfig {
-    pub up: GameKey,
-    pub down: GameKey,
-    pub left: GameKey,
-    pub right: GameKey,
-    pub select: GameKey,
-    pub start: GameKey,
+    pub up: KeyBindings,
+    pub down: KeyBindings,
+    pub left: KeyBindings,
+    pub right: KeyBindings,
+    pub select: KeyBindings,
+    pub start: KeyBindings,
 }
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct GameInputConfig {
-    pub move_left: GameKey,
-    pub move_right: GameKey,
-    pub soft_drop: GameKey,
-    pub hard_drop: GameKey,
-    pub rotate_clockwise: GameKey,
-    pub rotate_anticlockwise: GameKey,
-    pub hold: GameKey,
+    pub move_left: KeyBindings,
+    pub move_right: KeyBindings,
+    pub soft_drop: KeyBindings,
+    pub hard_drop: KeyBindings,
+    pub rotate_clockwise: KeyBindings,
+    pub rotate_anticlockwise: KeyBindings,
+    pub hold: KeyBindings,
+    /// cycle the theme of this player only
+    #[serde(default)]
+    pub next_theme: KeyBindings,
+}
+
+impl GameInputConfig {
+    fn bindings(&self, player: u32) -> Vec<(&KeyBindings, GameInputKey)> {
+        vec![
+            (&self.move_left, GameInputKey::MoveLeft { player }),
+            (&self.move_right, GameInputKey::MoveRight { player }),
+            (&self.soft_drop, GameInputKey::SoftDrop { player }),
+            (&self.hard_drop, GameInputKey::HardDrop { player }),
+            (&self.rotate_clockwise, GameInputKey::RotateClockwise { player }),
+            (&self.rotate_anticlockwise, GameInputKey::RotateAnticlockwise { player }),
+            (&self.hold, GameInputKey::Hold { player }),
+            (&self.next_theme, GameInputKey::NextPlayerTheme { player }),
+        ]
+    }
+}
+
+/// A key bound to an action, either the logical key which follows the keyboard layout
+/// or the physical key position which is the same on any layout e.g. `{ scancode: Z }` is `W` on AZERTY
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize)]
+#[serde(untagged)]
+pub enum KeyBinding {
+    Scancode { scancode: ScanKey },
+    Keycode(GameKey),
+}
+
+/// Key names are looked up after parsing so that an unknown name is reported as such
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawKeyBinding {
+    Scancode { scancode: String },
+    Keycode(String),
+}
+
+impl TryFrom<RawKeyBinding> for KeyBinding {
+    type Error = String;
+
+    fn try_from(value: RawKeyBinding) -> Result<Self, Self::Error> {
+        match value {
+            RawKeyBinding::Scancode { scancode } => ScanKey::iter()
+                .find(|key| format!("{:?}", key) == scancode)
+                .map(|scancode| KeyBinding::Scancode { scancode })
+                .ok_or_else(|| format!("unknown scancode name `{}`", scancode)),
+            RawKeyBinding::Keycode(name) => GameKey::iter()
+                .find(|key| format!("{:?}", key) == name)
+                .map(KeyBinding::Keycode)
+                .ok_or_else(|| format!("unknown key name `{}`", name)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for KeyBinding {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        RawKeyBinding::deserialize(deserializer)?
+            .try_into()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl std::fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeyBinding::Scancode { scancode } => write!(f, "scancode {:?}", scancode),
+            KeyBinding::Keycode(key) => write!(f, "{:?}", key),
+        }
+    }
+}
+
+/// A key as sent by sdl, matched against both logical & physical bindings
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum BoundKey {
+    Keycode(Keycode),
+    Scancode(Scancode),
+}
+
+impl BoundKey {
+    /// Physical bindings are checked first as they are the more specific
+    pub fn from_event(keycode: Option<Keycode>, scancode: Option<Scancode>) -> Vec<BoundKey> {
+        scancode
+            .map(BoundKey::Scancode)
+            .into_iter()
+            .chain(keycode.map(BoundKey::Keycode))
+            .collect()
+    }
+}
+
+impl From<KeyBinding> for BoundKey {
+    fn from(value: KeyBinding) -> Self {
+        match value {
+            KeyBinding::Scancode { scancode } => BoundKey::Scancode(scancode.into()),
+            KeyBinding::Keycode(key) => BoundKey::Keycode(key.into()),
+        }
+    }
+}
+
+impl KeyBinding {
+    /// The physical key of the binding, keycodes are resolved as on a US layout which is what sdl assumes before
+    /// it's running, keycodes without a physical key are kept as they are
+    fn physical(self) -> BoundKey {
+        match self {
+            KeyBinding::Keycode(key) => key
+                .default_scancode()
+                .map(BoundKey::Scancode)
+                .unwrap_or(BoundKey::Keycode(key.into())),
+            scancode => scancode.into(),
+        }
+    }
+}
+
+/// Any number of bindings for one action, a single binding or none at all (`~`) are also accepted
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct KeyBindings(pub Vec<KeyBinding>);
+
+impl KeyBindings {
+    pub fn key(key: GameKey) -> Self {
+        Self(vec![KeyBinding::Keycode(key)])
+    }
+}
+
+impl Serialize for KeyBindings {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0.as_slice() {
+            [binding] => binding.serialize(serializer),
+            bindings => bindings.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for KeyBindings {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum OneOrMany {
+            One(RawKeyBinding),
+            Many(Vec<RawKeyBinding>),
+        }
+        let raw = match Option::<OneOrMany>::deserialize(deserializer)? {
+            None => vec![],
+            Some(OneOrMany::One(binding)) => vec![binding],
+            Some(OneOrMany::Many(bindings)) => bindings,
+        };
+        raw.into_iter()
+            .map(KeyBinding::try_from)
+            .collect::<Result<Vec<_>, _>>()
+            .map(Self)
+            .map_err(serde::de::Error::custom)
+    }
 }
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct InputConfig {
     pub menu: MenuInputConfig,
     pub player1: GameInputConfig,
     pub player2: Option<GameInputConfig>,
-    pub pause: GameKey,
-    pub quit: GameKey,
-    pub next_theme: GameKey,
+    pub pause: KeyBindings,
+    pub quit: KeyBindings,
+    pub next_theme: KeyBindings,
+    /// restart the match straight away with a new seed, e.g. to practice sprints
+    #[serde(default)]
+    pub retry: KeyBindings,
+    #[serde(default)]
+    pub gamepad: GamepadConfig,
+}
+
+/// Game controller bindings, each action can be bound to any number of buttons & axes
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GamepadConfig {
+    /// how far a stick or trigger must move to press its direction, from 0 to 1
+    pub deadzone: f64,
+    pub menu: GamepadMenuConfig,
+    /// bindings of the first connected controller
+    pub player1: GamepadPlayerConfig,
+    /// bindings of the second connected controller
+    pub player2: GamepadPlayerConfig,
+    pub pause: Vec<PadInput>,
+    pub quit: Vec<PadInput>,
+    pub next_theme: Vec<PadInput>,
+    pub retry: Vec<PadInput>,
+}
+
+impl Default for GamepadConfig {
+    fn default() -> Self {
+        Self {
+            deadzone: 0.5,
+            menu: GamepadMenuConfig::default(),
+            player1: GamepadPlayerConfig::default(),
+            player2: GamepadPlayerConfig::default(),
+            pause: vec![PadInput::Button(PadButton::Start)],
+            quit: vec![PadInput::Button(PadButton::Back)],
+            next_theme: vec![],
+            retry: vec![],
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GamepadMenuConfig {
+    pub up: Vec<PadInput>,
+    pub down: Vec<PadInput>,
+    pub left: Vec<PadInput>,
+    pub right: Vec<PadInput>,
+    pub select: Vec<PadInput>,
+    pub start: Vec<PadInput>,
+}
+
+impl Default for GamepadMenuConfig {
+    fn default() -> Self {
+        Self {
+            up: vec![PadInput::Button(PadButton::DPadUp), PadInput::AxisNegative(PadAxis::LeftY)],
+            down: vec![PadInput::Button(PadButton::DPadDown), PadInput::AxisPositive(PadAxis::LeftY)],
+            left: vec![PadInput::Button(PadButton::DPadLeft), PadInput::AxisNegative(PadAxis::LeftX)],
+            right: vec![PadInput::Button(PadButton::DPadRight), PadInput::AxisPositive(PadAxis::LeftX)],
+            select: vec![PadInput::Button(PadButton::A)],
+            start: vec![PadInput::Button(PadButton::Start)],
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GamepadPlayerConfig {
+    pub move_left: Vec<PadInput>,
+    pub move_right: Vec<PadInput>,
+    pub soft_drop: Vec<PadInput>,
+    pub hard_drop: Vec<PadInput>,
+    pub rotate_clockwise: Vec<PadInput>,
+    pub rotate_anticlockwise: Vec<PadInput>,
+    pub hold: Vec<PadInput>,
+    /// cycle the theme of this player only
+    pub next_theme: Vec<PadInput>,
+}
+
+impl Default for GamepadPlayerConfig {
+    fn default() -> Self {
+        Self {
+            move_left: vec![PadInput::Button(PadButton::DPadLeft), PadInput::AxisNegative(PadAxis::LeftX)],
+            move_right: vec![PadInput::Button(PadButton::DPadRight), PadInput::AxisPositive(PadAxis::LeftX)],
+            soft_drop: vec![PadInput::Button(PadButton::DPadDown), PadInput::AxisPositive(PadAxis::LeftY)],
+            hard_drop: vec![PadInput::Button(PadButton::DPadUp), PadInput::AxisNegative(PadAxis::LeftY)],
+            rotate_clockwise: vec![PadInput::Button(PadButton::A)],
+            rotate_anticlockwise: vec![PadInput::Button(PadButton::B)],
+            hold: vec![
+                PadInput::Button(PadButton::LeftShoulder),
+                PadInput::Button(PadButton::RightShoulder),
+            ],
+            next_theme: vec![],
+        }
+    }
+}
+
+impl GamepadPlayerConfig {
+    fn bindings(&self, player: u32) -> Vec<(&Vec<PadInput>, GameInputKey)> {
+        vec![
+            (&self.move_left, GameInputKey::MoveLeft { player }),
+            (&self.move_right, GameInputKey::MoveRight { player }),
+            (&self.soft_drop, GameInputKey::SoftDrop { player }),
+            (&self.hard_drop, GameInputKey::HardDrop { player }),
+            (&self.rotate_clockwise, GameInputKey::RotateClockwise { player }),
+            (&self.rotate_anticlockwise, GameInputKey::RotateAnticlockwise { player }),
+            (&self.hold, GameInputKey::Hold { player }),
+            (&self.next_theme, GameInputKey::NextPlayerTheme { player }),
+        ]
+    }
 }
 
 impl InputConfig {
-    pub fn menu_map(&self) -> HashMap<Keycode, MenuInputKey> {
-        HashMap::from([
-            (self.menu.up.into(), MenuInputKey::Up),
-            (self.menu.down.into(), MenuInputKey::Down),
-            (self.menu.left.into(), MenuInputKey::Left),
-            (self.menu.right.into(), MenuInputKey::Right),
-            (self.menu.start.into(), MenuInputKey::Start),
-            (self.menu.select.into(), MenuInputKey::Select),
-            (self.quit.into(), MenuInputKey::Quit),
-        ])
-    }
-
-    pub fn game_map(&self) -> HashMap<Keycode, GameInputKey> {
-        let mut result = HashMap::from([
-            (self.quit.into(), GameInputKey::ReturnToMenu),
-            (self.pause.into(), GameInputKey::Pause),
-            (self.next_theme.into(), GameInputKey::NextTheme),
-            (self.player1.move_left.into(), GameInputKey::MoveLeft { player: 1 }),
-            (
-                self.player1.move_right.into(),
-                GameInputKey::MoveRight { player: 1 },
-            ),
-            (self.player1.soft_drop.into(), GameInputKey::SoftDrop { player: 1 }),
-            (self.player1.hard_drop.into(), GameInputKey::HardDrop { player: 1 }),
-            (
-                self.player1.rotate_anticlockwise.into(),
-                GameInputKey::RotateAnticlockwise { player: 1 },
-            ),
-            (
-                self.player1.rotate_clockwise.into(),
-                GameInputKey::RotateClockwise { player: 1 },
-            ),
-            (self.player1.hold.into(), GameInputKey::Hold { player: 1 }),
-        ]);
-
-        match self.player2 {
-            None => {}
-            Some(p2) => {
-                result.insert(p2.move_left.into(), GameInputKey::MoveLeft { player: 2 });
-                result.insert(p2.move_right.into(), GameInputKey::MoveRight { player: 2 });
-                result.insert(p2.soft_drop.into(), GameInputKey::SoftDrop { player: 2 });
-                result.insert(p2.hard_drop.into(), GameInputKey::HardDrop { player: 2 });
-                result.insert(
-                    p2.rotate_anticlockwise.into(),
-                    GameInputKey::RotateAnticlockwise { player: 2 },
-                );
-                result.insert(
-                    p2.rotate_clockwise.into(),
-                    GameInputKey::RotateClockwise { player: 2 },
-                );
-                result.insert(p2.hold.into(), GameInputKey::Hold { player: 2 });
+    pub fn menu_map(&self) -> HashMap<BoundKey, MenuInputKey> {
+        [
+            (&self.menu.up, MenuInputKey::Up),
+            (&self.menu.down, MenuInputKey::Down),
+            (&self.menu.left, MenuInputKey::Left),
+            (&self.menu.right, MenuInputKey::Right),
+            (&self.menu.start, MenuInputKey::Start),
+            (&self.menu.select, MenuInputKey::Select),
+            (&self.quit, MenuInputKey::Quit),
+        ]
+        .into_iter()
+        .flat_map(|(bindings, key)| bindings.0.iter().map(move |binding| ((*binding).into(), key)))
+        .collect()
+    }
+
+    pub fn gamepad_menu_map(&self) -> HashMap<PadInput, MenuInputKey> {
+        let menu = &self.gamepad.menu;
+        [
+            (&menu.up, MenuInputKey::Up),
+            (&menu.down, MenuInputKey::Down),
+            (&menu.left, MenuInputKey::Left),
+            (&menu.right, MenuInputKey::Right),
+            (&menu.start, MenuInputKey::Start),
+            (&menu.select, MenuInputKey::Select),
+        ]
+        .into_iter()
+        .flat_map(|(inputs, key)| inputs.iter().map(move |input| (*input, key)))
+        .collect()
+    }
+
+    /// Game keys by the player slot of the controller & the input
+    pub fn gamepad_game_map(&self) -> HashMap<(u32, PadInput), GameInputKey> {
+        let gamepad = &self.gamepad;
+        let mut result = HashMap::new();
+        for (player, player_config) in [(1, &gamepad.player1), (2, &gamepad.player2)] {
+            let mut bindings = vec![
+                (&gamepad.quit, GameInputKey::ReturnToMenu),
+                (&gamepad.pause, GameInputKey::Pause),
+                (&gamepad.next_theme, GameInputKey::NextTheme),
+                (&gamepad.retry, GameInputKey::Retry),
+            ];
+            bindings.extend(player_config.bindings(player));
+            for (inputs, key) in bindings {
+                for input in inputs {
+                    result.insert((player, *input), key);
+                }
             }
         }
-
         result
     }
+
+    /// Errors when a key is bound to more than one action e.g. by both players
+    pub fn game_map(&self) -> Result<HashMap<BoundKey, GameInputKey>, String> {
+        let (result, conflicts) = self.game_bindings();
+        match conflicts.first() {
+            None => Ok(result),
+            Some((field, reason)) => Err(format!("{}: {}", field, reason)),
+        }
+    }
+
+    fn game_bindings(&self) -> (HashMap<BoundKey, GameInputKey>, Vec<(String, String)>) {
+        let mut bindings = vec![
+            (&self.quit, GameInputKey::ReturnToMenu),
+            (&self.pause, GameInputKey::Pause),
+            (&self.next_theme, GameInputKey::NextTheme),
+            (&self.retry, GameInputKey::Retry),
+        ];
+        bindings.extend(self.player1.bindings(1));
+        if let Some(player2) = &self.player2 {
+            bindings.extend(player2.bindings(2));
+        }
+
+        map_bindings(bindings, describe_game_input)
+    }
+
+    /// Every key bound to more than one action in the menus or during a game, as the field of the later action & why
+    pub fn check_bindings(&self) -> Vec<(String, String)> {
+        let (_, mut conflicts) = self.game_bindings();
+        let menu = &self.menu;
+        let (_, menu_conflicts) = map_bindings(
+            vec![
+                (&menu.up, MenuInputKey::Up),
+                (&menu.down, MenuInputKey::Down),
+                (&menu.left, MenuInputKey::Left),
+                (&menu.right, MenuInputKey::Right),
+                (&menu.start, MenuInputKey::Start),
+                (&menu.select, MenuInputKey::Select),
+                (&self.quit, MenuInputKey::Quit),
+            ],
+            describe_menu_input,
+        );
+        conflicts.extend(menu_conflicts);
+        conflicts
+    }
+}
+
+/// Maps every bound key to its action, along with each key bound to more than one action
+fn map_bindings<K: Copy + Eq>(
+    bindings: Vec<(&KeyBindings, K)>,
+    describe: fn(K) -> String,
+) -> (HashMap<BoundKey, K>, Vec<(String, String)>) {
+    let mut result = HashMap::new();
+    let mut conflicts = vec![];
+    // conflicts are found by the physical key so that e.g. `Z` & `{ scancode: Z }` clash
+    let mut bound_by: HashMap<BoundKey, (KeyBinding, K)> = HashMap::new();
+    for (key_bindings, key) in bindings {
+        for binding in key_bindings.0.iter() {
+            match bound_by.get(&binding.physical()) {
+                Some(&(existing_binding, existing)) if existing != key => {
+                    let also = if existing_binding == *binding {
+                        String::new()
+                    } else {
+                        format!(" as {}", existing_binding)
+                    };
+                    conflicts.push((
+                        describe(key),
+                        format!("{} is also bound to {}{}", binding, describe(existing), also),
+                    ));
+                }
+                _ => {
+                    bound_by.entry(binding.physical()).or_insert((*binding, key));
+                    result.insert(BoundKey::from(*binding), key);
+                }
+            }
+        }
+    }
+    (result, conflicts)
+}
+
+/// Config field of the menu action
+fn describe_menu_input(key: MenuInputKey) -> String {
+    let action = match key {
+        MenuInputKey::Up => "up",
+        MenuInputKey::Down => "down",
+        MenuInputKey::Left => "left",
+        MenuInputKey::Right => "right",
+        MenuInputKey::Start => "start",
+        MenuInputKey::Select => "select",
+        MenuInputKey::Quit => return "input.quit".to_string(),
+    };
+    format!("input.menu.{}", action)
+}
+
+/// Config field of the game action
+fn describe_game_input(key: GameInputKey) -> String {
+    let action = match key {
+        GameInputKey::MoveLeft { .. } => "move_left",
+        GameInputKey::MoveRight { .. } => "move_right",
+        GameInputKey::SoftDrop { .. } => "soft_drop",
+        GameInputKey::HardDrop { .. } => "hard_drop",
+        GameInputKey::RotateClockwise { .. } => "rotate_clockwise",
+        GameInputKey::RotateAnticlockwise { .. } => "rotate_anticlockwise",
+        GameInputKey::Hold { .. } => "hold",
+        GameInputKey::NextPlayerTheme { .. } => "next_theme",
+        GameInputKey::Pause => "pause",
+        GameInputKey::ReturnToMenu | GameInputKey::Quit => "quit",
+        GameInputKey::NextTheme => "next_theme",
+        GameInputKey::Retry => "retry",
+    };
+    match key.player() {
+        Some(player) => format!("input.player{}.{}", player, action),
+        None => format!("input.{}", action),
+    }
 }
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct AudioConfig {
     pub music_volume: f64,
     pub effects_volume: f64,
@@ -132,12 +514,165 @@ impl AudioConfig {
     }
 }
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct VideoConfig {
     pub mode: VideoMode,
     pub vsync: bool,
     pub disable_screensaver: bool,
-    pub integer_scale: bool
+    pub integer_scale: bool,
+    #[serde(default)]
+    pub game_boy_palette: GameBoyPalette,
+    /// Name of a block skin png in the skins directory next to the config, used by the modern theme
+    #[serde(default)]
+    pub modern_skin: Option<String>,
+    #[serde(default)]
+    pub theme_transition: TransitionConfig,
+    /// CRT filter of each theme by menu name, themes without a filter are drawn as is
+    #[serde(default)]
+    pub crt_filters: HashMap<String, CrtConfig>,
+    #[serde(default)]
+    pub accessibility: AccessibilityConfig,
+    #[serde(default)]
+    pub ui: UiConfig,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiConfig {
+    /// text size of the menus, pause & high score screens relative to the window width
+    pub scale: f64,
+    /// opaque menus with bold colours
+    pub high_contrast: bool,
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            high_contrast: false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AccessibilityConfig {
+    /// draw a different glyph on the minos of each shape in every theme
+    pub mino_glyphs: bool,
+    /// colour blind friendly piece colours for the modern theme
+    pub modern_palette: ColorBlindPalette,
+    /// no shaking, smearing or flashing & fewer particles
+    pub reduced_motion: bool,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorBlindPalette {
+    #[default]
+    Default,
+    Deuteranopia,
+    Protanopia,
+    Tritanopia,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CrtConfig {
+    /// darkness of the gap between scanlines from 0 (off) to 1 (black)
+    pub scanlines: f64,
+    pub mask: CrtMask,
+    /// darkness of the other colours in each phosphor of the mask from 0 (off) to 1
+    pub mask_strength: f64,
+    /// how much bright pixels bleed into their neighbours from 0 (off) to 1
+    pub bloom: f64,
+    /// barrel distortion from 0 (flat) to around 0.2 (very curved)
+    pub curvature: f64,
+}
+
+impl Default for CrtConfig {
+    fn default() -> Self {
+        Self {
+            scanlines: 0.4,
+            mask: CrtMask::ApertureGrille,
+            mask_strength: 0.2,
+            bloom: 0.3,
+            curvature: 0.04,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CrtMask {
+    None,
+    /// vertical stripes of red, green & blue phosphors
+    #[default]
+    ApertureGrille,
+    /// staggered triads of red, green & blue phosphors
+    ShadowMask,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransitionConfig {
+    /// transition used when switching themes, none to use the transition of the next theme
+    pub transition: Option<ThemeTransition>,
+    pub duration_ms: u64,
+}
+
+impl Default for TransitionConfig {
+    fn default() -> Self {
+        Self {
+            transition: None,
+            duration_ms: 1000,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, strum::EnumIter)]
+#[serde(rename_all = "snake_case")]
+pub enum ThemeTransition {
+    /// Alpha fade from the previous theme
+    #[default]
+    Fade,
+    /// Horizontal wipe from left to right
+    Wipe,
+    /// The previous theme dissolves away in random blocks of pixels
+    Dissolve,
+    /// Horizontal blinds closing on the previous theme
+    Blinds,
+    /// CRT power off then back on again
+    Crt,
+    /// Game Boy style fade to white through the palette & back again
+    PaletteFade,
+    /// Any of the above
+    Random,
+}
+
+impl ThemeTransition {
+    /// Pick a random transition if random
+    pub fn resolve(self) -> Self {
+        match self {
+            ThemeTransition::Random => *Self::iter()
+                .filter(|t| *t != ThemeTransition::Random)
+                .collect::<Vec<Self>>()
+                .choose(&mut thread_rng())
+                .unwrap(),
+            transition => transition,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameBoyPalette {
+    /// Plain greys as per the original assets
+    #[default]
+    Default,
+    /// Green tinted original Game Boy (DMG) screen
+    DmgGreen,
+    /// Game Boy Pocket grey screen
+    PocketGrey,
+    /// Game Boy Light backlit screen
+    Light,
 }
 
 impl VideoConfig {
@@ -151,7 +686,59 @@ impl VideoConfig {
     }
 }
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+/// How the "all" themes mode plays through the themes
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlaylistConfig {
+    /// theme names in the order they're played, leave empty to play every theme
+    pub themes: Vec<String>,
+    pub shuffle: bool,
+    pub switch: ThemeSwitch,
+}
+
+impl Default for PlaylistConfig {
+    fn default() -> Self {
+        Self {
+            themes: vec![],
+            shuffle: false,
+            switch: ThemeSwitch::Levels(1),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ThemeSwitch {
+    Levels(u32),
+    Lines(u32),
+    Minutes(u32),
+}
+
+impl ThemeSwitch {
+    pub const DEFAULT_SWITCHES: [Self; 10] = [
+        Self::Levels(1),
+        Self::Levels(2),
+        Self::Levels(3),
+        Self::Levels(5),
+        Self::Lines(10),
+        Self::Lines(20),
+        Self::Lines(40),
+        Self::Minutes(1),
+        Self::Minutes(2),
+        Self::Minutes(5),
+    ];
+
+    pub fn name(&self) -> String {
+        match self {
+            ThemeSwitch::Levels(1) => "every level".to_string(),
+            ThemeSwitch::Levels(levels) => format!("every {} levels", levels),
+            ThemeSwitch::Lines(1) => "every line".to_string(),
+            ThemeSwitch::Lines(lines) => format!("every {} lines", lines),
+            ThemeSwitch::Minutes(1) => "every minute".to_string(),
+            ThemeSwitch::Minutes(minutes) => format!("every {} minutes", minutes),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct GameplayConfig {
     pub random_mode: RandomMode,
     pub min_garbage_per_hole: u32,
@@ -176,7 +763,13 @@ impl Default for Config {
 
                 // disable integer scaling to better fill small retro handheld screen
                 // otherwise keep it enabled as it does look better
-                integer_scale: !cfg!(feature = "retro_handheld")
+                integer_scale: !cfg!(feature = "retro_handheld"),
+                game_boy_palette: GameBoyPalette::Default,
+                modern_skin: None,
+                theme_transition: TransitionConfig::default(),
+                crt_filters: HashMap::new(),
+                accessibility: AccessibilityConfig::default(),
+                ui: UiConfig::default(),
             },
             audio: AudioConfig {
                 music_volume: 1.0,
@@ -197,66 +790,210 @@ impl Default for Config {
             */
             input: InputConfig {
                 menu: MenuInputConfig {
-                    up: GameKey::Up,
-                    down: GameKey::Down,
-                    left: GameKey::Left,
-                    right: GameKey::Right,
-                    select: GameKey::X,
-                    start: GameKey::Return,
+                    up: KeyBindings::key(GameKey::Up),
+                    down: KeyBindings::key(GameKey::Down),
+                    left: KeyBindings::key(GameKey::Left),
+                    right: KeyBindings::key(GameKey::Right),
+                    select: KeyBindings::key(GameKey::X),
+                    start: KeyBindings::key(GameKey::Return),
                 },
                 player1: GameInputConfig {
-                    move_left: GameKey::Left,
-                    move_right: GameKey::Right,
-                    soft_drop: GameKey::Down,
-                    hard_drop: GameKey::Up,
-                    rotate_clockwise: GameKey::X,
-                    rotate_anticlockwise: GameKey::Z,
-                    hold: GameKey::LShift,
+                    move_left: KeyBindings::key(GameKey::Left),
+                    move_right: KeyBindings::key(GameKey::Right),
+                    soft_drop: KeyBindings::key(GameKey::Down),
+                    hard_drop: KeyBindings::key(GameKey::Up),
+                    rotate_clockwise: KeyBindings::key(GameKey::X),
+                    rotate_anticlockwise: KeyBindings::key(GameKey::Z),
+                    hold: KeyBindings::key(GameKey::LShift),
+                    next_theme: KeyBindings::default(),
                 },
                 player2: None,
-                #[cfg(feature = "retro_handheld")] pause: GameKey::Return,
-                #[cfg(not(feature = "retro_handheld"))] pause: GameKey::F1,
-                #[cfg(feature = "retro_handheld")] next_theme: GameKey::RShift,
-                #[cfg(not(feature = "retro_handheld"))] next_theme: GameKey::F2,
-                quit: GameKey::Escape,
+                #[cfg(feature = "retro_handheld")] pause: KeyBindings::key(GameKey::Return),
+                #[cfg(not(feature = "retro_handheld"))] pause: KeyBindings::key(GameKey::F1),
+                #[cfg(feature = "retro_handheld")] next_theme: KeyBindings::key(GameKey::RShift),
+                #[cfg(not(feature = "retro_handheld"))] next_theme: KeyBindings::key(GameKey::F2),
+                quit: KeyBindings::key(GameKey::Escape),
+                #[cfg(feature = "retro_handheld")] retry: KeyBindings::default(),
+                #[cfg(not(feature = "retro_handheld"))] retry: KeyBindings::key(GameKey::F5),
+                gamepad: GamepadConfig::default(),
             },
             game: GameplayConfig {
                 random_mode: RandomMode::Bag,
                 min_garbage_per_hole: 10,
             },
+            playlist: PlaylistConfig::default(),
         }
     }
 }
 
-#[cfg(feature = "retro_handheld")]
+static CONFIG_PATH_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Uses another config file, the other files are kept next to it
+pub fn set_config_path(path: PathBuf) -> Result<(), String> {
+    let absolute = std::env::current_dir().map_err(|e| e.to_string())?.join(path);
+    CONFIG_PATH_OVERRIDE
+        .set(absolute)
+        .map_err(|path| format!("config path is already {}", path.display()))
+}
+
 pub fn config_path(name: &str) -> Result<PathBuf, String> {
+    match CONFIG_PATH_OVERRIDE.get() {
+        Some(path) if name == "config" => Ok(path.clone()),
+        Some(path) => Ok(path.with_file_name(format!("{}.yml", name))),
+        None => default_config_path(name),
+    }
+}
+
+#[cfg(feature = "retro_handheld")]
+fn default_config_path(name: &str) -> Result<PathBuf, String> {
     let mut absolute = std::env::current_dir().map_err(|e| e.to_string())?;
     absolute.push(format!("{}.yml", name));
     Ok(absolute)
 }
 
 #[cfg(not(feature = "retro_handheld"))]
-pub fn config_path(name: &str) -> Result<PathBuf, String> {
+fn default_config_path(name: &str) -> Result<PathBuf, String> {
     confy::get_configuration_file_path(crate::build_info::PKG_NAME, name)
         .map_err(|e| e.to_string())
 }
 
+/// Path of a file or directory next to the config file
+pub fn config_sibling_path(name: &str) -> Result<PathBuf, String> {
+    let config_path = config_path("config")?;
+    let dir = config_path
+        .parent()
+        .ok_or_else(|| format!("no parent directory of {}", config_path.display()))?;
+    Ok(dir.join(name))
+}
+
 impl Config {
 
-    pub fn load() -> Result<Self, String> {
+    /// Loads the config file, if it has any problems then the defaults are returned along with the problems.
+    /// A valid config from an older version is upgraded in place.
+    pub fn load() -> Result<(Self, Vec<ConfigError>), String> {
         let config_path = config_path("config")?;
 
         #[cfg(debug_assertions)]
         println!("loading config: {}", config_path.to_str().unwrap());
 
-        match confy::load_path(&config_path) {
-            Ok(config) => Ok(config),
-            Err(ConfyError::BadYamlData(error)) => {
-                println!("Bad config file at {}, {}, loading defaults", config_path.to_str().unwrap(), error);
-                Ok(Self::default())
+        let problems = match CONFIG_SCHEMA.load::<Self>(&config_path) {
+            Ok(loaded) => {
+                let problems = loaded.value.validate();
+                if problems.is_empty() {
+                    CONFIG_SCHEMA.upgrade_file(&config_path, &loaded)?;
+                    return Ok((loaded.value, vec![]));
+                }
+                problems
+            }
+            Err(LoadError::Yaml(error)) => vec![yaml_problem(&error)],
+            Err(LoadError::Io(error)) => return Err(error),
+        };
+        Ok((Self::default(), config_errors(&config_path, problems)))
+    }
+
+    /// Problems with the config file, read only so a missing file is a problem rather than created
+    /// & an older version is not upgraded
+    pub fn check() -> Result<Vec<ConfigError>, String> {
+        let config_path = config_path("config")?;
+        let problems = match std::fs::read_to_string(&config_path) {
+            Ok(yaml) => match CONFIG_SCHEMA.parse::<Self>(&yaml) {
+                Ok(loaded) => loaded.value.validate(),
+                Err(error) => vec![yaml_problem(&error)],
+            },
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                vec![(String::new(), "not found, the defaults are written here the first time the game runs".to_string())]
+            }
+            Err(error) => return Err(format!("{}: {}", config_path.display(), error)),
+        };
+        Ok(config_errors(&config_path, problems))
+    }
+
+    /// Updates the config file, refusing to overwrite a file with problems or from a newer version
+    pub fn update_file<F: FnOnce(&mut Self)>(update: F) -> Result<(), String> {
+        let (mut config, errors) = Self::load()?;
+        if let Some(error) = errors.first() {
+            return Err(format!("not saved, {} has errors", error.path.display()));
+        }
+        update(&mut config);
+        CONFIG_SCHEMA.store(&config_path("config")?, &config)
+    }
+
+    /// Problems that parse but cannot be used, as the field & the reason
+    pub fn validate(&self) -> Vec<(String, String)> {
+        let mut result = vec![];
+        for (field, volume) in [
+            ("audio.music_volume", self.audio.music_volume),
+            ("audio.effects_volume", self.audio.effects_volume),
+        ] {
+            if !(0.0..=1.0).contains(&volume) {
+                result.push((field.to_string(), format!("must be from 0 to 1, not {}", volume)));
+            }
+        }
+        if let VideoMode::Window { width, height } | VideoMode::FullScreen { width, height } =
+            self.video.mode
+        {
+            if width == 0 || height == 0 {
+                result.push((
+                    "video.mode".to_string(),
+                    format!("size must not be zero, not {}x{}", width, height),
+                ));
             }
-            Err(error) => Err(format!("{}", error)),
         }
+        if self.video.ui.scale <= 0.0 {
+            result.push((
+                "video.ui.scale".to_string(),
+                format!("must be greater than 0, not {}", self.video.ui.scale),
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.input.gamepad.deadzone) {
+            result.push((
+                "input.gamepad.deadzone".to_string(),
+                format!("must be from 0 to 1, not {}", self.input.gamepad.deadzone),
+            ));
+        }
+        result.extend(self.input.check_bindings());
+        result
+    }
+}
+
+/// A problem with a config file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConfigError {
+    pub path: PathBuf,
+    /// dotted path of the field, empty if the problem is not with any one field
+    pub field: String,
+    pub reason: String,
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.field.is_empty() {
+            write!(f, "{}: {}", self.path.display(), self.reason)
+        } else {
+            write!(f, "{}: {}: {}", self.path.display(), self.field, self.reason)
+        }
+    }
+}
+
+fn config_errors(path: &Path, problems: Vec<(String, String)>) -> Vec<ConfigError> {
+    problems
+        .into_iter()
+        .map(|(field, reason)| ConfigError {
+            path: path.to_path_buf(),
+            field,
+            reason,
+        })
+        .collect()
+}
+
+/// Serde yaml errors are formatted as `field.path: reason at line n column m`
+fn yaml_problem(error: &serde_yaml::Error) -> (String, String) {
+    let message = error.to_string();
+    match message.split_once(": ") {
+        Some((field, reason)) if !field.contains(char::is_whitespace) => {
+            (field.to_string(), reason.to_string())
+        }
+        _ => (String::new(), message),
     }
 }
 
@@ -270,17 +1007,21 @@ pub enum MatchRules {
     LineSprint { lines: u32 },
     /// Endless game
     Marathon,
+    /// Game Boy style B-type, start with a garbage stack and clear some number of lines
+    BType { lines: u32 },
 }
 
 impl MatchRules {
     pub const DEFAULT_LINE_SPRINT: Self = Self::LineSprint { lines: 40 };
     pub const DEFAULT_SCORE_SPRINT: Self = Self::ScoreSprint { score: 10_000 };
+    pub const DEFAULT_B_TYPE: Self = Self::BType { lines: 25 };
 
-    pub const DEFAULT_MODES: [Self; 4] = [
+    pub const DEFAULT_MODES: [Self; 5] = [
         Self::Battle,
         Self::DEFAULT_LINE_SPRINT,
         Self::DEFAULT_SCORE_SPRINT,
-        Self::Marathon
+        Self::Marathon,
+        Self::DEFAULT_B_TYPE,
     ];
 
     pub fn garbage_enabled(&self) -> bool {
@@ -292,9 +1033,14 @@ impl MatchRules {
             MatchRules::Battle => "battle".to_string(),
             MatchRules::ScoreSprint { score } => format!("{} point sprint", score.to_formatted_string(&Locale::en)),
             MatchRules::LineSprint { lines } => format!("{} line sprint", lines.to_formatted_string(&Locale::en)),
-            MatchRules::Marathon => "marathon".to_string()
+            MatchRules::Marathon => "marathon".to_string(),
+            MatchRules::BType { .. } => "b-type".to_string(),
         }
     }
+
+    pub fn is_b_type(&self) -> bool {
+        matches!(self, MatchRules::BType { .. })
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, strum::IntoStaticStr, strum::EnumIter, strum::EnumString)]
@@ -304,12 +1050,17 @@ pub enum MatchThemes {
     All,
     #[strum(serialize = "gameboy")]
     GameBoy,
+    #[strum(serialize = "gameboycolor")]
+    GameBoyColor,
     #[strum(serialize = "nes")]
     Nes,
     #[strum(serialize = "snes")]
     Snes,
     #[strum(serialize = "modern")]
     Modern,
+    /// Theme pack loaded from disk, by index of discovery
+    #[strum(disabled)]
+    External(usize),
 }
 
 impl MatchThemes {
@@ -317,7 +1068,21 @@ impl MatchThemes {
         Self::iter().map(|e| e.into()).collect()
     }
     pub fn count() -> usize {
-        Self::iter().filter(|i| *i as usize > 0).count()
+        Self::iter().filter(|i| *i != Self::All).count()
+    }
+
+    /// Index of the theme in the built in themes followed by the theme packs
+    pub fn index(&self) -> usize {
+        match self {
+            Self::External(id) => Self::iter().count() + id,
+            theme => Self::iter().position(|t| t == *theme).unwrap(),
+        }
+    }
+
+    pub fn from_index(index: usize) -> Self {
+        Self::iter()
+            .nth(index)
+            .unwrap_or_else(|| Self::External(index - Self::iter().count()))
     }
 }
 
@@ -327,22 +1092,47 @@ pub struct GameConfig {
     pub level: u32,
     pub rules: MatchRules,
     pub themes: MatchThemes,
+    /// starting garbage height, only used by b-type
+    pub height: u32,
+    pub ruleset: Ruleset,
+    /// theme of player 2 in versus, none to share the themes of player 1
+    pub player2_themes: Option<MatchThemes>,
+    /// seed of the tetrominoes & garbage, none for a random seed
+    pub seed: Option<u64>,
 }
 
 impl GameConfig {
-    pub fn new(players: u32, level: u32, rules: MatchRules, themes: MatchThemes) -> Self {
+    pub fn new(
+        players: u32,
+        level: u32,
+        rules: MatchRules,
+        themes: MatchThemes,
+        height: u32,
+        ruleset: Ruleset,
+    ) -> Self {
         Self {
             players,
             level,
             rules,
             themes,
+            height,
+            ruleset,
+            player2_themes: None,
+            seed: None,
+        }
+    }
+
+    pub fn player_themes(&self, player: u32) -> MatchThemes {
+        match self.player2_themes {
+            Some(themes) if player == 2 => themes,
+            _ => self.themes,
         }
     }
 }
 
 impl Default for GameConfig {
     fn default() -> Self {
-        Self::new(1, 0, MatchRules::Battle, MatchThemes::All)
+        Self::new(1, 0, MatchRules::Battle, MatchThemes::All, 0, Ruleset::Guideline)
     }
 }
 
@@ -587,10 +1377,58 @@ pub enum GameKey {
 }
 
 
+impl GameKey {
+    pub fn from_keycode(keycode: Keycode) -> Option<Self> {
+        let code = keycode.into_i32();
+        Self::iter().find(|&e| code == e as i32)
+    }
+}
+
+/// Keycodes of keys that don't type a character are their scancode with this bit set
+const SDLK_SCANCODE_MASK: i32 = 1 << 30;
+
+impl GameKey {
+    /// The physical key that sends this keycode on a US layout
+    fn default_scancode(self) -> Option<Scancode> {
+        let code = self as i32;
+        if code & SDLK_SCANCODE_MASK != 0 {
+            return Scancode::from_i32(code & !SDLK_SCANCODE_MASK);
+        }
+        let scan_key = match self {
+            _ if (GameKey::A as i32..=GameKey::Z as i32).contains(&code) => {
+                return Scancode::from_i32(ScanKey::A as i32 + code - GameKey::A as i32);
+            }
+            _ if (GameKey::Num1 as i32..=GameKey::Num9 as i32).contains(&code) => {
+                return Scancode::from_i32(ScanKey::Num1 as i32 + code - GameKey::Num1 as i32);
+            }
+            GameKey::Num0 => ScanKey::Num0,
+            GameKey::Return => ScanKey::Return,
+            GameKey::Escape => ScanKey::Escape,
+            GameKey::Backspace => ScanKey::Backspace,
+            GameKey::Tab => ScanKey::Tab,
+            GameKey::Space => ScanKey::Space,
+            GameKey::Minus => ScanKey::Minus,
+            GameKey::Equals => ScanKey::Equals,
+            GameKey::LeftBracket => ScanKey::LeftBracket,
+            GameKey::RightBracket => ScanKey::RightBracket,
+            GameKey::Backslash => ScanKey::Backslash,
+            GameKey::Semicolon => ScanKey::Semicolon,
+            GameKey::Quote => ScanKey::Apostrophe,
+            GameKey::Backquote => ScanKey::Grave,
+            GameKey::Comma => ScanKey::Comma,
+            GameKey::Period => ScanKey::Period,
+            GameKey::Slash => ScanKey::Slash,
+            GameKey::Delete => ScanKey::Delete,
+            // shifted characters such as `!` have no key of their own
+            _ => return None,
+        };
+        Some(scan_key.into())
+    }
+}
+
 impl From<Keycode> for GameKey {
     fn from(value: Keycode) -> Self {
-        let code = value.into_i32();
-        Self::iter().find(|&e| code == e as i32).expect("Invalid keycode")
+        Self::from_keycode(value).expect("Invalid keycode")
     }
 }
 
@@ -598,4 +1436,432 @@ impl Into<Keycode> for GameKey {
     fn into(self) -> Keycode {
         Keycode::from_i32(self as i32).expect("Invalid keycode")
     }
-}
\ No newline at end of file
+}
+
+/// Physical key position, named as the sdl scancode
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize, strum::EnumIter)]
+#[repr(i32)]
+pub enum ScanKey {
+    A = sys::SDL_Scancode::SDL_SCANCODE_A as i32,
+    B = sys::SDL_Scancode::SDL_SCANCODE_B as i32,
+    C = sys::SDL_Scancode::SDL_SCANCODE_C as i32,
+    D = sys::SDL_Scancode::SDL_SCANCODE_D as i32,
+    E = sys::SDL_Scancode::SDL_SCANCODE_E as i32,
+    F = sys::SDL_Scancode::SDL_SCANCODE_F as i32,
+    G = sys::SDL_Scancode::SDL_SCANCODE_G as i32,
+    H = sys::SDL_Scancode::SDL_SCANCODE_H as i32,
+    I = sys::SDL_Scancode::SDL_SCANCODE_I as i32,
+    J = sys::SDL_Scancode::SDL_SCANCODE_J as i32,
+    K = sys::SDL_Scancode::SDL_SCANCODE_K as i32,
+    L = sys::SDL_Scancode::SDL_SCANCODE_L as i32,
+    M = sys::SDL_Scancode::SDL_SCANCODE_M as i32,
+    N = sys::SDL_Scancode::SDL_SCANCODE_N as i32,
+    O = sys::SDL_Scancode::SDL_SCANCODE_O as i32,
+    P = sys::SDL_Scancode::SDL_SCANCODE_P as i32,
+    Q = sys::SDL_Scancode::SDL_SCANCODE_Q as i32,
+    R = sys::SDL_Scancode::SDL_SCANCODE_R as i32,
+    S = sys::SDL_Scancode::SDL_SCANCODE_S as i32,
+    T = sys::SDL_Scancode::SDL_SCANCODE_T as i32,
+    U = sys::SDL_Scancode::SDL_SCANCODE_U as i32,
+    V = sys::SDL_Scancode::SDL_SCANCODE_V as i32,
+    W = sys::SDL_Scancode::SDL_SCANCODE_W as i32,
+    X = sys::SDL_Scancode::SDL_SCANCODE_X as i32,
+    Y = sys::SDL_Scancode::SDL_SCANCODE_Y as i32,
+    Z = sys::SDL_Scancode::SDL_SCANCODE_Z as i32,
+    Num1 = sys::SDL_Scancode::SDL_SCANCODE_1 as i32,
+    Num2 = sys::SDL_Scancode::SDL_SCANCODE_2 as i32,
+    Num3 = sys::SDL_Scancode::SDL_SCANCODE_3 as i32,
+    Num4 = sys::SDL_Scancode::SDL_SCANCODE_4 as i32,
+    Num5 = sys::SDL_Scancode::SDL_SCANCODE_5 as i32,
+    Num6 = sys::SDL_Scancode::SDL_SCANCODE_6 as i32,
+    Num7 = sys::SDL_Scancode::SDL_SCANCODE_7 as i32,
+    Num8 = sys::SDL_Scancode::SDL_SCANCODE_8 as i32,
+    Num9 = sys::SDL_Scancode::SDL_SCANCODE_9 as i32,
+    Num0 = sys::SDL_Scancode::SDL_SCANCODE_0 as i32,
+    Return = sys::SDL_Scancode::SDL_SCANCODE_RETURN as i32,
+    Escape = sys::SDL_Scancode::SDL_SCANCODE_ESCAPE as i32,
+    Backspace = sys::SDL_Scancode::SDL_SCANCODE_BACKSPACE as i32,
+    Tab = sys::SDL_Scancode::SDL_SCANCODE_TAB as i32,
+    Space = sys::SDL_Scancode::SDL_SCANCODE_SPACE as i32,
+    Minus = sys::SDL_Scancode::SDL_SCANCODE_MINUS as i32,
+    Equals = sys::SDL_Scancode::SDL_SCANCODE_EQUALS as i32,
+    LeftBracket = sys::SDL_Scancode::SDL_SCANCODE_LEFTBRACKET as i32,
+    RightBracket = sys::SDL_Scancode::SDL_SCANCODE_RIGHTBRACKET as i32,
+    Backslash = sys::SDL_Scancode::SDL_SCANCODE_BACKSLASH as i32,
+    NonUsHash = sys::SDL_Scancode::SDL_SCANCODE_NONUSHASH as i32,
+    Semicolon = sys::SDL_Scancode::SDL_SCANCODE_SEMICOLON as i32,
+    Apostrophe = sys::SDL_Scancode::SDL_SCANCODE_APOSTROPHE as i32,
+    Grave = sys::SDL_Scancode::SDL_SCANCODE_GRAVE as i32,
+    Comma = sys::SDL_Scancode::SDL_SCANCODE_COMMA as i32,
+    Period = sys::SDL_Scancode::SDL_SCANCODE_PERIOD as i32,
+    Slash = sys::SDL_Scancode::SDL_SCANCODE_SLASH as i32,
+    CapsLock = sys::SDL_Scancode::SDL_SCANCODE_CAPSLOCK as i32,
+    F1 = sys::SDL_Scancode::SDL_SCANCODE_F1 as i32,
+    F2 = sys::SDL_Scancode::SDL_SCANCODE_F2 as i32,
+    F3 = sys::SDL_Scancode::SDL_SCANCODE_F3 as i32,
+    F4 = sys::SDL_Scancode::SDL_SCANCODE_F4 as i32,
+    F5 = sys::SDL_Scancode::SDL_SCANCODE_F5 as i32,
+    F6 = sys::SDL_Scancode::SDL_SCANCODE_F6 as i32,
+    F7 = sys::SDL_Scancode::SDL_SCANCODE_F7 as i32,
+    F8 = sys::SDL_Scancode::SDL_SCANCODE_F8 as i32,
+    F9 = sys::SDL_Scancode::SDL_SCANCODE_F9 as i32,
+    F10 = sys::SDL_Scancode::SDL_SCANCODE_F10 as i32,
+    F11 = sys::SDL_Scancode::SDL_SCANCODE_F11 as i32,
+    F12 = sys::SDL_Scancode::SDL_SCANCODE_F12 as i32,
+    PrintScreen = sys::SDL_Scancode::SDL_SCANCODE_PRINTSCREEN as i32,
+    ScrollLock = sys::SDL_Scancode::SDL_SCANCODE_SCROLLLOCK as i32,
+    Pause = sys::SDL_Scancode::SDL_SCANCODE_PAUSE as i32,
+    Insert = sys::SDL_Scancode::SDL_SCANCODE_INSERT as i32,
+    Home = sys::SDL_Scancode::SDL_SCANCODE_HOME as i32,
+    PageUp = sys::SDL_Scancode::SDL_SCANCODE_PAGEUP as i32,
+    Delete = sys::SDL_Scancode::SDL_SCANCODE_DELETE as i32,
+    End = sys::SDL_Scancode::SDL_SCANCODE_END as i32,
+    PageDown = sys::SDL_Scancode::SDL_SCANCODE_PAGEDOWN as i32,
+    Right = sys::SDL_Scancode::SDL_SCANCODE_RIGHT as i32,
+    Left = sys::SDL_Scancode::SDL_SCANCODE_LEFT as i32,
+    Down = sys::SDL_Scancode::SDL_SCANCODE_DOWN as i32,
+    Up = sys::SDL_Scancode::SDL_SCANCODE_UP as i32,
+    NumLockClear = sys::SDL_Scancode::SDL_SCANCODE_NUMLOCKCLEAR as i32,
+    KpDivide = sys::SDL_Scancode::SDL_SCANCODE_KP_DIVIDE as i32,
+    KpMultiply = sys::SDL_Scancode::SDL_SCANCODE_KP_MULTIPLY as i32,
+    KpMinus = sys::SDL_Scancode::SDL_SCANCODE_KP_MINUS as i32,
+    KpPlus = sys::SDL_Scancode::SDL_SCANCODE_KP_PLUS as i32,
+    KpEnter = sys::SDL_Scancode::SDL_SCANCODE_KP_ENTER as i32,
+    Kp1 = sys::SDL_Scancode::SDL_SCANCODE_KP_1 as i32,
+    Kp2 = sys::SDL_Scancode::SDL_SCANCODE_KP_2 as i32,
+    Kp3 = sys::SDL_Scancode::SDL_SCANCODE_KP_3 as i32,
+    Kp4 = sys::SDL_Scancode::SDL_SCANCODE_KP_4 as i32,
+    Kp5 = sys::SDL_Scancode::SDL_SCANCODE_KP_5 as i32,
+    Kp6 = sys::SDL_Scancode::SDL_SCANCODE_KP_6 as i32,
+    Kp7 = sys::SDL_Scancode::SDL_SCANCODE_KP_7 as i32,
+    Kp8 = sys::SDL_Scancode::SDL_SCANCODE_KP_8 as i32,
+    Kp9 = sys::SDL_Scancode::SDL_SCANCODE_KP_9 as i32,
+    Kp0 = sys::SDL_Scancode::SDL_SCANCODE_KP_0 as i32,
+    KpPeriod = sys::SDL_Scancode::SDL_SCANCODE_KP_PERIOD as i32,
+    NonUsBackslash = sys::SDL_Scancode::SDL_SCANCODE_NONUSBACKSLASH as i32,
+    Application = sys::SDL_Scancode::SDL_SCANCODE_APPLICATION as i32,
+    Power = sys::SDL_Scancode::SDL_SCANCODE_POWER as i32,
+    KpEquals = sys::SDL_Scancode::SDL_SCANCODE_KP_EQUALS as i32,
+    F13 = sys::SDL_Scancode::SDL_SCANCODE_F13 as i32,
+    F14 = sys::SDL_Scancode::SDL_SCANCODE_F14 as i32,
+    F15 = sys::SDL_Scancode::SDL_SCANCODE_F15 as i32,
+    F16 = sys::SDL_Scancode::SDL_SCANCODE_F16 as i32,
+    F17 = sys::SDL_Scancode::SDL_SCANCODE_F17 as i32,
+    F18 = sys::SDL_Scancode::SDL_SCANCODE_F18 as i32,
+    F19 = sys::SDL_Scancode::SDL_SCANCODE_F19 as i32,
+    F20 = sys::SDL_Scancode::SDL_SCANCODE_F20 as i32,
+    F21 = sys::SDL_Scancode::SDL_SCANCODE_F21 as i32,
+    F22 = sys::SDL_Scancode::SDL_SCANCODE_F22 as i32,
+    F23 = sys::SDL_Scancode::SDL_SCANCODE_F23 as i32,
+    F24 = sys::SDL_Scancode::SDL_SCANCODE_F24 as i32,
+    Execute = sys::SDL_Scancode::SDL_SCANCODE_EXECUTE as i32,
+    Help = sys::SDL_Scancode::SDL_SCANCODE_HELP as i32,
+    Menu = sys::SDL_Scancode::SDL_SCANCODE_MENU as i32,
+    Select = sys::SDL_Scancode::SDL_SCANCODE_SELECT as i32,
+    Stop = sys::SDL_Scancode::SDL_SCANCODE_STOP as i32,
+    Again = sys::SDL_Scancode::SDL_SCANCODE_AGAIN as i32,
+    Undo = sys::SDL_Scancode::SDL_SCANCODE_UNDO as i32,
+    Cut = sys::SDL_Scancode::SDL_SCANCODE_CUT as i32,
+    Copy = sys::SDL_Scancode::SDL_SCANCODE_COPY as i32,
+    Paste = sys::SDL_Scancode::SDL_SCANCODE_PASTE as i32,
+    Find = sys::SDL_Scancode::SDL_SCANCODE_FIND as i32,
+    Mute = sys::SDL_Scancode::SDL_SCANCODE_MUTE as i32,
+    VolumeUp = sys::SDL_Scancode::SDL_SCANCODE_VOLUMEUP as i32,
+    VolumeDown = sys::SDL_Scancode::SDL_SCANCODE_VOLUMEDOWN as i32,
+    KpComma = sys::SDL_Scancode::SDL_SCANCODE_KP_COMMA as i32,
+    KpEqualsAS400 = sys::SDL_Scancode::SDL_SCANCODE_KP_EQUALSAS400 as i32,
+    International1 = sys::SDL_Scancode::SDL_SCANCODE_INTERNATIONAL1 as i32,
+    International2 = sys::SDL_Scancode::SDL_SCANCODE_INTERNATIONAL2 as i32,
+    International3 = sys::SDL_Scancode::SDL_SCANCODE_INTERNATIONAL3 as i32,
+    International4 = sys::SDL_Scancode::SDL_SCANCODE_INTERNATIONAL4 as i32,
+    International5 = sys::SDL_Scancode::SDL_SCANCODE_INTERNATIONAL5 as i32,
+    International6 = sys::SDL_Scancode::SDL_SCANCODE_INTERNATIONAL6 as i32,
+    International7 = sys::SDL_Scancode::SDL_SCANCODE_INTERNATIONAL7 as i32,
+    International8 = sys::SDL_Scancode::SDL_SCANCODE_INTERNATIONAL8 as i32,
+    International9 = sys::SDL_Scancode::SDL_SCANCODE_INTERNATIONAL9 as i32,
+    Lang1 = sys::SDL_Scancode::SDL_SCANCODE_LANG1 as i32,
+    Lang2 = sys::SDL_Scancode::SDL_SCANCODE_LANG2 as i32,
+    Lang3 = sys::SDL_Scancode::SDL_SCANCODE_LANG3 as i32,
+    Lang4 = sys::SDL_Scancode::SDL_SCANCODE_LANG4 as i32,
+    Lang5 = sys::SDL_Scancode::SDL_SCANCODE_LANG5 as i32,
+    Lang6 = sys::SDL_Scancode::SDL_SCANCODE_LANG6 as i32,
+    Lang7 = sys::SDL_Scancode::SDL_SCANCODE_LANG7 as i32,
+    Lang8 = sys::SDL_Scancode::SDL_SCANCODE_LANG8 as i32,
+    Lang9 = sys::SDL_Scancode::SDL_SCANCODE_LANG9 as i32,
+    AltErase = sys::SDL_Scancode::SDL_SCANCODE_ALTERASE as i32,
+    SysReq = sys::SDL_Scancode::SDL_SCANCODE_SYSREQ as i32,
+    Cancel = sys::SDL_Scancode::SDL_SCANCODE_CANCEL as i32,
+    Clear = sys::SDL_Scancode::SDL_SCANCODE_CLEAR as i32,
+    Prior = sys::SDL_Scancode::SDL_SCANCODE_PRIOR as i32,
+    Return2 = sys::SDL_Scancode::SDL_SCANCODE_RETURN2 as i32,
+    Separator = sys::SDL_Scancode::SDL_SCANCODE_SEPARATOR as i32,
+    Out = sys::SDL_Scancode::SDL_SCANCODE_OUT as i32,
+    Oper = sys::SDL_Scancode::SDL_SCANCODE_OPER as i32,
+    ClearAgain = sys::SDL_Scancode::SDL_SCANCODE_CLEARAGAIN as i32,
+    CrSel = sys::SDL_Scancode::SDL_SCANCODE_CRSEL as i32,
+    ExSel = sys::SDL_Scancode::SDL_SCANCODE_EXSEL as i32,
+    Kp00 = sys::SDL_Scancode::SDL_SCANCODE_KP_00 as i32,
+    Kp000 = sys::SDL_Scancode::SDL_SCANCODE_KP_000 as i32,
+    ThousandsSeparator = sys::SDL_Scancode::SDL_SCANCODE_THOUSANDSSEPARATOR as i32,
+    DecimalSeparator = sys::SDL_Scancode::SDL_SCANCODE_DECIMALSEPARATOR as i32,
+    CurrencyUnit = sys::SDL_Scancode::SDL_SCANCODE_CURRENCYUNIT as i32,
+    CurrencySubUnit = sys::SDL_Scancode::SDL_SCANCODE_CURRENCYSUBUNIT as i32,
+    KpLeftParen = sys::SDL_Scancode::SDL_SCANCODE_KP_LEFTPAREN as i32,
+    KpRightParen = sys::SDL_Scancode::SDL_SCANCODE_KP_RIGHTPAREN as i32,
+    KpLeftBrace = sys::SDL_Scancode::SDL_SCANCODE_KP_LEFTBRACE as i32,
+    KpRightBrace = sys::SDL_Scancode::SDL_SCANCODE_KP_RIGHTBRACE as i32,
+    KpTab = sys::SDL_Scancode::SDL_SCANCODE_KP_TAB as i32,
+    KpBackspace = sys::SDL_Scancode::SDL_SCANCODE_KP_BACKSPACE as i32,
+    KpA = sys::SDL_Scancode::SDL_SCANCODE_KP_A as i32,
+    KpB = sys::SDL_Scancode::SDL_SCANCODE_KP_B as i32,
+    KpC = sys::SDL_Scancode::SDL_SCANCODE_KP_C as i32,
+    KpD = sys::SDL_Scancode::SDL_SCANCODE_KP_D as i32,
+    KpE = sys::SDL_Scancode::SDL_SCANCODE_KP_E as i32,
+    KpF = sys::SDL_Scancode::SDL_SCANCODE_KP_F as i32,
+    KpXor = sys::SDL_Scancode::SDL_SCANCODE_KP_XOR as i32,
+    KpPower = sys::SDL_Scancode::SDL_SCANCODE_KP_POWER as i32,
+    KpPercent = sys::SDL_Scancode::SDL_SCANCODE_KP_PERCENT as i32,
+    KpLess = sys::SDL_Scancode::SDL_SCANCODE_KP_LESS as i32,
+    KpGreater = sys::SDL_Scancode::SDL_SCANCODE_KP_GREATER as i32,
+    KpAmpersand = sys::SDL_Scancode::SDL_SCANCODE_KP_AMPERSAND as i32,
+    KpDblAmpersand = sys::SDL_Scancode::SDL_SCANCODE_KP_DBLAMPERSAND as i32,
+    KpVerticalBar = sys::SDL_Scancode::SDL_SCANCODE_KP_VERTICALBAR as i32,
+    KpDblVerticalBar = sys::SDL_Scancode::SDL_SCANCODE_KP_DBLVERTICALBAR as i32,
+    KpColon = sys::SDL_Scancode::SDL_SCANCODE_KP_COLON as i32,
+    KpHash = sys::SDL_Scancode::SDL_SCANCODE_KP_HASH as i32,
+    KpSpace = sys::SDL_Scancode::SDL_SCANCODE_KP_SPACE as i32,
+    KpAt = sys::SDL_Scancode::SDL_SCANCODE_KP_AT as i32,
+    KpExclam = sys::SDL_Scancode::SDL_SCANCODE_KP_EXCLAM as i32,
+    KpMemStore = sys::SDL_Scancode::SDL_SCANCODE_KP_MEMSTORE as i32,
+    KpMemRecall = sys::SDL_Scancode::SDL_SCANCODE_KP_MEMRECALL as i32,
+    KpMemClear = sys::SDL_Scancode::SDL_SCANCODE_KP_MEMCLEAR as i32,
+    KpMemAdd = sys::SDL_Scancode::SDL_SCANCODE_KP_MEMADD as i32,
+    KpMemSubtract = sys::SDL_Scancode::SDL_SCANCODE_KP_MEMSUBTRACT as i32,
+    KpMemMultiply = sys::SDL_Scancode::SDL_SCANCODE_KP_MEMMULTIPLY as i32,
+    KpMemDivide = sys::SDL_Scancode::SDL_SCANCODE_KP_MEMDIVIDE as i32,
+    KpPlusMinus = sys::SDL_Scancode::SDL_SCANCODE_KP_PLUSMINUS as i32,
+    KpClear = sys::SDL_Scancode::SDL_SCANCODE_KP_CLEAR as i32,
+    KpClearEntry = sys::SDL_Scancode::SDL_SCANCODE_KP_CLEARENTRY as i32,
+    KpBinary = sys::SDL_Scancode::SDL_SCANCODE_KP_BINARY as i32,
+    KpOctal = sys::SDL_Scancode::SDL_SCANCODE_KP_OCTAL as i32,
+    KpDecimal = sys::SDL_Scancode::SDL_SCANCODE_KP_DECIMAL as i32,
+    KpHexadecimal = sys::SDL_Scancode::SDL_SCANCODE_KP_HEXADECIMAL as i32,
+    LCtrl = sys::SDL_Scancode::SDL_SCANCODE_LCTRL as i32,
+    LShift = sys::SDL_Scancode::SDL_SCANCODE_LSHIFT as i32,
+    LAlt = sys::SDL_Scancode::SDL_SCANCODE_LALT as i32,
+    LGui = sys::SDL_Scancode::SDL_SCANCODE_LGUI as i32,
+    RCtrl = sys::SDL_Scancode::SDL_SCANCODE_RCTRL as i32,
+    RShift = sys::SDL_Scancode::SDL_SCANCODE_RSHIFT as i32,
+    RAlt = sys::SDL_Scancode::SDL_SCANCODE_RALT as i32,
+    RGui = sys::SDL_Scancode::SDL_SCANCODE_RGUI as i32,
+    Mode = sys::SDL_Scancode::SDL_SCANCODE_MODE as i32,
+    AudioNext = sys::SDL_Scancode::SDL_SCANCODE_AUDIONEXT as i32,
+    AudioPrev = sys::SDL_Scancode::SDL_SCANCODE_AUDIOPREV as i32,
+    AudioStop = sys::SDL_Scancode::SDL_SCANCODE_AUDIOSTOP as i32,
+    AudioPlay = sys::SDL_Scancode::SDL_SCANCODE_AUDIOPLAY as i32,
+    AudioMute = sys::SDL_Scancode::SDL_SCANCODE_AUDIOMUTE as i32,
+    MediaSelect = sys::SDL_Scancode::SDL_SCANCODE_MEDIASELECT as i32,
+    Www = sys::SDL_Scancode::SDL_SCANCODE_WWW as i32,
+    Mail = sys::SDL_Scancode::SDL_SCANCODE_MAIL as i32,
+    Calculator = sys::SDL_Scancode::SDL_SCANCODE_CALCULATOR as i32,
+    Computer = sys::SDL_Scancode::SDL_SCANCODE_COMPUTER as i32,
+    AcSearch = sys::SDL_Scancode::SDL_SCANCODE_AC_SEARCH as i32,
+    AcHome = sys::SDL_Scancode::SDL_SCANCODE_AC_HOME as i32,
+    AcBack = sys::SDL_Scancode::SDL_SCANCODE_AC_BACK as i32,
+    AcForward = sys::SDL_Scancode::SDL_SCANCODE_AC_FORWARD as i32,
+    AcStop = sys::SDL_Scancode::SDL_SCANCODE_AC_STOP as i32,
+    AcRefresh = sys::SDL_Scancode::SDL_SCANCODE_AC_REFRESH as i32,
+    AcBookmarks = sys::SDL_Scancode::SDL_SCANCODE_AC_BOOKMARKS as i32,
+    BrightnessDown = sys::SDL_Scancode::SDL_SCANCODE_BRIGHTNESSDOWN as i32,
+    BrightnessUp = sys::SDL_Scancode::SDL_SCANCODE_BRIGHTNESSUP as i32,
+    DisplaySwitch = sys::SDL_Scancode::SDL_SCANCODE_DISPLAYSWITCH as i32,
+    KbdIllumToggle = sys::SDL_Scancode::SDL_SCANCODE_KBDILLUMTOGGLE as i32,
+    KbdIllumDown = sys::SDL_Scancode::SDL_SCANCODE_KBDILLUMDOWN as i32,
+    KbdIllumUp = sys::SDL_Scancode::SDL_SCANCODE_KBDILLUMUP as i32,
+    Eject = sys::SDL_Scancode::SDL_SCANCODE_EJECT as i32,
+    Sleep = sys::SDL_Scancode::SDL_SCANCODE_SLEEP as i32,
+    App1 = sys::SDL_Scancode::SDL_SCANCODE_APP1 as i32,
+    App2 = sys::SDL_Scancode::SDL_SCANCODE_APP2 as i32,
+}
+
+impl ScanKey {
+    pub fn from_scancode(scancode: Scancode) -> Option<Self> {
+        let code = scancode as i32;
+        Self::iter().find(|&e| code == e as i32)
+    }
+}
+
+impl From<ScanKey> for Scancode {
+    fn from(value: ScanKey) -> Self {
+        Scancode::from_i32(value as i32).expect("Invalid scancode")
+    }
+}
+
+/// Game controller button, named as in SDL
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize, strum::EnumIter)]
+#[repr(i32)]
+pub enum PadButton {
+    A = sys::SDL_GameControllerButton::SDL_CONTROLLER_BUTTON_A as i32,
+    B = sys::SDL_GameControllerButton::SDL_CONTROLLER_BUTTON_B as i32,
+    X = sys::SDL_GameControllerButton::SDL_CONTROLLER_BUTTON_X as i32,
+    Y = sys::SDL_GameControllerButton::SDL_CONTROLLER_BUTTON_Y as i32,
+    Back = sys::SDL_GameControllerButton::SDL_CONTROLLER_BUTTON_BACK as i32,
+    Guide = sys::SDL_GameControllerButton::SDL_CONTROLLER_BUTTON_GUIDE as i32,
+    Start = sys::SDL_GameControllerButton::SDL_CONTROLLER_BUTTON_START as i32,
+    LeftStick = sys::SDL_GameControllerButton::SDL_CONTROLLER_BUTTON_LEFTSTICK as i32,
+    RightStick = sys::SDL_GameControllerButton::SDL_CONTROLLER_BUTTON_RIGHTSTICK as i32,
+    LeftShoulder = sys::SDL_GameControllerButton::SDL_CONTROLLER_BUTTON_LEFTSHOULDER as i32,
+    RightShoulder = sys::SDL_GameControllerButton::SDL_CONTROLLER_BUTTON_RIGHTSHOULDER as i32,
+    DPadUp = sys::SDL_GameControllerButton::SDL_CONTROLLER_BUTTON_DPAD_UP as i32,
+    DPadDown = sys::SDL_GameControllerButton::SDL_CONTROLLER_BUTTON_DPAD_DOWN as i32,
+    DPadLeft = sys::SDL_GameControllerButton::SDL_CONTROLLER_BUTTON_DPAD_LEFT as i32,
+    DPadRight = sys::SDL_GameControllerButton::SDL_CONTROLLER_BUTTON_DPAD_RIGHT as i32,
+    Misc1 = sys::SDL_GameControllerButton::SDL_CONTROLLER_BUTTON_MISC1 as i32,
+    Paddle1 = sys::SDL_GameControllerButton::SDL_CONTROLLER_BUTTON_PADDLE1 as i32,
+    Paddle2 = sys::SDL_GameControllerButton::SDL_CONTROLLER_BUTTON_PADDLE2 as i32,
+    Paddle3 = sys::SDL_GameControllerButton::SDL_CONTROLLER_BUTTON_PADDLE3 as i32,
+    Paddle4 = sys::SDL_GameControllerButton::SDL_CONTROLLER_BUTTON_PADDLE4 as i32,
+    Touchpad = sys::SDL_GameControllerButton::SDL_CONTROLLER_BUTTON_TOUCHPAD as i32,
+}
+
+impl From<Button> for PadButton {
+    fn from(value: Button) -> Self {
+        let code = value as i32;
+        Self::iter().find(|&e| code == e as i32).expect("Invalid button")
+    }
+}
+
+/// Game controller stick or trigger, named as in SDL
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize, strum::EnumIter)]
+#[repr(i32)]
+pub enum PadAxis {
+    LeftX = sys::SDL_GameControllerAxis::SDL_CONTROLLER_AXIS_LEFTX as i32,
+    LeftY = sys::SDL_GameControllerAxis::SDL_CONTROLLER_AXIS_LEFTY as i32,
+    RightX = sys::SDL_GameControllerAxis::SDL_CONTROLLER_AXIS_RIGHTX as i32,
+    RightY = sys::SDL_GameControllerAxis::SDL_CONTROLLER_AXIS_RIGHTY as i32,
+    TriggerLeft = sys::SDL_GameControllerAxis::SDL_CONTROLLER_AXIS_TRIGGERLEFT as i32,
+    TriggerRight = sys::SDL_GameControllerAxis::SDL_CONTROLLER_AXIS_TRIGGERRIGHT as i32,
+}
+
+impl From<Axis> for PadAxis {
+    fn from(value: Axis) -> Self {
+        let code = value as i32;
+        Self::iter().find(|&e| code == e as i32).expect("Invalid axis")
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+pub enum PadInput {
+    Button(PadButton),
+    /// stick pushed right or down past the deadzone, or a trigger pulled
+    AxisPositive(PadAxis),
+    /// stick pushed left or up past the deadzone
+    AxisNegative(PadAxis),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_and_multiple_key_bindings() {
+        let bindings: KeyBindings = serde_yaml::from_str("Left").unwrap();
+        assert_eq!(bindings, KeyBindings::key(GameKey::Left));
+
+        let bindings: KeyBindings = serde_yaml::from_str("[ Left, { scancode: A } ]").unwrap();
+        assert_eq!(
+            bindings.0,
+            vec![
+                KeyBinding::Keycode(GameKey::Left),
+                KeyBinding::Scancode { scancode: ScanKey::A }
+            ]
+        );
+
+        let bindings: KeyBindings = serde_yaml::from_str("~").unwrap();
+        assert_eq!(bindings, KeyBindings::default());
+        assert!(serde_yaml::from_str::<KeyBindings>("{ scancode: NotAKey }").is_err());
+    }
+
+    #[test]
+    fn unknown_key_names_are_reported() {
+        let error = serde_yaml::from_str::<KeyBindings>("[ Left, Lefty ]").unwrap_err();
+        assert!(error.to_string().contains("unknown key name `Lefty`"));
+        let error = serde_yaml::from_str::<KeyBindings>("{ scancode: Nope }").unwrap_err();
+        assert!(error.to_string().contains("unknown scancode name `Nope`"));
+    }
+
+    #[test]
+    fn validate_reports_each_problem() {
+        let mut config = Config::default();
+        assert!(config.validate().is_empty());
+
+        config.audio.music_volume = 1.5;
+        config.video.mode = VideoMode::Window { width: 0, height: 720 };
+        let fields: Vec<String> = config.validate().into_iter().map(|(field, _)| field).collect();
+        assert_eq!(fields, vec!["audio.music_volume", "video.mode"]);
+    }
+
+    #[test]
+    fn yaml_problem_splits_the_field() {
+        let yaml = "audio:\n  music_volume: loud\n";
+        let error = serde_yaml::from_str::<Config>(yaml).unwrap_err();
+        let (field, reason) = yaml_problem(&error);
+        assert_eq!(field, "audio.music_volume");
+        assert!(reason.contains("invalid type"));
+    }
+
+    #[test]
+    fn players_binding_the_same_key_is_an_error() {
+        let mut input = Config::default().input;
+        let mut player2 = input.player1.clone();
+        player2.move_left = KeyBindings(vec![KeyBinding::Keycode(GameKey::A)]);
+        input.player2 = Some(player2);
+        assert_eq!(
+            input.game_map(),
+            Err("input.player2.move_right: Right is also bound to input.player1.move_right".to_string())
+        );
+        let fields: Vec<String> = input.check_bindings().into_iter().map(|(field, _)| field).collect();
+        assert_eq!(
+            fields,
+            vec![
+                "input.player2.move_right",
+                "input.player2.soft_drop",
+                "input.player2.hard_drop",
+                "input.player2.rotate_clockwise",
+                "input.player2.rotate_anticlockwise",
+                "input.player2.hold",
+            ]
+        );
+
+        let mut player2 = input.player1.clone();
+        for (bindings, key) in [
+            (&mut player2.move_left, GameKey::A),
+            (&mut player2.move_right, GameKey::D),
+            (&mut player2.soft_drop, GameKey::S),
+            (&mut player2.hard_drop, GameKey::W),
+            (&mut player2.rotate_clockwise, GameKey::E),
+            (&mut player2.rotate_anticlockwise, GameKey::Q),
+            (&mut player2.hold, GameKey::Tab),
+        ] {
+            *bindings = KeyBindings::key(key);
+        }
+        input.player2 = Some(player2);
+        assert!(input.game_map().is_ok());
+    }
+
+    #[test]
+    fn physical_and_logical_bindings_of_the_same_key_clash() {
+        let mut input = Config::default().input;
+        input.player1.hold = KeyBindings(vec![KeyBinding::Scancode { scancode: ScanKey::Z }]);
+        assert_eq!(
+            input.game_map(),
+            Err("input.player1.hold: scancode Z is also bound to input.player1.rotate_anticlockwise as Z".to_string())
+        );
+
+        input.player1.hold = KeyBindings(vec![KeyBinding::Scancode { scancode: ScanKey::Left }]);
+        assert_eq!(
+            input.game_map(),
+            Err("input.player1.hold: scancode Left is also bound to input.player1.move_left as Left".to_string())
+        );
+    }
+}
@@ -0,0 +1,299 @@
+use crate::config::{InputConfig, KeyBinding, KeyBindings};
+use crate::game_input::GameInputKey;
+use crate::menu::{Menu, MenuItem};
+use crate::menu_input::MenuInputKey;
+use crate::ui::UiStyle;
+use sdl2::render::{TextureCreator, WindowCanvas};
+use sdl2::ttf::Sdl2TtfContext;
+use sdl2::video::WindowContext;
+
+pub const BIND_BY: &str = "bind by";
+pub const BIND_BY_KEY: &str = "key";
+pub const BIND_BY_POSITION: &str = "key position";
+pub const RESET_CONTROLS: &str = "reset defaults";
+pub const SAVE_CONTROLS: &str = "save";
+pub const CANCEL_CONTROLS: &str = "cancel";
+const ADD_BINDING: &str = "add";
+
+/// A rebindable keyboard action on the controls screen
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ControlAction {
+    Menu(MenuInputKey),
+    Game(GameInputKey),
+}
+
+impl ControlAction {
+    /// All rebindable actions in the order they are listed
+    pub fn all(max_players: u32) -> Vec<Self> {
+        let mut result: Vec<Self> = [
+            MenuInputKey::Up,
+            MenuInputKey::Down,
+            MenuInputKey::Left,
+            MenuInputKey::Right,
+            MenuInputKey::Select,
+            MenuInputKey::Start,
+        ]
+        .into_iter()
+        .map(Self::Menu)
+        .collect();
+
+        for player in 1..=max_players {
+            result.extend(
+                [
+                    GameInputKey::MoveLeft { player },
+                    GameInputKey::MoveRight { player },
+                    GameInputKey::SoftDrop { player },
+                    GameInputKey::HardDrop { player },
+                    GameInputKey::RotateClockwise { player },
+                    GameInputKey::RotateAnticlockwise { player },
+                    GameInputKey::Hold { player },
+                    GameInputKey::NextPlayerTheme { player },
+                ]
+                .into_iter()
+                .map(Self::Game),
+            );
+        }
+
+        result.extend(
+            [
+                GameInputKey::Pause,
+                GameInputKey::NextTheme,
+                GameInputKey::Retry,
+                GameInputKey::ReturnToMenu,
+            ]
+                .into_iter()
+                .map(Self::Game),
+        );
+        result
+    }
+
+    pub fn name(&self) -> String {
+        match *self {
+            ControlAction::Menu(key) => {
+                let action = match key {
+                    MenuInputKey::Up => "up",
+                    MenuInputKey::Down => "down",
+                    MenuInputKey::Left => "left",
+                    MenuInputKey::Right => "right",
+                    MenuInputKey::Start => "start",
+                    MenuInputKey::Select => "select",
+                    MenuInputKey::Quit => "quit",
+                };
+                format!("menu {}", action)
+            }
+            ControlAction::Game(key) => {
+                let action = match key {
+                    GameInputKey::MoveLeft { .. } => "left",
+                    GameInputKey::MoveRight { .. } => "right",
+                    GameInputKey::SoftDrop { .. } => "soft drop",
+                    GameInputKey::HardDrop { .. } => "hard drop",
+                    GameInputKey::RotateClockwise { .. } => "rotate",
+                    GameInputKey::RotateAnticlockwise { .. } => "rotate back",
+                    GameInputKey::Hold { .. } => "hold",
+                    GameInputKey::NextPlayerTheme { .. } | GameInputKey::NextTheme => "next theme",
+                    GameInputKey::Pause => "pause",
+                    GameInputKey::Retry => "retry",
+                    GameInputKey::ReturnToMenu | GameInputKey::Quit => "quit",
+                };
+                match key.player() {
+                    Some(player) => format!("p{} {}", player, action),
+                    None => action.to_string(),
+                }
+            }
+        }
+    }
+
+    pub fn bindings<'a>(&self, config: &'a InputConfig) -> Option<&'a KeyBindings> {
+        let player_config = |player: u32| match player {
+            1 => Some(&config.player1),
+            _ => config.player2.as_ref(),
+        };
+        Some(match *self {
+            ControlAction::Menu(key) => match key {
+                MenuInputKey::Up => &config.menu.up,
+                MenuInputKey::Down => &config.menu.down,
+                MenuInputKey::Left => &config.menu.left,
+                MenuInputKey::Right => &config.menu.right,
+                MenuInputKey::Start => &config.menu.start,
+                MenuInputKey::Select => &config.menu.select,
+                MenuInputKey::Quit => &config.quit,
+            },
+            ControlAction::Game(key) => match key {
+                GameInputKey::MoveLeft { player } => &player_config(player)?.move_left,
+                GameInputKey::MoveRight { player } => &player_config(player)?.move_right,
+                GameInputKey::SoftDrop { player } => &player_config(player)?.soft_drop,
+                GameInputKey::HardDrop { player } => &player_config(player)?.hard_drop,
+                GameInputKey::RotateClockwise { player } => &player_config(player)?.rotate_clockwise,
+                GameInputKey::RotateAnticlockwise { player } => {
+                    &player_config(player)?.rotate_anticlockwise
+                }
+                GameInputKey::Hold { player } => &player_config(player)?.hold,
+                GameInputKey::NextPlayerTheme { player } => &player_config(player)?.next_theme,
+                GameInputKey::Pause => &config.pause,
+                GameInputKey::ReturnToMenu | GameInputKey::Quit => &config.quit,
+                GameInputKey::NextTheme => &config.next_theme,
+                GameInputKey::Retry => &config.retry,
+            },
+        })
+    }
+
+    /// Player 2 controls are created on first bind as there are no defaults
+    pub fn bindings_mut<'a>(&self, config: &'a mut InputConfig) -> &'a mut KeyBindings {
+        match *self {
+            ControlAction::Menu(key) => match key {
+                MenuInputKey::Up => &mut config.menu.up,
+                MenuInputKey::Down => &mut config.menu.down,
+                MenuInputKey::Left => &mut config.menu.left,
+                MenuInputKey::Right => &mut config.menu.right,
+                MenuInputKey::Start => &mut config.menu.start,
+                MenuInputKey::Select => &mut config.menu.select,
+                MenuInputKey::Quit => &mut config.quit,
+            },
+            ControlAction::Game(key) => {
+                let player_config = match key.player() {
+                    Some(1) => &mut config.player1,
+                    Some(_) => config.player2.get_or_insert_with(Default::default),
+                    None => {
+                        return match key {
+                            GameInputKey::Pause => &mut config.pause,
+                            GameInputKey::NextTheme => &mut config.next_theme,
+                            GameInputKey::Retry => &mut config.retry,
+                            _ => &mut config.quit,
+                        }
+                    }
+                };
+                match key {
+                    GameInputKey::MoveLeft { .. } => &mut player_config.move_left,
+                    GameInputKey::MoveRight { .. } => &mut player_config.move_right,
+                    GameInputKey::SoftDrop { .. } => &mut player_config.soft_drop,
+                    GameInputKey::HardDrop { .. } => &mut player_config.hard_drop,
+                    GameInputKey::RotateClockwise { .. } => &mut player_config.rotate_clockwise,
+                    GameInputKey::RotateAnticlockwise { .. } => {
+                        &mut player_config.rotate_anticlockwise
+                    }
+                    GameInputKey::Hold { .. } => &mut player_config.hold,
+                    _ => &mut player_config.next_theme,
+                }
+            }
+        }
+    }
+
+    /// Each current binding as shown on the controls screen, followed by an entry to add another
+    pub fn entries(&self, config: &InputConfig) -> Vec<String> {
+        self.bindings(config)
+            .into_iter()
+            .flat_map(|bindings| bindings.0.iter().map(|b| b.to_string()))
+            .chain([ADD_BINDING.to_string()])
+            .collect()
+    }
+
+    /// Replaces the binding of an entry, or adds it when it's the add entry. Returns false if already bound.
+    pub fn bind(&self, config: &mut InputConfig, entry: usize, binding: KeyBinding) -> bool {
+        let bindings = &mut self.bindings_mut(config).0;
+        if bindings.contains(&binding) {
+            return false;
+        }
+        match bindings.get_mut(entry) {
+            Some(existing) => *existing = binding,
+            None => bindings.push(binding),
+        }
+        true
+    }
+
+    /// Removes the binding of an entry, nothing happens for the add entry
+    pub fn unbind(&self, config: &mut InputConfig, entry: usize) {
+        let bindings = &mut self.bindings_mut(config).0;
+        if entry < bindings.len() {
+            bindings.remove(entry);
+        }
+    }
+}
+
+/// The controls screen, each action with its current bindings followed by how keys are bound, reset, save & cancel
+pub fn controls_menu<'a>(
+    canvas: &mut WindowCanvas,
+    ttf: &Sdl2TtfContext,
+    texture_creator: &'a TextureCreator<WindowContext>,
+    style: UiStyle,
+    actions: &[ControlAction],
+    config: &InputConfig,
+    by_position: bool,
+    status: &str,
+    current_row: usize,
+) -> Result<Menu<'a>, String> {
+    let menu_items = actions
+        .iter()
+        .map(|action| MenuItem::select_list(&action.name(), action.entries(config), 0))
+        .chain([
+            MenuItem::select_list(
+                BIND_BY,
+                vec![BIND_BY_KEY.to_string(), BIND_BY_POSITION.to_string()],
+                by_position as usize,
+            ),
+            MenuItem::select(RESET_CONTROLS),
+            MenuItem::select(SAVE_CONTROLS),
+            MenuItem::select(CANCEL_CONTROLS),
+        ])
+        .collect();
+    let menu = Menu::new(
+        menu_items,
+        canvas,
+        ttf,
+        texture_creator,
+        style,
+        "CONTROLS".to_string(),
+        status.to_string(),
+    )?;
+    Ok(menu.with_current_row(current_row))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, GameKey, ScanKey};
+
+    #[test]
+    fn action_names_are_unique() {
+        let actions = ControlAction::all(2);
+        let mut names: Vec<String> = actions.iter().map(|a| a.name()).collect();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), actions.len());
+    }
+
+    #[test]
+    fn binding_player2_creates_their_controls() {
+        let mut input = Config::default().input;
+        let action = ControlAction::Game(GameInputKey::Hold { player: 2 });
+        assert_eq!(action.entries(&input), vec!["add"]);
+
+        *action.bindings_mut(&mut input) = KeyBindings::key(GameKey::Tab);
+        assert_eq!(action.entries(&input), vec!["Tab", "add"]);
+        assert_eq!(
+            ControlAction::Game(GameInputKey::MoveLeft { player: 2 }).entries(&input),
+            vec!["add"]
+        );
+        assert_eq!(
+            ControlAction::Game(GameInputKey::MoveLeft { player: 1 }).entries(&input),
+            vec!["Left", "add"]
+        );
+    }
+
+    #[test]
+    fn binding_replaces_or_adds_a_single_entry() {
+        let mut input = Config::default().input;
+        let action = ControlAction::Game(GameInputKey::MoveLeft { player: 1 });
+        let position = KeyBinding::Scancode { scancode: ScanKey::A };
+
+        assert!(action.bind(&mut input, 1, position));
+        assert_eq!(action.entries(&input), vec!["Left", "scancode A", "add"]);
+        assert!(!action.bind(&mut input, 1, position));
+
+        assert!(action.bind(&mut input, 0, KeyBinding::Keycode(GameKey::J)));
+        assert_eq!(action.entries(&input), vec!["J", "scancode A", "add"]);
+
+        action.unbind(&mut input, 0);
+        action.unbind(&mut input, 1);
+        assert_eq!(action.entries(&input), vec!["scancode A", "add"]);
+    }
+}
@@ -0,0 +1,228 @@
+use crate::config::{CrtConfig, CrtMask};
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::rect::Rect;
+use sdl2::render::{Texture, TextureCreator, WindowCanvas};
+use sdl2::video::WindowContext;
+
+const BYTES_PER_PIXEL: usize = 3;
+const BLOOM_RADIUS: usize = 2;
+
+/// CPU post processing of the composited frame to look like an old CRT,
+/// reads back the canvas so works the same on every renderer including software
+pub struct CrtFilter<'a> {
+    texture: Texture<'a>,
+    input: Vec<u8>,
+    output: Vec<u8>,
+    bloom: Vec<u8>,
+    lookups: Vec<CrtLookup>,
+}
+
+impl<'a> CrtFilter<'a> {
+    pub fn new(
+        texture_creator: &'a TextureCreator<WindowContext>,
+        window_size: (u32, u32),
+    ) -> Result<Self, String> {
+        let (width, height) = window_size;
+        let texture = texture_creator
+            .create_texture_streaming(PixelFormatEnum::RGB24, width, height)
+            .map_err(|e| e.to_string())?;
+        Ok(Self {
+            texture,
+            input: vec![],
+            output: vec![],
+            bloom: vec![],
+            lookups: vec![],
+        })
+    }
+
+    /// Filter a rect of the canvas, pixel size is the size of a scaled source pixel so scanlines line up
+    pub fn apply(
+        &mut self,
+        canvas: &mut WindowCanvas,
+        rect: Rect,
+        pixel_size: u32,
+        config: CrtConfig,
+    ) -> Result<(), String> {
+        let (width, height) = (rect.width() as usize, rect.height() as usize);
+        self.input = canvas.read_pixels(rect, PixelFormatEnum::RGB24)?;
+        self.output.resize(self.input.len(), 0);
+
+        let lookup = match self.lookups.iter().position(|l| l.matches(rect, pixel_size, config)) {
+            Some(index) => &self.lookups[index],
+            None => {
+                self.lookups.push(CrtLookup::new(rect, pixel_size, config));
+                self.lookups.last().unwrap()
+            }
+        };
+
+        if config.bloom > 0.0 {
+            blur_rows(&self.input, &mut self.bloom, width);
+        }
+
+        for y in 0..height {
+            for x in 0..width {
+                let dst = (y * width + x) * BYTES_PER_PIXEL;
+                let Some((src_x, src_y)) = lookup.source(x, y) else {
+                    self.output[dst..dst + BYTES_PER_PIXEL].fill(0);
+                    continue;
+                };
+                let src = (src_y * width + src_x) * BYTES_PER_PIXEL;
+                let scanline = lookup.scanlines[src_y];
+                let mask = mask_weights(config.mask, config.mask_strength, x, y);
+                for (c, mask) in mask.iter().enumerate() {
+                    let mut value = self.input[src + c] as f64;
+                    if config.bloom > 0.0 {
+                        value += config.bloom * (self.bloom[src + c] as f64 - value).max(0.0);
+                    }
+                    self.output[dst + c] = (value * scanline * mask).round().min(255.0) as u8;
+                }
+            }
+        }
+
+        let texture_rect = Rect::new(0, 0, rect.width(), rect.height());
+        self.texture
+            .update(texture_rect, &self.output, width * BYTES_PER_PIXEL)
+            .map_err(|e| e.to_string())?;
+        canvas.copy(&self.texture, texture_rect, rect)
+    }
+}
+
+/// Everything about the filter that only depends on the config & the size of the filtered rect
+struct CrtLookup {
+    rect: Rect,
+    pixel_size: u32,
+    config: CrtConfig,
+    /// brightness of each source row
+    scanlines: Vec<f64>,
+    /// source pixel of each destination pixel when curved, none is outside of the screen
+    curve: Option<Vec<Option<(usize, usize)>>>,
+}
+
+impl CrtLookup {
+    fn new(rect: Rect, pixel_size: u32, config: CrtConfig) -> Self {
+        let (width, height) = (rect.width() as usize, rect.height() as usize);
+        let scanlines = (0..height)
+            .map(|y| scanline_weight(y, pixel_size as usize, config.scanlines))
+            .collect();
+        let curve = if config.curvature > 0.0 {
+            let curve = (0..height)
+                .flat_map(|y| (0..width).map(move |x| (x, y)))
+                .map(|(x, y)| curve_point(x, y, width, height, config.curvature))
+                .collect();
+            Some(curve)
+        } else {
+            None
+        };
+        Self {
+            rect,
+            pixel_size,
+            config,
+            scanlines,
+            curve,
+        }
+    }
+
+    fn matches(&self, rect: Rect, pixel_size: u32, config: CrtConfig) -> bool {
+        self.rect == rect && self.pixel_size == pixel_size && self.config == config
+    }
+
+    fn source(&self, x: usize, y: usize) -> Option<(usize, usize)> {
+        match &self.curve {
+            None => Some((x, y)),
+            Some(curve) => curve[y * self.rect.width() as usize + x],
+        }
+    }
+}
+
+/// Brightness of a row, the last third of every scaled source pixel is the dark gap between scanlines
+fn scanline_weight(y: usize, pixel_size: usize, scanlines: f64) -> f64 {
+    let pixel_size = pixel_size.max(2);
+    let gap = (pixel_size / 3).max(1);
+    if y % pixel_size >= pixel_size - gap {
+        1.0 - scanlines.clamp(0.0, 1.0)
+    } else {
+        1.0
+    }
+}
+
+/// Brightness of each channel of the phosphor at a pixel
+fn mask_weights(mask: CrtMask, strength: f64, x: usize, y: usize) -> [f64; 3] {
+    let phosphor = match mask {
+        CrtMask::None => return [1.0; 3],
+        CrtMask::ApertureGrille => x % 3,
+        // every other pair of rows is offset by half a triad
+        CrtMask::ShadowMask => (x + (y / 2 % 2) * 2) % 3,
+    };
+    let dim = 1.0 - strength.clamp(0.0, 1.0);
+    let mut weights = [dim; 3];
+    weights[phosphor] = 1.0;
+    weights
+}
+
+/// Barrel distortion, the source pixel of a destination pixel or none if it's off the curved screen
+fn curve_point(x: usize, y: usize, width: usize, height: usize, curvature: f64) -> Option<(usize, usize)> {
+    let u = 2.0 * (x as f64 + 0.5) / width as f64 - 1.0;
+    let v = 2.0 * (y as f64 + 0.5) / height as f64 - 1.0;
+    let distortion = 1.0 + curvature * (u * u + v * v);
+    let (u, v) = (u * distortion, v * distortion);
+    if u.abs() > 1.0 || v.abs() > 1.0 {
+        return None;
+    }
+    let src_x = ((u + 1.0) * width as f64 / 2.0).floor() as usize;
+    let src_y = ((v + 1.0) * height as f64 / 2.0).floor() as usize;
+    Some((src_x.min(width - 1), src_y.min(height - 1)))
+}
+
+/// Horizontal box blur of each row of rgb pixels
+fn blur_rows(input: &[u8], output: &mut Vec<u8>, width: usize) {
+    output.resize(input.len(), 0);
+    let row_bytes = width * BYTES_PER_PIXEL;
+    for (row, out) in input.chunks_exact(row_bytes).zip(output.chunks_exact_mut(row_bytes)) {
+        for x in 0..width {
+            let from = x.saturating_sub(BLOOM_RADIUS);
+            let to = (x + BLOOM_RADIUS).min(width - 1);
+            for c in 0..BYTES_PER_PIXEL {
+                let sum: u32 = (from..=to).map(|i| row[i * BYTES_PER_PIXEL + c] as u32).sum();
+                out[x * BYTES_PER_PIXEL + c] = (sum / (to - from + 1) as u32) as u8;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scanlines_darken_the_bottom_of_each_pixel() {
+        let weights: Vec<f64> = (0..6).map(|y| scanline_weight(y, 3, 0.5)).collect();
+        assert_eq!(weights, vec![1.0, 1.0, 0.5, 1.0, 1.0, 0.5]);
+        let weights: Vec<f64> = (0..4).map(|y| scanline_weight(y, 1, 1.0)).collect();
+        assert_eq!(weights, vec![1.0, 0.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn masks() {
+        assert_eq!(mask_weights(CrtMask::None, 0.5, 1, 0), [1.0, 1.0, 1.0]);
+        assert_eq!(mask_weights(CrtMask::ApertureGrille, 0.5, 4, 0), [0.5, 1.0, 0.5]);
+        assert_eq!(mask_weights(CrtMask::ApertureGrille, 0.5, 4, 2), [0.5, 1.0, 0.5]);
+        assert_eq!(mask_weights(CrtMask::ShadowMask, 0.5, 4, 0), [0.5, 1.0, 0.5]);
+        assert_eq!(mask_weights(CrtMask::ShadowMask, 0.5, 4, 2), [1.0, 0.5, 0.5]);
+    }
+
+    #[test]
+    fn curvature_keeps_the_center_and_clips_the_corners() {
+        assert_eq!(curve_point(50, 50, 101, 101, 0.1), Some((50, 50)));
+        assert_eq!(curve_point(0, 0, 101, 101, 0.1), None);
+        assert_eq!(curve_point(50, 0, 101, 101, 0.0), Some((50, 0)));
+    }
+
+    #[test]
+    fn blur() {
+        let input = [0, 0, 0, 0, 0, 0, 250, 250, 250, 0, 0, 0, 0, 0, 0];
+        let mut output = vec![];
+        blur_rows(&input, &mut output, 5);
+        assert_eq!(&output[0..3], &[83, 83, 83]);
+        assert_eq!(&output[6..9], &[50, 50, 50]);
+    }
+}
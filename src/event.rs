@@ -40,7 +40,10 @@ pub enum GameEvent {
     },
     Quit,
     ReturnToMenu,
-    NextTheme,
+    /// Cycle the theme of a single player or all players
+    NextTheme {
+        player: Option<u32>,
+    },
     ReceivedGarbage {
         player: u32,
         lines: u32,
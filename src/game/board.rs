@@ -1,6 +1,7 @@
 use super::block::BlockState;
 use super::geometry::Point;
 use super::tetromino::{Tetromino, TetrominoShape};
+use crate::game::ruleset::Ruleset;
 use crate::game::tetromino::Minos;
 
 use std::fmt::{Display, Formatter};
@@ -27,6 +28,7 @@ pub fn compact_destroy_lines(lines: DestroyLines) -> Vec<u32> {
 pub struct Board {
     blocks: [BlockState; TOTAL_BLOCKS as usize],
     tetromino: Option<Tetromino>,
+    ruleset: Ruleset,
 }
 
 fn index_at(x: u32, y: u32) -> usize {
@@ -47,10 +49,16 @@ fn rows_range(y_from: u32, y_to: u32) -> Range<usize> {
 }
 
 impl Board {
+    #[cfg(test)]
     pub fn new() -> Self {
+        Self::with_ruleset(Ruleset::default())
+    }
+
+    pub fn with_ruleset(ruleset: Ruleset) -> Self {
         Self {
             blocks: [BlockState::Empty; TOTAL_BLOCKS as usize],
             tetromino: None,
+            ruleset,
         }
     }
 
@@ -172,6 +180,10 @@ impl Board {
 
     fn render_ghost(&mut self) {
         // todo test
+        if !self.ruleset.has_ghost() {
+            return;
+        }
+
         // remove all existing ghost blocks
         for i in 0..(TOTAL_BLOCKS as usize) {
             if matches!(self.blocks[i], BlockState::Ghost(_, _, _)) {
@@ -205,6 +217,10 @@ impl Board {
     }
 
     pub fn rotate(&mut self, clockwise: bool) -> bool {
+        if self.ruleset.is_classic() {
+            return self.rotate_classic(clockwise);
+        }
+
         let wall_kick_id = self.try_rotate(clockwise);
         if wall_kick_id.is_none() {
             return false;
@@ -213,6 +229,16 @@ impl Board {
         true
     }
 
+    fn rotate_classic(&mut self, clockwise: bool) -> bool {
+        match self.tetromino {
+            Some(tetromino) if self.minos_fit(&tetromino.minos_after_classic_rotation(clockwise)) => {
+                self.mutate_tetromino(|tetromino| tetromino.rotate_classic(clockwise));
+                true
+            }
+            _ => false,
+        }
+    }
+
     fn try_rotate(&self, clockwise: bool) -> Option<usize> {
         self.tetromino?;
 
@@ -220,24 +246,17 @@ impl Board {
             .tetromino
             .unwrap()
             .possible_minos_after_rotation(clockwise);
-        for (id, minos) in next_minos.iter().enumerate() {
-            let mut success = true;
-            for p in minos {
-                if p.x < 0 || p.x >= BOARD_WIDTH as i32 || p.y < 0 || p.y >= TOTAL_HEIGHT as i32 {
-                    success = false;
-                    break;
-                }
-                if self.block(*p).collides() {
-                    success = false;
-                    break;
-                }
-            }
-            if success {
-                return Some(id);
-            }
-        }
+        next_minos.iter().position(|minos| self.minos_fit(minos))
+    }
 
-        None
+    fn minos_fit(&self, minos: &Minos) -> bool {
+        minos.iter().all(|p| {
+            p.x >= 0
+                && p.x < BOARD_WIDTH as i32
+                && p.y >= 0
+                && p.y < TOTAL_HEIGHT as i32
+                && !self.block(*p).collides()
+        })
     }
 
     pub fn register_lock_placement(&mut self) -> u32 {
@@ -397,6 +416,30 @@ impl Board {
         }
     }
 
+    /// Replace row y with garbage, blocks are placed where filled is true
+    pub fn set_garbage_row(&mut self, y: u32, filled: [bool; BOARD_WIDTH as usize]) {
+        for (x, is_filled) in filled.into_iter().enumerate() {
+            self.blocks[index_at(x as u32, y)] = if is_filled {
+                BlockState::Garbage
+            } else {
+                BlockState::Empty
+            };
+        }
+    }
+
+    /// Height of the highest row containing any stack or garbage
+    pub fn stack_height(&self) -> u32 {
+        (0..TOTAL_HEIGHT)
+            .rev()
+            .find(|y| self.row(*y).iter().any(|b| b.collides()))
+            .map(|y| y + 1)
+            .unwrap_or(0)
+    }
+
+    pub fn stack_block_count(&self) -> u32 {
+        self.blocks.iter().filter(|b| b.collides()).count() as u32
+    }
+
     pub fn is_tetromino_above_skyline(&self) -> bool {
         if self.tetromino.is_none() {
             return false;
@@ -936,6 +979,37 @@ mod tests {
         }
     }
 
+    #[test]
+    fn sets_garbage_row() {
+        let mut board = Board::new();
+        let mut filled = [true; BOARD_WIDTH as usize];
+        filled[2] = false;
+        filled[7] = false;
+        board.set_garbage_row(3, filled);
+
+        assert_eq!(board.stack_block_count(), BOARD_WIDTH - 2);
+        for x in 0..BOARD_WIDTH {
+            let block = board.blocks[index_at(x, 3)];
+            if x == 2 || x == 7 {
+                assert_eq!(block, BlockState::Empty);
+            } else {
+                assert_eq!(block, BlockState::Garbage);
+            }
+        }
+    }
+
+    #[test]
+    fn stack_height() {
+        let mut board = Board::new();
+        assert_eq!(board.stack_height(), 0);
+        having_stack_at(&mut board, 4, 0);
+        assert_eq!(board.stack_height(), 1);
+        having_stack_at(&mut board, 2, 5);
+        assert_eq!(board.stack_height(), 6);
+        can_spawn_tetromino(&mut board, TetrominoShape::I);
+        assert_eq!(board.stack_height(), 6, "{}", board);
+    }
+
     #[test]
     fn is_tetromino_above_skyline() {
         let mut board = Board::new();
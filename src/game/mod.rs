@@ -2,6 +2,9 @@ use crate::event::{GameEvent, GameOverCondition};
 use crate::game::block::BlockState;
 use crate::game::board::DestroyLines;
 use crate::game::random::{RandomTetromino, PEEK_SIZE};
+use crate::game::ruleset::{
+    classic_level, classic_line_points, classic_spawn_delay, classic_step_delay, Ruleset,
+};
 use board::Board;
 
 use std::cmp::{max, min};
@@ -13,8 +16,10 @@ pub mod block;
 pub mod board;
 pub mod geometry;
 pub mod random;
+pub mod ruleset;
 pub mod tetromino;
 
+pub const MAX_START_LEVEL: u32 = 19;
 const LINES_PER_LEVEL: u32 = 10;
 const SOFT_DROP_STEP_FACTOR: u32 = 20;
 const SOFT_DROP_SPAWN_FACTOR: u32 = 10;
@@ -33,6 +38,11 @@ const DIFFICULT_MULTIPLIER: f64 = 1.5;
 const SOFT_DROP_POINTS_PER_ROW: u32 = 1;
 const HARD_DROP_POINTS_PER_ROW: u32 = 2;
 
+// b-type starting garbage rows for each selectable height, as per the game boy
+const B_TYPE_HEIGHT_ROWS: [u32; 6] = [0, 3, 5, 8, 10, 12];
+pub const MAX_B_TYPE_HEIGHT: u32 = B_TYPE_HEIGHT_ROWS.len() as u32 - 1;
+const B_TYPE_POINTS_PER_BLOCK: u32 = 50;
+
 // pre-calculated step durations in ms: 1000 * (0.8 - (level as f64 * 0.007)).powi(level as i32)
 // doing it like this as hashmaps cannot be constant and fp logic is not yet supported at compile time
 const STEP_0: Duration = Duration::from_millis(1000);
@@ -83,6 +93,8 @@ pub struct Game {
     player: u32,
     board: Board,
     random: RandomTetromino,
+    ruleset: Ruleset,
+    start_level: u32,
     level: u32,
     lines: u32,
     score: u32,
@@ -106,12 +118,14 @@ pub struct GameMetrics {
 }
 
 impl Game {
-    pub fn new(player: u32, level: u32, mut random: RandomTetromino) -> Game {
+    pub fn new(player: u32, level: u32, mut random: RandomTetromino, ruleset: Ruleset) -> Game {
         let first_shape = random.next();
         Game {
             player,
-            board: Board::new(),
+            board: Board::with_ruleset(ruleset),
             random,
+            ruleset,
+            start_level: level,
             level,
             lines: 0,
             score: 0,
@@ -129,6 +143,10 @@ impl Game {
     }
 
     pub fn hold(&mut self) -> Option<GameEvent> {
+        if !self.ruleset.has_hold() {
+            return None;
+        }
+
         if !(matches!(self.state, GameState::Fall(_))
             || matches!(self.state, GameState::Lock(duration) if duration < LOCK_DURATION))
             || matches!(self.hold, Some(HoldState { locked: true, .. }))
@@ -165,6 +183,10 @@ impl Game {
     }
 
     pub fn hard_drop(&mut self) -> Option<GameEvent> {
+        if !self.ruleset.has_hard_drop() {
+            return None;
+        }
+
         self.board.hard_drop().map(|(hard_dropped_rows, minos)| {
             self.state = GameState::HardDropLock;
             self.score += hard_dropped_rows * HARD_DROP_POINTS_PER_ROW;
@@ -213,6 +235,22 @@ impl Game {
         }
     }
 
+    /// Fill the bottom of the board with random garbage for a b-type game
+    pub fn fill_b_type_garbage(&mut self, height: u32) {
+        let rows = B_TYPE_HEIGHT_ROWS[min(height, MAX_B_TYPE_HEIGHT) as usize];
+        for y in 0..rows {
+            let row = self.random.next_garbage_row();
+            self.board.set_garbage_row(y, row);
+        }
+    }
+
+    /// Award the b-type bonus for every block left in the stack, returns the bonus
+    pub fn add_b_type_bonus(&mut self) -> u32 {
+        let bonus = self.board.stack_block_count() * B_TYPE_POINTS_PER_BLOCK * (self.level + 1);
+        self.score += bonus;
+        bonus
+    }
+
     pub fn send_garbage(&mut self, rows: u32) {
         self.garbage_buffer += rows;
     }
@@ -319,8 +357,14 @@ impl Game {
         }
 
         if !self.board.step_down() {
-            // cannot step down, start lock
-            return (GameState::Lock(Duration::ZERO), None);
+            return if self.ruleset.is_classic() {
+                // no lock delay in classic, lock on the first gravity step that fails.
+                // locking now rather than entering the lock state means no movement can reset it
+                self.lock(LOCK_DURATION, false)
+            } else {
+                // cannot step down, start lock
+                (GameState::Lock(Duration::ZERO), None)
+            };
         }
 
         // has stepped down one row, update score if soft dropping
@@ -328,6 +372,11 @@ impl Game {
             self.score += SOFT_DROP_POINTS_PER_ROW;
         }
 
+        if self.ruleset.is_classic() {
+            // classic pieces keep falling until the next gravity step fails
+            return (GameState::Fall(Duration::ZERO), Some(GameEvent::Fall));
+        }
+
         if self.board.is_collision() {
             // step has caused a collision, start a lock
             let state = if self.board.lock_placements() >= MAX_LOCK_PLACEMENTS {
@@ -477,6 +526,23 @@ impl Game {
             _ => unreachable!(),
         };
 
+        if self.ruleset.is_classic() {
+            // no combos or back to back bonuses in classic
+            self.score += classic_line_points(line_count, self.level);
+            self.lines += line_count;
+            let line_level = classic_level(self.start_level, self.lines);
+            let level_up = line_level > self.level;
+            if level_up {
+                self.level = line_level;
+            }
+            return Some(GameEvent::Destroyed {
+                player: self.player,
+                lines: pattern,
+                send_garbage_lines: garbage_lines,
+                level_up,
+            });
+        }
+
         // update combo
         self.combo = match self.combo {
             None => Some(Combo {
@@ -527,11 +593,21 @@ impl Game {
         self.board.row(y)
     }
 
+    pub fn stack_height(&self) -> u32 {
+        self.board.stack_height()
+    }
+
     fn spawn_delay(&self) -> Duration {
+        if self.ruleset.is_classic() {
+            return classic_spawn_delay();
+        }
         min(self.base_delay(SOFT_DROP_SPAWN_FACTOR), MIN_SPAWN_DELAY)
     }
 
     fn step_delay(&self) -> Duration {
+        if self.ruleset.is_classic() {
+            return classic_step_delay(self.level, self.soft_drop);
+        }
         self.base_delay(SOFT_DROP_STEP_FACTOR)
     }
 
@@ -561,3 +637,37 @@ impl Game {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::random::RandomMode;
+    use crate::game::ruleset::Ruleset;
+
+    /// Gravity steps until the first tetromino locks, optionally moving it after every step
+    fn steps_to_lock(moving: bool) -> u32 {
+        let random = RandomMode::Nes.build(1, 1, 42).pop().unwrap();
+        let mut game = Game::new(0, 0, random, Ruleset::Classic);
+        let step = Duration::from_secs(1);
+        assert!(matches!(game.update(step), Some(GameEvent::Spawn { .. })));
+        for steps in 1..100 {
+            if let Some(GameEvent::Lock { .. }) = game.update(step) {
+                return steps;
+            }
+            if moving {
+                // alternate so the move always succeeds on the empty board
+                if steps % 2 == 0 {
+                    game.left();
+                } else {
+                    game.right();
+                }
+            }
+        }
+        panic!("tetromino never locked");
+    }
+
+    #[test]
+    fn classic_moves_do_not_delay_the_lock() {
+        assert_eq!(steps_to_lock(true), steps_to_lock(false));
+    }
+}
@@ -2,7 +2,7 @@ use super::tetromino::TetrominoShape;
 use crate::game::board::BOARD_WIDTH;
 use rand::prelude::*;
 use rand::seq::SliceRandom;
-use rand::{thread_rng, Rng};
+use rand::Rng;
 use rand_chacha::{ChaCha8Rng, ChaChaRng};
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
@@ -17,11 +17,20 @@ const ALL_SHAPES: [TetrominoShape; 7] = [
     TetrominoShape::J,
     TetrominoShape::L,
 ];
+const GARBAGE_ROW_FILL_PROBABILITY: f64 = 0.6;
 
 fn rand_shape<R: Rng>(rng: &mut R) -> TetrominoShape {
     ALL_SHAPES[rng.gen_range(0..ALL_SHAPES.len())]
 }
 
+/// NES rolls an 8 sided die, rerolling once if it hits the phantom 8th side or repeats the previous shape
+fn nes_shape<R: Rng>(rng: &mut R, previous: Option<TetrominoShape>) -> TetrominoShape {
+    match ALL_SHAPES.get(rng.gen_range(0..=ALL_SHAPES.len())) {
+        Some(shape) if Some(*shape) != previous => *shape,
+        _ => rand_shape(rng),
+    }
+}
+
 type Seed = <ChaCha8Rng as SeedableRng>::Seed;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -30,14 +39,29 @@ pub enum RandomMode {
     True,
     /// All tetrominoes placed in a shuffled "bag" and drawn until the bag is empty, after which a new bag is shuffled
     Bag,
+    /// NES style, random tetromino with a single re-roll on repeats
+    Nes,
+}
+
+impl RandomMode {
+    pub const ALL: [Self; 3] = [Self::True, Self::Bag, Self::Nes];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RandomMode::True => "true",
+            RandomMode::Bag => "bag",
+            RandomMode::Nes => "nes",
+        }
+    }
 }
 
 impl RandomMode {
-    pub fn build(self, count: usize, min_garbage_per_hole: u32) -> Vec<RandomTetromino> {
-        let mut seed: Seed = Default::default();
-        thread_rng().fill(&mut seed);
+    /// The same seed always deals the same tetrominoes & garbage
+    pub fn build(self, count: usize, min_garbage_per_hole: u32, seed: u64) -> Vec<RandomTetromino> {
+        let mut expanded: Seed = Default::default();
+        ChaCha8Rng::seed_from_u64(seed).fill(&mut expanded);
         (0..count)
-            .map(|_| RandomTetromino::new(self, min_garbage_per_hole, seed))
+            .map(|_| RandomTetromino::new(self, min_garbage_per_hole, expanded))
             .collect()
     }
 }
@@ -81,6 +105,21 @@ impl RandomTetromino {
                 result.assert_bags();
                 result
             }
+            RandomMode::Nes => {
+                let mut queue = VecDeque::new();
+                for _ in 0..PEEK_SIZE {
+                    let shape = nes_shape(&mut rng, queue.back().copied());
+                    queue.push_back(shape);
+                }
+                Self {
+                    random_mode,
+                    min_garbage_per_hole,
+                    garbage_since_last_hole: 0,
+                    current_garbage_hole,
+                    rng,
+                    queue,
+                }
+            }
         }
     }
 
@@ -94,13 +133,30 @@ impl RandomTetromino {
         result
     }
 
+    /// A row of garbage with random blocks missing, always has at least one hole
+    pub fn next_garbage_row(&mut self) -> [bool; BOARD_WIDTH as usize] {
+        let mut result = [false; BOARD_WIDTH as usize];
+        for filled in result.iter_mut() {
+            *filled = self.rng.gen_bool(GARBAGE_ROW_FILL_PROBABILITY);
+        }
+        result[self.rng.gen_range(0..BOARD_WIDTH) as usize] = false;
+        result
+    }
+
     pub fn next(&mut self) -> TetrominoShape {
         match self.random_mode {
             RandomMode::True => self.next_true(),
             RandomMode::Bag => self.next_bag(),
+            RandomMode::Nes => self.next_nes(),
         }
     }
 
+    fn next_nes(&mut self) -> TetrominoShape {
+        let shape = nes_shape(&mut self.rng, self.queue.back().copied());
+        self.queue.push_back(shape);
+        self.queue.pop_front().unwrap()
+    }
+
     fn next_true(&mut self) -> TetrominoShape {
         self.queue.push_back(rand_shape(&mut self.rng));
         self.queue.pop_front().unwrap()
@@ -138,6 +194,15 @@ impl RandomTetromino {
 #[cfg(test)]
 mod tests {
     use super::*;
+
+    #[test]
+    fn same_seed_deals_the_same_tetrominoes() {
+        let mut a = RandomMode::Bag.build(1, 10, 42).pop().unwrap();
+        let mut b = RandomMode::Bag.build(1, 10, 42).pop().unwrap();
+        for _ in 0..20 {
+            assert_eq!(a.next(), b.next());
+        }
+    }
     use std::collections::HashSet;
 
     fn next_n(random: &mut RandomTetromino, n: usize) -> Vec<TetrominoShape> {
@@ -150,7 +215,7 @@ mod tests {
 
     #[test]
     fn bag_random() {
-        let mut random = RandomMode::Bag.build(1, 10).pop().unwrap();
+        let mut random = RandomMode::Bag.build(1, 10, thread_rng().gen()).pop().unwrap();
 
         // chunk into 3 bags of 7 shapes (arrays make it easier for creating the sets)
         let bags: Vec<[TetrominoShape; 7]> = next_n(&mut random, 21)
@@ -171,7 +236,7 @@ mod tests {
 
     #[test]
     fn bag_random_peek() {
-        let mut random = RandomMode::Bag.build(1, 10).pop().unwrap();
+        let mut random = RandomMode::Bag.build(1, 10, thread_rng().gen()).pop().unwrap();
         let peek = random.peek();
         let observed: [TetrominoShape; PEEK_SIZE] =
             next_n(&mut random, PEEK_SIZE).try_into().unwrap();
@@ -180,7 +245,7 @@ mod tests {
 
     #[test]
     fn true_random() {
-        let mut random = RandomMode::True.build(1, 10).pop().unwrap();
+        let mut random = RandomMode::True.build(1, 10, thread_rng().gen()).pop().unwrap();
         let observed: [TetrominoShape; 1000] = next_n(&mut random, 1000).try_into().unwrap();
         // should generate all shapes in 1000 tries
         assert_eq!(HashSet::from(observed), HashSet::from(ALL_SHAPES));
@@ -188,7 +253,27 @@ mod tests {
 
     #[test]
     fn true_random_peek() {
-        let mut random = RandomMode::True.build(1, 10).pop().unwrap();
+        let mut random = RandomMode::True.build(1, 10, thread_rng().gen()).pop().unwrap();
+        let peek = random.peek();
+        let observed: [TetrominoShape; PEEK_SIZE] =
+            next_n(&mut random, PEEK_SIZE).try_into().unwrap();
+        assert_eq!(observed, peek);
+    }
+
+    #[test]
+    fn nes_random() {
+        let mut random = RandomMode::Nes.build(1, 10, thread_rng().gen()).pop().unwrap();
+        let observed = next_n(&mut random, 10000);
+        assert_eq!(HashSet::<TetrominoShape>::from_iter(observed.iter().copied()), HashSet::from(ALL_SHAPES));
+
+        // repeats are re-rolled once so should be much rarer than 1 in 7
+        let repeats = observed.windows(2).filter(|w| w[0] == w[1]).count();
+        assert!(repeats < observed.len() / 14, "{} repeats", repeats);
+    }
+
+    #[test]
+    fn nes_random_peek() {
+        let mut random = RandomMode::Nes.build(1, 10, thread_rng().gen()).pop().unwrap();
         let peek = random.peek();
         let observed: [TetrominoShape; PEEK_SIZE] =
             next_n(&mut random, PEEK_SIZE).try_into().unwrap();
@@ -197,14 +282,23 @@ mod tests {
 
     #[test]
     fn static_garbage_hole() {
-        let mut random = RandomMode::True.build(1, 100).pop().unwrap();
+        let mut random = RandomMode::True.build(1, 100, thread_rng().gen()).pop().unwrap();
         let observed: [u32; 100] = next_n_holes(&mut random, 100).try_into().unwrap();
         assert_eq!(HashSet::from(observed).len(), 1);
     }
 
+    #[test]
+    fn garbage_rows_have_holes() {
+        let mut random = RandomMode::True.build(1, 1, thread_rng().gen()).pop().unwrap();
+        for _ in 0..100 {
+            let row = random.next_garbage_row();
+            assert!(row.iter().any(|filled| !filled));
+        }
+    }
+
     #[test]
     fn dynamic_garbage_hole() {
-        let mut random = RandomMode::True.build(1, 1).pop().unwrap();
+        let mut random = RandomMode::True.build(1, 1, thread_rng().gen()).pop().unwrap();
         let observed: [u32; 100] = next_n_holes(&mut random, 100).try_into().unwrap();
         assert!(HashSet::from(observed).len() > 1);
     }
@@ -0,0 +1,148 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::min;
+use std::time::Duration;
+use strum::IntoEnumIterator;
+
+// the NES runs at ~60.0988 frames per second
+const NES_FRAME: Duration = Duration::from_nanos(16_639_267);
+const NES_SPAWN_DELAY_FRAMES: u32 = 10;
+const NES_SOFT_DROP_FRAMES: u32 = 2;
+const NES_LINES_PER_LEVEL: u32 = 10;
+
+// frames per row for each level, everything from level 29 is the "kill screen"
+const NES_GRAVITY_FRAMES: [u32; 30] = [
+    48, 43, 38, 33, 28, 23, 18, 13, 8, 6, // 0-9
+    5, 5, 5, 4, 4, 4, 3, 3, 3, 2, // 10-19
+    2, 2, 2, 2, 2, 2, 2, 2, 2, 1, // 20-29
+];
+
+const NES_LINE_POINTS: [u32; 4] = [40, 100, 300, 1200];
+
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    strum::IntoStaticStr,
+    strum::EnumIter,
+    strum::EnumString,
+)]
+pub enum Ruleset {
+    /// Modern guideline rules: hold, hard drop, ghost, 7-bag and SRS
+    #[default]
+    #[strum(serialize = "guideline")]
+    Guideline,
+    /// NES rules: no hold, hard drop or ghost, NES gravity, scoring, rotation and randomizer
+    #[strum(serialize = "classic")]
+    Classic,
+}
+
+impl Ruleset {
+    pub fn names() -> Vec<&'static str> {
+        Self::iter().map(|e| e.into()).collect()
+    }
+
+    pub fn is_classic(&self) -> bool {
+        self == &Ruleset::Classic
+    }
+
+    pub fn has_hold(&self) -> bool {
+        !self.is_classic()
+    }
+
+    pub fn has_hard_drop(&self) -> bool {
+        !self.is_classic()
+    }
+
+    pub fn has_ghost(&self) -> bool {
+        !self.is_classic()
+    }
+}
+
+/// Duration of a single row of NES gravity at the specified level
+pub fn classic_step_delay(level: u32, soft_drop: bool) -> Duration {
+    let frames = NES_GRAVITY_FRAMES[min(level as usize, NES_GRAVITY_FRAMES.len() - 1)];
+    if soft_drop {
+        NES_FRAME * min(frames, NES_SOFT_DROP_FRAMES)
+    } else {
+        NES_FRAME * frames
+    }
+}
+
+pub fn classic_spawn_delay() -> Duration {
+    NES_FRAME * NES_SPAWN_DELAY_FRAMES
+}
+
+pub fn classic_line_points(lines: u32, level: u32) -> u32 {
+    match lines {
+        0 => 0,
+        _ => NES_LINE_POINTS[lines as usize - 1] * (level + 1),
+    }
+}
+
+/// The NES only levels up from the starting level after some number of lines,
+/// after that it's every 10 lines as normal
+pub fn classic_level(start_level: u32, lines: u32) -> u32 {
+    let first_transition = min(
+        start_level * NES_LINES_PER_LEVEL + NES_LINES_PER_LEVEL,
+        (start_level * NES_LINES_PER_LEVEL).saturating_sub(50).max(100),
+    );
+    if lines < first_transition {
+        start_level
+    } else {
+        start_level + 1 + (lines - first_transition) / NES_LINES_PER_LEVEL
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classic_level_from_zero() {
+        assert_eq!(classic_level(0, 9), 0);
+        assert_eq!(classic_level(0, 10), 1);
+        assert_eq!(classic_level(0, 25), 2);
+    }
+
+    #[test]
+    fn classic_level_from_nine() {
+        assert_eq!(classic_level(9, 99), 9);
+        assert_eq!(classic_level(9, 100), 10);
+        assert_eq!(classic_level(9, 110), 11);
+    }
+
+    #[test]
+    fn classic_level_from_eighteen() {
+        assert_eq!(classic_level(18, 129), 18);
+        assert_eq!(classic_level(18, 130), 19);
+        assert_eq!(classic_level(18, 140), 20);
+    }
+
+    #[test]
+    fn classic_level_from_nineteen() {
+        assert_eq!(classic_level(19, 139), 19);
+        assert_eq!(classic_level(19, 140), 20);
+    }
+
+    #[test]
+    fn classic_points() {
+        assert_eq!(classic_line_points(0, 0), 0);
+        assert_eq!(classic_line_points(1, 0), 40);
+        assert_eq!(classic_line_points(2, 1), 200);
+        assert_eq!(classic_line_points(3, 2), 900);
+        assert_eq!(classic_line_points(4, 18), 22800);
+    }
+
+    #[test]
+    fn kill_screen() {
+        assert_eq!(classic_step_delay(29, false), NES_FRAME);
+        assert_eq!(classic_step_delay(100, false), NES_FRAME);
+        assert_eq!(classic_step_delay(0, false), NES_FRAME * 48);
+        assert_eq!(classic_step_delay(0, true), NES_FRAME * 2);
+    }
+}
@@ -457,6 +457,36 @@ impl Tetromino {
         self.translate_point(wall_kick);
     }
 
+    /// NES rotation has no wall kicks and I, S & Z only have two states
+    fn classic_rotation(&self, clockwise: bool) -> Rotation {
+        match self.shape() {
+            TetrominoShape::I | TetrominoShape::S | TetrominoShape::Z => {
+                if self.rotation == Rotation::North {
+                    Rotation::East
+                } else {
+                    Rotation::North
+                }
+            }
+            _ => self.rotation.rotate(clockwise),
+        }
+    }
+
+    pub fn minos_after_classic_rotation(&self, clockwise: bool) -> Minos {
+        let to_rotation = self.classic_rotation(clockwise);
+        // the first "kick" is the basic rotation
+        let offset = self.meta.wall_kicks(self.rotation, to_rotation)[0];
+        self.meta
+            .rotated_minos(to_rotation)
+            .map(|p| p + self.position + offset)
+    }
+
+    pub fn rotate_classic(&mut self, clockwise: bool) {
+        let to_rotation = self.classic_rotation(clockwise);
+        let offset = self.meta.wall_kicks(self.rotation, to_rotation)[0];
+        self.rotation = to_rotation;
+        self.translate_point(offset);
+    }
+
     fn translate_point(&mut self, p: Point) {
         self.position += p;
         self.minos = self
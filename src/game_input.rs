@@ -1,6 +1,7 @@
-use crate::config::InputConfig;
+use crate::config::{BoundKey, InputConfig, PadInput};
+use crate::gamepad::InputEvent;
 use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
+use sdl2::keyboard::{Keycode, Scancode};
 use std::collections::HashMap;
 use std::time::Duration;
 
@@ -16,10 +17,28 @@ pub enum GameInputKey {
     RotateClockwise { player: u32 },
     RotateAnticlockwise { player: u32 },
     Hold { player: u32 },
+    NextPlayerTheme { player: u32 },
     Pause,
     ReturnToMenu,
     Quit,
     NextTheme,
+    Retry,
+}
+
+impl GameInputKey {
+    pub fn player(&self) -> Option<u32> {
+        match *self {
+            GameInputKey::MoveLeft { player }
+            | GameInputKey::MoveRight { player }
+            | GameInputKey::SoftDrop { player }
+            | GameInputKey::HardDrop { player }
+            | GameInputKey::RotateClockwise { player }
+            | GameInputKey::RotateAnticlockwise { player }
+            | GameInputKey::Hold { player }
+            | GameInputKey::NextPlayerTheme { player } => Some(player),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Hash, Clone, Copy, Debug, PartialEq, Eq)]
@@ -27,6 +46,8 @@ struct GameInput {
     key: GameInputKey,
     duration: Duration,
     repeating: bool,
+    /// number of bindings holding the key, e.g. both the d-pad & stick
+    held: u32,
 }
 
 impl GameInput {
@@ -35,11 +56,13 @@ impl GameInput {
             key,
             duration: Duration::ZERO,
             repeating: false,
+            held: 1,
         }
     }
 }
 
-type KeyMapping = HashMap<Keycode, GameInputKey>;
+type KeyMapping = HashMap<BoundKey, GameInputKey>;
+type PadMapping = HashMap<(u32, PadInput), GameInputKey>;
 
 enum MaybeKey {
     Down(GameInputKey),
@@ -49,20 +72,22 @@ enum MaybeKey {
 
 pub struct GameInputContext {
     mapping: KeyMapping,
+    pad_mapping: PadMapping,
     current: HashMap<GameInputKey, GameInput>,
 }
 
 impl GameInputContext {
-    pub fn new(config: InputConfig) -> Self {
-        Self {
-            mapping: config.game_map(),
+    pub fn new(config: &InputConfig) -> Result<Self, String> {
+        Ok(Self {
+            mapping: config.game_map()?,
+            pad_mapping: config.gamepad_game_map(),
             current: HashMap::new(),
-        }
+        })
     }
 
-    pub fn update<I>(&mut self, delta: Duration, sdl_events: I) -> Vec<GameInputKey>
+    pub fn update<I>(&mut self, delta: Duration, events: I) -> Vec<GameInputKey>
     where
-        I: Iterator<Item = Event>,
+        I: Iterator<Item = InputEvent>,
     {
         let mut result: Vec<GameInputKey> = vec![];
 
@@ -71,16 +96,37 @@ impl GameInputContext {
             event.duration += delta;
         }
 
-        for sdl_event in sdl_events {
-            match self.map_from_sdl_event(sdl_event) {
+        for event in events {
+            let maybe_key = match event {
+                InputEvent::Sdl(sdl_event) => self.map_from_sdl_event(sdl_event),
+                InputEvent::Pad {
+                    player,
+                    input,
+                    pressed,
+                } => match self.pad_mapping.get(&(player, input)) {
+                    None => MaybeKey::None,
+                    Some(key) if pressed => MaybeKey::Down(*key),
+                    Some(key) => MaybeKey::Up(*key),
+                },
+            };
+            match maybe_key {
                 MaybeKey::None => {}
-                MaybeKey::Down(key) => {
-                    let event = GameInput::new(key);
-                    self.current.insert(key, event);
-                    result.push(key);
-                }
+                MaybeKey::Down(key) => match self.current.get_mut(&key) {
+                    // already held by another binding, carry on repeating
+                    Some(event) => event.held += 1,
+                    None => {
+                        self.current.insert(key, GameInput::new(key));
+                        result.push(key);
+                    }
+                },
                 MaybeKey::Up(key) => {
-                    self.current.remove(&key);
+                    // released once every binding holding it is released
+                    if let Some(event) = self.current.get_mut(&key) {
+                        event.held -= 1;
+                        if event.held == 0 {
+                            self.current.remove(&key);
+                        }
+                    }
                 }
             };
         }
@@ -115,22 +161,64 @@ impl GameInputContext {
         match event {
             Event::Quit { .. } => MaybeKey::Down(GameInputKey::Quit),
             Event::KeyDown {
-                keycode: Some(keycode),
+                keycode,
+                scancode,
                 repeat: false,
                 ..
-            } => match self.mapping.get(&keycode) {
+            } => match self.find_key(keycode, scancode) {
                 None => MaybeKey::None,
-                Some(key) => MaybeKey::Down(*key),
+                Some(key) => MaybeKey::Down(key),
             },
             Event::KeyUp {
-                keycode: Some(keycode),
+                keycode,
+                scancode,
                 repeat: false,
                 ..
-            } => match self.mapping.get(&keycode) {
+            } => match self.find_key(keycode, scancode) {
                 None => MaybeKey::None,
-                Some(key) => MaybeKey::Up(*key),
+                Some(key) => MaybeKey::Up(key),
             },
             _ => MaybeKey::None,
         }
     }
+
+    fn find_key(&self, keycode: Option<Keycode>, scancode: Option<Scancode>) -> Option<GameInputKey> {
+        BoundKey::from_event(keycode, scancode)
+            .into_iter()
+            .find_map(|bound| self.mapping.get(&bound).copied())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, PadAxis, PadButton};
+
+    fn pad(input: PadInput, pressed: bool) -> InputEvent {
+        InputEvent::Pad {
+            player: 1,
+            input,
+            pressed,
+        }
+    }
+
+    #[test]
+    fn key_held_by_two_bindings_is_released_by_the_last() {
+        let mut context = GameInputContext::new(&Config::default().input).unwrap();
+        let dpad = PadInput::Button(PadButton::DPadLeft);
+        let stick = PadInput::AxisNegative(PadAxis::LeftX);
+        let left = GameInputKey::MoveLeft { player: 1 };
+
+        assert_eq!(context.update(Duration::ZERO, [pad(dpad, true)].into_iter()), vec![left]);
+        // the second binding neither moves again nor restarts the auto repeat
+        assert!(context.update(AUTO_REPEAT_DELAY / 2, [pad(stick, true)].into_iter()).is_empty());
+        assert_eq!(context.update(AUTO_REPEAT_DELAY / 2, std::iter::empty()), vec![left]);
+
+        // still held by the stick
+        context.update(Duration::ZERO, [pad(dpad, false)].into_iter());
+        assert_eq!(context.update(AUTO_REPEAT_ITERATION, std::iter::empty()), vec![left]);
+
+        context.update(Duration::ZERO, [pad(stick, false)].into_iter());
+        assert!(context.update(AUTO_REPEAT_DELAY, std::iter::empty()).is_empty());
+    }
 }
@@ -0,0 +1,364 @@
+use crate::config::{PadAxis, PadButton, PadInput};
+use sdl2::controller::GameController;
+use sdl2::event::Event;
+use sdl2::joystick::{HatState, Joystick};
+use sdl2::{GameControllerSubsystem, JoystickSubsystem};
+use std::collections::{HashMap, HashSet};
+
+/// An sdl event or a controller input, controller inputs are tagged with the player of the slot they are connected to
+#[derive(Clone, Debug)]
+pub enum InputEvent {
+    Sdl(Event),
+    Pad {
+        player: u32,
+        input: PadInput,
+        pressed: bool,
+    },
+}
+
+/// Connected game controllers, each controller is assigned to the first free player slot as it is connected.
+/// Joysticks without an sdl controller mapping fall back to a basic layout, see `joystick_button` & `joystick_axis`
+pub struct Gamepads {
+    subsystem: GameControllerSubsystem,
+    joystick_subsystem: JoystickSubsystem,
+    deadzone: f64,
+    slots: Vec<Option<u32>>,
+    controllers: HashMap<u32, GameController>,
+    joysticks: HashMap<u32, Joystick>,
+    buttons: HashSet<(u32, PadButton)>,
+    axes: HashMap<(u32, PadAxis), Option<bool>>,
+}
+
+impl Gamepads {
+    pub fn new(
+        subsystem: GameControllerSubsystem,
+        joystick_subsystem: JoystickSubsystem,
+        deadzone: f64,
+    ) -> Self {
+        Self {
+            subsystem,
+            joystick_subsystem,
+            deadzone,
+            slots: vec![],
+            controllers: HashMap::new(),
+            joysticks: HashMap::new(),
+            buttons: HashSet::new(),
+            axes: HashMap::new(),
+        }
+    }
+
+    pub fn set_deadzone(&mut self, deadzone: f64) {
+        self.deadzone = deadzone;
+    }
+
+    /// Tracks hot plugging & translates controller buttons & axes into presses & releases
+    pub fn translate(&mut self, event: Event) -> Vec<InputEvent> {
+        match event {
+            Event::ControllerDeviceAdded { which, .. } => {
+                match self.subsystem.open(which) {
+                    Ok(controller) => {
+                        let id = controller.instance_id();
+                        if !self.controllers.contains_key(&id) {
+                            let slot = assign_slot(&mut self.slots, id);
+                            println!("{} connected as player {}", controller.name(), slot + 1);
+                            self.controllers.insert(id, controller);
+                        }
+                    }
+                    Err(e) => println!("cannot open controller {}: {}", which, e),
+                }
+                vec![]
+            }
+            Event::ControllerDeviceRemoved { which, .. } => {
+                if let Some(controller) = self.controllers.remove(&which) {
+                    println!("{} disconnected", controller.name());
+                }
+                self.disconnect(which)
+            }
+            Event::ControllerButtonDown { which, button, .. } => {
+                self.button(which, PadButton::from(button), true)
+            }
+            Event::ControllerButtonUp { which, button, .. } => {
+                self.button(which, PadButton::from(button), false)
+            }
+            Event::ControllerAxisMotion {
+                which, axis, value, ..
+            } => self.axis(which, PadAxis::from(axis), value),
+            // controllers also send joystick events, only joysticks opened here as a fallback are translated
+            Event::JoyDeviceAdded { which, .. } if !self.subsystem.is_game_controller(which) => {
+                match self.joystick_subsystem.open(which) {
+                    Ok(joystick) => {
+                        let id = joystick.instance_id();
+                        if !self.joysticks.contains_key(&id) {
+                            let slot = assign_slot(&mut self.slots, id);
+                            println!("{} connected as player {} without a controller mapping", joystick.name(), slot + 1);
+                            self.joysticks.insert(id, joystick);
+                        }
+                    }
+                    Err(e) => println!("cannot open joystick {}: {}", which, e),
+                }
+                vec![]
+            }
+            Event::JoyDeviceRemoved { which, .. } if self.joysticks.contains_key(&which) => {
+                if let Some(joystick) = self.joysticks.remove(&which) {
+                    println!("{} disconnected", joystick.name());
+                }
+                self.disconnect(which)
+            }
+            Event::JoyButtonDown { which, button_idx, .. } if self.joysticks.contains_key(&which) => {
+                joystick_button(button_idx)
+                    .map(|button| self.button(which, button, true))
+                    .unwrap_or_default()
+            }
+            Event::JoyButtonUp { which, button_idx, .. } if self.joysticks.contains_key(&which) => {
+                joystick_button(button_idx)
+                    .map(|button| self.button(which, button, false))
+                    .unwrap_or_default()
+            }
+            Event::JoyAxisMotion {
+                which, axis_idx, value, ..
+            } if self.joysticks.contains_key(&which) => joystick_axis(axis_idx)
+                .map(|axis| self.axis(which, axis, value))
+                .unwrap_or_default(),
+            Event::JoyHatMotion {
+                which, hat_idx: 0, state, ..
+            } if self.joysticks.contains_key(&which) => {
+                let pressed = hat_buttons(state);
+                [PadButton::DPadUp, PadButton::DPadDown, PadButton::DPadLeft, PadButton::DPadRight]
+                    .into_iter()
+                    .flat_map(|button| self.button(which, button, pressed.contains(&button)))
+                    .collect()
+            }
+            event => vec![InputEvent::Sdl(event)],
+        }
+    }
+
+    /// A button press or release, repeated presses or releases of the same button are dropped
+    fn button(&mut self, instance_id: u32, button: PadButton, pressed: bool) -> Vec<InputEvent> {
+        let Some(player) = self.player(instance_id) else {
+            return vec![];
+        };
+        let changed = if pressed {
+            self.buttons.insert((player, button))
+        } else {
+            self.buttons.remove(&(player, button))
+        };
+        if !changed {
+            return vec![];
+        }
+        vec![InputEvent::Pad {
+            player,
+            input: PadInput::Button(button),
+            pressed,
+        }]
+    }
+
+    fn axis(&mut self, instance_id: u32, axis: PadAxis, value: i16) -> Vec<InputEvent> {
+        let Some(player) = self.player(instance_id) else {
+            return vec![];
+        };
+        let next = axis_direction(value, self.deadzone);
+        let previous = self.axes.insert((player, axis), next).flatten();
+        axis_transitions(axis, previous, next)
+            .into_iter()
+            .map(|(input, pressed)| InputEvent::Pad { player, input, pressed })
+            .collect()
+    }
+
+    /// Frees the slot of a disconnected controller or joystick,
+    /// releasing anything held so e.g. soft drop doesn't stay pressed after unplugging
+    fn disconnect(&mut self, instance_id: u32) -> Vec<InputEvent> {
+        let Some(player) = release_slot(&mut self.slots, instance_id) else {
+            return vec![];
+        };
+        let mut result: Vec<InputEvent> = release_buttons(&mut self.buttons, player)
+            .into_iter()
+            .map(|button| InputEvent::Pad {
+                player,
+                input: PadInput::Button(button),
+                pressed: false,
+            })
+            .collect();
+        self.axes.retain(|&(axis_player, axis), direction| {
+            if axis_player != player {
+                return true;
+            }
+            if let Some(input) = direction.map(|positive| axis_input(axis, positive)) {
+                result.push(InputEvent::Pad { player, input, pressed: false });
+            }
+            false
+        });
+        result
+    }
+
+    /// The player number of a connected controller by its instance id
+    fn player(&self, instance_id: u32) -> Option<u32> {
+        self.slots
+            .iter()
+            .position(|&slot| slot == Some(instance_id))
+            .map(|index| index as u32 + 1)
+    }
+}
+
+/// Assigns the controller to the first free slot, returning the zero based slot index
+fn assign_slot(slots: &mut Vec<Option<u32>>, instance_id: u32) -> usize {
+    match slots.iter().position(|slot| slot.is_none()) {
+        Some(index) => {
+            slots[index] = Some(instance_id);
+            index
+        }
+        None => {
+            slots.push(Some(instance_id));
+            slots.len() - 1
+        }
+    }
+}
+
+/// Frees the slot of a disconnected controller, returning the player number it was assigned to
+fn release_slot(slots: &mut [Option<u32>], instance_id: u32) -> Option<u32> {
+    let index = slots.iter().position(|&slot| slot == Some(instance_id))?;
+    slots[index] = None;
+    Some(index as u32 + 1)
+}
+
+/// Removes the held buttons of a player, returning them in a stable order
+fn release_buttons(buttons: &mut HashSet<(u32, PadButton)>, player: u32) -> Vec<PadButton> {
+    let mut released: Vec<PadButton> = buttons
+        .iter()
+        .filter(|(button_player, _)| *button_player == player)
+        .map(|&(_, button)| button)
+        .collect();
+    released.sort_by_key(|&button| button as i32);
+    buttons.retain(|(button_player, _)| *button_player != player);
+    released
+}
+
+/// The button of an unmapped joystick, the face buttons then shoulders, back & start as most pads number them
+fn joystick_button(index: u8) -> Option<PadButton> {
+    match index {
+        0 => Some(PadButton::A),
+        1 => Some(PadButton::B),
+        2 => Some(PadButton::X),
+        3 => Some(PadButton::Y),
+        4 => Some(PadButton::LeftShoulder),
+        5 => Some(PadButton::RightShoulder),
+        6 => Some(PadButton::Back),
+        7 => Some(PadButton::Start),
+        _ => None,
+    }
+}
+
+/// The stick axis of an unmapped joystick, the first two axes are the left stick & the next two the right
+fn joystick_axis(index: u8) -> Option<PadAxis> {
+    match index {
+        0 => Some(PadAxis::LeftX),
+        1 => Some(PadAxis::LeftY),
+        2 => Some(PadAxis::RightX),
+        3 => Some(PadAxis::RightY),
+        _ => None,
+    }
+}
+
+/// The d-pad buttons pressed by a joystick hat position
+fn hat_buttons(state: HatState) -> Vec<PadButton> {
+    match state {
+        HatState::Centered => vec![],
+        HatState::Up => vec![PadButton::DPadUp],
+        HatState::Down => vec![PadButton::DPadDown],
+        HatState::Left => vec![PadButton::DPadLeft],
+        HatState::Right => vec![PadButton::DPadRight],
+        HatState::LeftUp => vec![PadButton::DPadLeft, PadButton::DPadUp],
+        HatState::LeftDown => vec![PadButton::DPadLeft, PadButton::DPadDown],
+        HatState::RightUp => vec![PadButton::DPadRight, PadButton::DPadUp],
+        HatState::RightDown => vec![PadButton::DPadRight, PadButton::DPadDown],
+    }
+}
+
+/// The pressed direction of an axis, true if positive & false if negative, none within the deadzone
+fn axis_direction(value: i16, deadzone: f64) -> Option<bool> {
+    let normalized = value as f64 / i16::MAX as f64;
+    if normalized >= deadzone {
+        Some(true)
+    } else if normalized <= -deadzone {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+fn axis_input(axis: PadAxis, positive: bool) -> PadInput {
+    if positive {
+        PadInput::AxisPositive(axis)
+    } else {
+        PadInput::AxisNegative(axis)
+    }
+}
+
+fn axis_transitions(
+    axis: PadAxis,
+    previous: Option<bool>,
+    next: Option<bool>,
+) -> Vec<(PadInput, bool)> {
+    if previous == next {
+        return vec![];
+    }
+    let mut result = vec![];
+    if let Some(positive) = previous {
+        result.push((axis_input(axis, positive), false));
+    }
+    if let Some(positive) = next {
+        result.push((axis_input(axis, positive), true));
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reconnected_controllers_fill_the_first_free_slot() {
+        let mut slots = vec![];
+        assert_eq!(assign_slot(&mut slots, 10), 0);
+        assert_eq!(assign_slot(&mut slots, 11), 1);
+        assert_eq!(release_slot(&mut slots, 10), Some(1));
+        assert_eq!(release_slot(&mut slots, 10), None);
+        assert_eq!(assign_slot(&mut slots, 12), 0);
+        assert_eq!(slots, vec![Some(12), Some(11)]);
+    }
+
+    #[test]
+    fn unplugging_releases_only_that_players_buttons() {
+        let mut buttons = HashSet::from([
+            (1, PadButton::DPadDown),
+            (1, PadButton::A),
+            (2, PadButton::B),
+        ]);
+        assert_eq!(release_buttons(&mut buttons, 1), vec![PadButton::A, PadButton::DPadDown]);
+        assert_eq!(release_buttons(&mut buttons, 1), vec![]);
+        assert_eq!(buttons, HashSet::from([(2, PadButton::B)]));
+    }
+
+    #[test]
+    fn axis_direction_respects_deadzone() {
+        assert_eq!(axis_direction(0, 0.5), None);
+        assert_eq!(axis_direction(i16::MAX / 4, 0.5), None);
+        assert_eq!(axis_direction(i16::MAX, 0.5), Some(true));
+        assert_eq!(axis_direction(i16::MIN, 0.5), Some(false));
+    }
+
+    #[test]
+    fn axis_crossing_centre_releases_then_presses() {
+        assert_eq!(axis_transitions(PadAxis::LeftX, Some(true), Some(true)), vec![]);
+        assert_eq!(
+            axis_transitions(PadAxis::LeftX, None, Some(false)),
+            vec![(PadInput::AxisNegative(PadAxis::LeftX), true)]
+        );
+        assert_eq!(
+            axis_transitions(PadAxis::LeftX, Some(false), Some(true)),
+            vec![
+                (PadInput::AxisNegative(PadAxis::LeftX), false),
+                (PadInput::AxisPositive(PadAxis::LeftX), true),
+            ]
+        );
+    }
+}
@@ -1,14 +1,52 @@
+use crate::config::GameConfig;
+
 pub mod render;
 pub mod table;
 
+/// Which high score table a game should be recorded against.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum HighScoreCategory {
+    /// Shared table for all a-type modes
+    Default,
+    /// B-type keeps a table per starting level & height
+    BType { level: u32, height: u32 },
+}
+
+impl HighScoreCategory {
+    pub fn from_game_config(game_config: GameConfig) -> Self {
+        if game_config.rules.is_b_type() {
+            Self::BType {
+                level: game_config.level,
+                height: game_config.height,
+            }
+        } else {
+            Self::Default
+        }
+    }
+
+    pub fn title(&self) -> String {
+        match self {
+            HighScoreCategory::Default => "High Scores".to_string(),
+            HighScoreCategory::BType { level, height } => {
+                format!("B-Type L{} H{}", level, height)
+            }
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct NewHighScore {
     pub player: u32,
     pub score: u32,
+    pub category: HighScoreCategory,
 }
 
 impl NewHighScore {
-    pub fn new(player: u32, score: u32) -> Self {
-        Self { player, score }
+    pub fn new(player: u32, score: u32, category: HighScoreCategory) -> Self {
+        Self {
+            player,
+            score,
+            category,
+        }
     }
 }
@@ -2,7 +2,7 @@ use crate::high_score::table::{HighScore, HighScoreTable};
 
 use crate::event::HighScoreEntryEvent;
 use crate::font::{FontTexture, FontType};
-use crate::high_score::NewHighScore;
+use crate::high_score::{HighScoreCategory, NewHighScore};
 use sdl2::pixels::Color;
 use sdl2::rect::{Point, Rect};
 use sdl2::render::{BlendMode, Texture, TextureCreator, WindowCanvas};
@@ -10,6 +10,7 @@ use sdl2::ttf::{Font, Sdl2TtfContext};
 use sdl2::video::WindowContext;
 use std::cmp::min;
 use crate::theme::helper::{CanvasRenderer, TextureFactory};
+use crate::ui::{scroll_to, UiStyle};
 
 const NAME_CHARACTERS: usize = 5;
 const CARET_HEIGHT: u32 = 2;
@@ -163,8 +164,12 @@ pub struct HighScoreRender<'a, 'ttf> {
     padding: u32,
     width: u32,
     rect: Rect,
+    /// offset of the visible rows when the table doesn't fit in the window
+    scroll: u32,
+    content_height: u32,
     entry: Option<Entry>,
     font: Font<'ttf, 'ttf>,
+    style: UiStyle,
 }
 
 /// TODO music
@@ -174,12 +179,14 @@ impl<'a, 'ttf> HighScoreRender<'a, 'ttf> {
         ttf: &'ttf Sdl2TtfContext,
         texture_creator: &'a TextureCreator<WindowContext>,
         (window_width, window_height): (u32, u32),
+        category: HighScoreCategory,
         new_high_score: Option<NewHighScore>,
+        style: UiStyle,
     ) -> Result<Self, String> {
-        let font_size = window_width / 32;
+        let font_size = style.font_size(window_width, 32);
         let font_header = FontType::Bold.load(ttf, font_size)?;
         let font_body = FontType::Mono.load(ttf, font_size)?;
-        let font_title = FontType::Retro.load(ttf, window_width / 24)?;
+        let font_title = FontType::Retro.load(ttf, style.font_size(window_width, 24))?;
 
         let (table, entry) = if let Some(new_high_score) = new_high_score {
             let score_index = table
@@ -214,6 +221,10 @@ impl<'a, 'ttf> HighScoreRender<'a, 'ttf> {
                 &row.score.to_string(),
             )?);
         }
+        if table.is_empty() {
+            // a b-type level & height that hasn't been played
+            rows.push(HighScoreTableRow::new(&font_body, texture_creator, "-", "no scores yet", "-")?);
+        }
 
         let n_rows = rows.len() as u32;
         if n_rows == 0 {
@@ -228,27 +239,34 @@ impl<'a, 'ttf> HighScoreRender<'a, 'ttf> {
             ordinal_column_width + padding + name_column_width + padding + score_column_width;
         // all rows will be same height as the tallest row
         let row_height = rows.iter().map(|r| r.height()).max().unwrap();
-        let height = n_rows * row_height + (n_rows - 1) * padding;
-        let texture = texture_creator.create_texture_target_blended(width, height)?;
-        let rect = Rect::from_center(
-            Point::new(window_width as i32 / 2, window_height as i32 / 2),
-            width,
-            height,
-        );
+        let content_height = n_rows * row_height;
 
         let title_text = entry
             .as_ref()
             .map(|e| e.title_text())
-            .unwrap_or("High Scores".to_string());
+            .unwrap_or(category.title());
         let title =
             FontTexture::from_string(&font_title, texture_creator, &title_text, FONT_COLOR)?;
         let title_rect = Rect::new(
-            (window_width - title.width) as i32 / 2,
+            (window_width as i32 - title.width as i32) / 2,
             padding as i32,
             title.width,
             title.height,
         );
 
+        // the rows scroll below the title when they don't fit
+        let top = title_rect.bottom() + padding as i32;
+        let height = content_height.min((window_height as i32 - top - padding as i32).max(row_height as i32) as u32);
+        let texture = texture_creator.create_texture_target_blended(width, height)?;
+        let mut rect = Rect::from_center(
+            Point::new(window_width as i32 / 2, window_height as i32 / 2),
+            width,
+            height,
+        );
+        if rect.top() < top {
+            rect.set_y(top);
+        }
+
         Ok(Self {
             texture_creator,
             rows,
@@ -260,8 +278,11 @@ impl<'a, 'ttf> HighScoreRender<'a, 'ttf> {
             padding,
             ordinal_column_width,
             rect,
+            scroll: 0,
+            content_height,
             entry,
             font: font_body,
+            style,
         })
     }
 
@@ -309,11 +330,32 @@ impl<'a, 'ttf> HighScoreRender<'a, 'ttf> {
     }
 
     pub fn draw(&mut self, canvas: &mut WindowCanvas) -> Result<(), String> {
+        // keep the new entry in view
+        if let Some(entry) = self.entry.as_ref() {
+            let row_top = (entry.ordinal as u32 + 1) * self.row_height;
+            self.scroll = scroll_to(
+                self.scroll,
+                row_top,
+                row_top + self.row_height,
+                self.rect.height(),
+                self.content_height,
+            );
+        }
+
+        if let Some(panel_color) = self.style.panel_color() {
+            let mut panel = self.rect;
+            panel.offset(-(self.padding as i32), -(self.padding as i32));
+            panel.resize(panel.width() + 2 * self.padding, panel.height() + 2 * self.padding);
+            canvas.set_draw_color(panel_color);
+            canvas.fill_rect(panel)?;
+        }
+
+        let caret_color = self.style.caret_color();
         canvas
             .with_texture_canvas(&mut self.texture, |c| {
                 c.clear_0();
 
-                let mut y = 0;
+                let mut y = -(self.scroll as i32);
                 for (i, row) in self.rows.iter().enumerate() {
                     c.copy(
                         &row.ordinal.texture,
@@ -332,7 +374,7 @@ impl<'a, 'ttf> HighScoreRender<'a, 'ttf> {
 
                     if let Some(entry) = self.entry.as_ref() {
                         if entry.ordinal + 1 == i {
-                            c.set_draw_color(Color::RED);
+                            c.set_draw_color(caret_color);
                             let caret = entry.current_caret();
                             c.fill_rect(Rect::new(
                                 name_rect.x() + caret.x(),
@@ -1,8 +1,12 @@
 use crate::config::config_path;
+use crate::migration::{LoadError, Loaded, HIGH_SCORE_SCHEMA};
+use crate::high_score::HighScoreCategory;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 const MAX_HIGH_SCORES: usize = 5;
 const CONFIG_NAME: &str = "high_scores";
+const B_TYPE_CONFIG_NAME: &str = "b_type_high_scores";
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct HighScore {
@@ -42,20 +46,80 @@ impl Default for HighScoreTable {
     }
 }
 
-impl HighScoreTable {
-    pub fn load() -> Result<Self, String> {
-        let config_path = config_path(CONFIG_NAME)?;
+/// B-type tables are stored together in a single file, keyed by starting level & height
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct BTypeHighScoreTables {
+    tables: BTreeMap<String, HighScoreTable>,
+}
+
+impl BTypeHighScoreTables {
+    fn key(level: u32, height: u32) -> String {
+        format!("level {} height {}", level, height)
+    }
+
+    fn load() -> Result<Self, String> {
+        let config_path = config_path(B_TYPE_CONFIG_NAME)?;
         #[cfg(debug_assertions)]
-        println!("loading high_scores: {}", config_path.to_str().unwrap());
-        let mut result: Self = confy::load_path(config_path).map_err(|e| e.to_string())?;
+        println!("loading b-type high_scores: {}", config_path.to_str().unwrap());
+        load_versioned(&config_path)
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let config_path = config_path(B_TYPE_CONFIG_NAME)?;
+        HIGH_SCORE_SCHEMA.store(&config_path, self)
+    }
+}
+
+/// Loads a high score file, upgrading it in place if it is from an older version
+fn load_versioned<T>(path: &std::path::Path) -> Result<T, String>
+where
+    T: serde::de::DeserializeOwned + Serialize + Default,
+{
+    let loaded: Loaded<T> = HIGH_SCORE_SCHEMA.load(path).map_err(|e| match e {
+        LoadError::Yaml(error) => format!("{}: {}", path.display(), error),
+        LoadError::Io(error) => error,
+    })?;
+    HIGH_SCORE_SCHEMA.upgrade_file(path, &loaded)?;
+    Ok(loaded.value)
+}
+
+impl HighScoreTable {
+    pub fn load(category: HighScoreCategory) -> Result<Self, String> {
+        let mut result = match category {
+            HighScoreCategory::Default => {
+                let config_path = config_path(CONFIG_NAME)?;
+                #[cfg(debug_assertions)]
+                println!("loading high_scores: {}", config_path.to_str().unwrap());
+                load_versioned(&config_path)?
+            }
+            HighScoreCategory::BType { level, height } => BTypeHighScoreTables::load()?
+                .tables
+                .remove(&BTypeHighScoreTables::key(level, height))
+                .unwrap_or(Self { scores: vec![] }),
+        };
         result.sorted();
         result.scores = result.scores.into_iter().take(MAX_HIGH_SCORES).collect();
         Ok(result)
     }
 
-    pub fn save(&self) -> Result<(), String> {
-        let config_path = config_path(CONFIG_NAME)?;
-        confy::store_path(config_path, self).map_err(|e| e.to_string())
+    pub fn save(&self, category: HighScoreCategory) -> Result<(), String> {
+        match category {
+            HighScoreCategory::Default => {
+                let config_path = config_path(CONFIG_NAME)?;
+                HIGH_SCORE_SCHEMA.store(&config_path, self)
+            }
+            HighScoreCategory::BType { level, height } => {
+                let mut tables = BTypeHighScoreTables::load()?;
+                tables
+                    .tables
+                    .insert(BTypeHighScoreTables::key(level, height), self.clone());
+                tables.save()
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scores.is_empty()
     }
 
     pub fn entries(&self) -> &[HighScore] {
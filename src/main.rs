@@ -1,41 +1,60 @@
 #![windows_subsystem = "windows"]
 
 mod animation;
+mod cli;
 mod build_info {
     include!(concat!(env!("OUT_DIR"), "/built.rs"));
 }
 mod config;
+mod controls;
+mod crt;
 mod event;
 mod font;
 mod frame_rate;
 mod game;
 mod game_input;
+mod gamepad;
 mod high_score;
 mod menu;
 mod menu_input;
+mod migration;
 mod particles;
 mod paused;
 mod player;
 mod scale;
+mod settings;
 mod theme;
 mod theme_context;
+mod ui;
+mod watch;
 mod icon;
 
 extern crate sdl2;
 
 use crate::animation::game_over::GameOverAnimate;
 use crate::animation::hard_drop::HardDropAnimation;
-use crate::config::{Config, GameConfig, MatchRules, MatchThemes, VideoMode};
+use crate::cli::{Cli, Command, StartMatch, USAGE};
+use crate::config::{
+    config_path, set_config_path, Config, CrtConfig, GameConfig, GameKey, KeyBinding, MatchRules,
+    MatchThemes, ScanKey, ThemeSwitch, VideoConfig, VideoMode,
+};
+use crate::controls::{
+    controls_menu, ControlAction, BIND_BY, BIND_BY_POSITION, CANCEL_CONTROLS, RESET_CONTROLS, SAVE_CONTROLS,
+};
 use crate::event::{GameEvent, HighScoreEntryEvent};
+use crate::game::ruleset::Ruleset;
+use crate::game::{MAX_B_TYPE_HEIGHT, MAX_START_LEVEL};
 use crate::game_input::GameInputKey;
+use crate::gamepad::{Gamepads, InputEvent};
 use crate::high_score::render::HighScoreRender;
 use crate::high_score::table::HighScoreTable;
 use crate::menu::{Menu, MenuItem};
 use crate::menu_input::{MenuInputContext, MenuInputKey};
 use crate::player::MatchState;
+use crate::settings::{video_modes, Settings, CANCEL_SETTINGS, SAVE_SETTINGS};
 
 use crate::frame_rate::FrameRate;
-use crate::high_score::NewHighScore;
+use crate::high_score::{HighScoreCategory, NewHighScore};
 
 use crate::particles::prescribed::{
     prescribed_fireworks, prescribed_orbit, prescribed_tetromino_race,
@@ -43,12 +62,22 @@ use crate::particles::prescribed::{
 use crate::particles::render::ParticleRender;
 use crate::particles::source::ParticleSource;
 use crate::particles::Particles;
-use crate::paused::PausedScreen;
+use crate::paused::{PauseAction, PausedScreen};
 use crate::theme::all::AllThemes;
+use crate::theme::modern::{skin_names, skins_path};
+use crate::theme::pack::{themes_path, ThemePack};
+use crate::theme::sound::set_volumes;
+use crate::ui::UiStyle;
+use crate::watch::Watcher;
 
 use game_input::GameInputContext;
 use player::Match;
+use sdl2::event::Event;
 use sdl2::image::{InitFlag as ImageInitFlag, Sdl2ImageContext};
+use sdl2::keyboard::Keycode;
+use sdl2::messagebox::{
+    show_message_box, ButtonData, ClickedButton, MessageBoxButtonFlag, MessageBoxFlag,
+};
 use sdl2::mixer::{InitFlag as MixerInitFlag, AUDIO_S16LSB, DEFAULT_CHANNELS};
 use sdl2::pixels::Color;
 use sdl2::rect::Rect;
@@ -56,13 +85,13 @@ use sdl2::render::{Texture, WindowCanvas};
 use sdl2::sys::mixer::MIX_CHANNELS;
 use sdl2::ttf::Sdl2TtfContext;
 
-use sdl2::{AudioSubsystem, EventPump, Sdl};
+use sdl2::{AudioSubsystem, EventPump, Sdl, VideoSubsystem};
 use std::collections::HashMap;
 use std::fmt::Debug;
 use std::str::FromStr;
 
 use crate::menu::sound::MenuSound;
-use theme_context::{PlayerTextures, TextureMode, ThemeContext};
+use theme_context::{PlayerTextures, PlaylistTimer, TextureMode, ThemeContext};
 use crate::icon::app_icon;
 
 #[cfg(not(feature = "retro_handheld"))]
@@ -73,21 +102,104 @@ const MAX_PLAYERS: u32 = 1;
 
 const MAX_PARTICLES_PER_PLAYER: usize = 100000;
 const MAX_BACKGROUND_PARTICLES: usize = 100000;
+const REDUCED_MOTION_MAX_PARTICLES_PER_PLAYER: usize = 1000;
+
+// switch to critical music when any stack reaches this height & back once it drops below the exit height
+const CRITICAL_STACK_HEIGHT: u32 = 15;
+const CRITICAL_STACK_EXIT_HEIGHT: u32 = 12;
+const CRITICAL_MUSIC_FADE_MS: i32 = 1000;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum MainMenuAction {
     Start,
     ViewHighScores,
+    Controls,
+    Settings,
+    /// the config or theme packs changed & must be applied, or an error must be shown
+    Reload,
+    Quit,
+}
+
+/// What must be rebuilt to apply a reloaded config or theme packs, in increasing order of cost
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Reload {
+    Menu,
+    Themes,
+    Window,
+}
+
+/// Why the menus & games stopped running
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RunExit {
     Quit,
+    /// recreate the window for new video settings
+    RecreateWindow,
+    /// rebuild the themes from reloaded theme packs or video settings
+    ReloadThemes,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum PostGameAction {
     NewHighScore(NewHighScore),
     ReturnToMenu,
+    /// play again with the same game config, none for a new seed.
+    /// A finished match is retried after entering its high score.
+    Restart {
+        seed: Option<u64>,
+        high_score: Option<NewHighScore>,
+    },
     Quit,
 }
 
+fn poll_events<'a>(
+    event_pump: &'a mut EventPump,
+    gamepads: &'a mut Gamepads,
+) -> impl Iterator<Item = InputEvent> + 'a {
+    event_pump
+        .poll_iter()
+        .flat_map(|event| gamepads.translate(event))
+}
+
+fn create_canvas(video: &VideoSubsystem, config: &VideoConfig) -> Result<WindowCanvas, String> {
+    let (width, height) = match config.mode {
+        VideoMode::Window { width, height } => (width, height),
+        VideoMode::FullScreen { width, height } => (width, height),
+        _ => (1, 1),
+    };
+
+    let mut window_builder = video.window(build_info::PKG_NAME, width, height);
+    match config.mode {
+        VideoMode::FullScreen { .. } => {
+            window_builder.fullscreen();
+        }
+        VideoMode::FullScreenDesktop => {
+            window_builder.fullscreen_desktop();
+        }
+        _ => {}
+    };
+
+    let mut window = window_builder
+        .position_centered()
+        .opengl()
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    window.set_icon(app_icon()?);
+
+    let canvas_builder = window
+        .into_canvas()
+        .target_texture()
+        .accelerated();
+
+    if config.vsync {
+        canvas_builder.present_vsync()
+    } else {
+        canvas_builder
+    }
+    .build()
+    .map_err(|e| e.to_string())
+}
+
 struct TetrisSdl {
     config: Config,
     _sdl: Sdl,
@@ -95,15 +207,25 @@ struct TetrisSdl {
     _image: Sdl2ImageContext,
     canvas: WindowCanvas,
     event_pump: EventPump,
+    gamepads: Gamepads,
     _audio: AudioSubsystem,
     particle_scale: particles::scale::Scale,
     menu_sound: MenuSound,
-    game_config: GameConfig
+    game_config: GameConfig,
+    theme_packs: Vec<ThemePack>,
+    /// match to play instead of the main menu, set from the command line
+    start_match: Option<GameConfig>,
+    /// video mode from the command line, kept over reloads of the config
+    video_override: Option<VideoMode>,
+    watcher: Watcher,
+    /// reloaded changes that are applied the next time the main menu is shown
+    pending_reload: Option<Reload>,
+    /// problem with the last reload, shown on the main menu until it is fixed
+    reload_error: Option<String>,
 }
 
 impl TetrisSdl {
-    pub fn new() -> Result<Self, String> {
-        let config = Config::load()?;
+    pub fn new(config: Config) -> Result<Self, String> {
         let sdl = sdl2::init()?;
         let image = sdl2::image::init(ImageInitFlag::PNG)?;
         let video = sdl.video()?;
@@ -119,54 +241,23 @@ impl TetrisSdl {
             video.disable_screen_saver();
         }
 
-        let (width, height) = match config.video.mode {
-            VideoMode::Window { width, height } => (width, height),
-            VideoMode::FullScreen { width, height } => (width, height),
-            _ => (1, 1),
-        };
-
-        let mut window_builder = video.window(build_info::PKG_NAME, width, height);
-        match config.video.mode {
-            VideoMode::FullScreen { .. } => {
-                window_builder.fullscreen();
-            }
-            VideoMode::FullScreenDesktop => {
-                window_builder.fullscreen_desktop();
-            }
-            _ => {}
-        };
-
-        let mut window = window_builder
-            .position_centered()
-            .opengl()
-            .build()
-            .map_err(|e| e.to_string())?;
-
-        window.set_icon(app_icon()?);
-
-        let canvas_builder = window
-            .into_canvas()
-            .target_texture()
-            .accelerated();
-
-        let canvas = if config.video.vsync {
-            canvas_builder.present_vsync()
-        } else {
-            canvas_builder
-        }
-        .build()
-        .map_err(|e| e.to_string())?;
-
+        let canvas = create_canvas(&video, &config.video)?;
         let (width, height) = canvas.window().size();
 
         let event_pump = sdl.event_pump()?;
+        let gamepads = Gamepads::new(sdl.game_controller()?, sdl.joystick()?, config.input.gamepad.deadzone);
 
         let audio = sdl.audio()?;
         sdl2::mixer::open_audio(44_100, AUDIO_S16LSB, DEFAULT_CHANNELS, 512)?;
         let _mixer_context = sdl2::mixer::init(MixerInitFlag::OGG)?;
         sdl2::mixer::allocate_channels((MAX_PLAYERS * MIX_CHANNELS) as i32);
-        sdl2::mixer::Music::set_volume(config.audio.music_volume());
-        let menu_sound = MenuSound::new(config.audio)?;
+        set_volumes(config.audio);
+        let (theme_packs, problems) = ThemePack::discover();
+        let (menu_sound, sound_problems) = MenuSound::new(&theme_packs)?;
+        for problem in problems.iter().chain(sound_problems.iter()) {
+            println!("{}", problem);
+        }
+        let watcher = Watcher::new(config_path("config")?, vec![themes_path()?, skins_path()?]);
 
         Ok(Self {
             config,
@@ -175,13 +266,170 @@ impl TetrisSdl {
             _image: image,
             canvas,
             event_pump,
+            gamepads,
             _audio: audio,
             particle_scale: particles::scale::Scale::new((width, height)),
             menu_sound,
             game_config: Default::default(),
+            theme_packs,
+            start_match: None,
+            video_override: None,
+            watcher,
+            pending_reload: None,
+            reload_error: None,
         })
     }
 
+    /// Reloads a changed config file or theme packs. Audio & gamepads apply immediately so this is safe mid match,
+    /// everything else is applied the next time the main menu is shown.
+    fn check_reload(&mut self) {
+        let changes = self.watcher.poll();
+        if changes.config {
+            let reload = self.reload_config();
+            self.pending_reload = self.pending_reload.max(Some(reload));
+        }
+        if changes.themes {
+            self.pending_reload = self.pending_reload.max(Some(Reload::Themes));
+        }
+    }
+
+    /// Saves a change already made to the config from the main menu, the error is shown on the main menu
+    fn save_menu_config<F: FnOnce(&mut Config)>(&mut self, update: F) -> bool {
+        match Config::update_file(update) {
+            Ok(()) => {
+                self.watcher.sync_config();
+                true
+            }
+            Err(error) => {
+                println!("{}", error);
+                self.reload_error = Some(error);
+                false
+            }
+        }
+    }
+
+    /// A config with problems is not applied, the first problem is shown on the main menu
+    fn reload_config(&mut self) -> Reload {
+        let errors = match Config::load() {
+            Ok((mut config, errors)) if errors.is_empty() => {
+                if let Some(mode) = self.video_override {
+                    config.video.mode = mode;
+                }
+                set_volumes(config.audio);
+                self.gamepads.set_deadzone(config.input.gamepad.deadzone);
+                let reload = if Settings::from_config(&config)
+                    .is_video_changed(&Settings::from_config(&self.config))
+                {
+                    Reload::Window
+                } else if config.video != self.config.video {
+                    Reload::Themes
+                } else {
+                    Reload::Menu
+                };
+                println!("reloaded config");
+                self.config = config;
+                self.reload_error = None;
+                return reload;
+            }
+            Ok((_, errors)) => errors.iter().map(|e| e.to_string()).collect(),
+            Err(error) => vec![error],
+        };
+        for error in errors.iter() {
+            println!("config not reloaded, {}", error);
+        }
+        self.reload_error = Some(format!("config not reloaded, {}", errors[0]));
+        Reload::Menu
+    }
+
+    /// Replaces the theme packs, packs that cannot be loaded have already been skipped & are reported as problems
+    fn set_theme_packs(&mut self, theme_packs: Vec<ThemePack>, mut problems: Vec<String>) -> Result<(), String> {
+        let (menu_sound, sound_problems) = MenuSound::new(&theme_packs)?;
+        self.menu_sound = menu_sound;
+        self.theme_packs = theme_packs;
+        problems.extend(sound_problems);
+        for problem in problems.iter() {
+            println!("{}", problem);
+        }
+        if let Some(problem) = problems.into_iter().next() {
+            self.reload_error = Some(problem);
+        }
+
+        // the selected themes may have been removed
+        let theme_count = self.theme_names().len();
+        if self.game_config.themes.index() >= theme_count {
+            self.game_config.themes = MatchThemes::All;
+        }
+        if self.game_config.player2_themes.is_some_and(|t| t.index() >= theme_count) {
+            self.game_config.player2_themes = None;
+        }
+        Ok(())
+    }
+
+    /// Skips the main menu to play a match from the command line
+    fn start_match(&mut self, start: StartMatch) -> Result<(), String> {
+        let mut game_config = start.game_config;
+        if game_config.players > MAX_PLAYERS {
+            return Err(format!("at most {} players are supported", MAX_PLAYERS));
+        }
+        if let Some(theme) = start.theme {
+            let theme_names = self.theme_names();
+            let theme_index = theme_names.iter().position(|n| *n == theme).ok_or_else(|| {
+                format!("unknown theme {}, expected one of {}", theme, theme_names.join(", "))
+            })?;
+            game_config.themes = MatchThemes::from_index(theme_index);
+        }
+        self.start_match = Some(game_config);
+        Ok(())
+    }
+
+    /// Applies the video config, every texture created from the previous canvas must be recreated
+    fn recreate_window(&mut self) -> Result<(), String> {
+        let video = self.canvas.window().subsystem().clone();
+        self.canvas = create_canvas(&video, &self.config.video)?;
+        self.particle_scale = particles::scale::Scale::new(self.canvas.window().size());
+        Ok(())
+    }
+
+    fn theme_names(&self) -> Vec<String> {
+        MatchThemes::names()
+            .into_iter()
+            .map(|s| s.to_string())
+            .chain(self.theme_packs.iter().map(|pack| pack.name().to_string()))
+            .collect()
+    }
+
+    /// Indexes of the configured playlist themes, unknown themes are skipped
+    fn theme_playlist(&self) -> Vec<usize> {
+        let theme_names = self.theme_names();
+        self.config
+            .playlist
+            .themes
+            .iter()
+            .filter_map(|name| match theme_names.iter().position(|n| n == name) {
+                Some(index) if index > 0 => Some(index - 1),
+                _ => {
+                    println!("Unknown playlist theme {}, skipping", name);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Crt filter of each theme, unknown themes are skipped
+    fn theme_crt_filters(&self) -> Vec<Option<CrtConfig>> {
+        let theme_names = self.theme_names();
+        for name in self.config.video.crt_filters.keys() {
+            if !theme_names.iter().skip(1).any(|n| n == name) {
+                println!("Unknown crt filter theme {}, skipping", name);
+            }
+        }
+        theme_names
+            .iter()
+            .skip(1)
+            .map(|name| self.config.video.crt_filters.get(name).copied())
+            .collect()
+    }
+
     fn orbit_particle_source(&self) -> Box<dyn ParticleSource> {
         let (window_width, window_height) = self.canvas.window().size();
         prescribed_orbit(
@@ -209,38 +457,101 @@ impl TetrisSdl {
     pub fn main_menu(&mut self, particles: &mut ParticleRender) -> Result<MainMenuAction, String> {
         const PLAYERS: &str = "players";
         const THEMES: &str = "themes";
+        const PLAYER2_THEMES: &str = "p2 themes";
+        const THEME_SWITCH: &str = "switch themes";
+        const SHUFFLE_THEMES: &str = "shuffle themes";
+        const OFF: &str = "off";
+        const ON: &str = "on";
+        const SAME_THEMES: &str = "same";
         const MODE: &str = "mode";
+        const RULES: &str = "rules";
         const LEVEL: &str = "level";
+        const HEIGHT: &str = "height";
+        const SKIN: &str = "skin";
+        const DEFAULT_SKIN: &str = "default";
         const HIGH_SCORES: &str = "high scores";
+        const CONTROLS: &str = "controls";
+        const SETTINGS: &str = "settings";
         const START: &str = "start";
         const QUIT: &str = "quit";
 
         let texture_creator = self.canvas.texture_creator();
-        let inputs = MenuInputContext::new(self.config.input);
+        let inputs = MenuInputContext::new(&self.config.input);
         let modes = MatchRules::DEFAULT_MODES;
+        let mut theme_switches = ThemeSwitch::DEFAULT_SWITCHES.to_vec();
+        if !theme_switches.contains(&self.config.playlist.switch) {
+            theme_switches.push(self.config.playlist.switch);
+        }
+        let theme_names = self.theme_names();
+        let skin_names: Vec<String> = [DEFAULT_SKIN.to_string()]
+            .into_iter()
+            .chain(skin_names())
+            .collect();
+        let skin_index = self
+            .config
+            .video
+            .modern_skin
+            .as_ref()
+            .and_then(|skin| skin_names.iter().position(|name| name == skin))
+            .unwrap_or(0);
 
         let mut menu_items = vec![
             MenuItem::select_list(
                 THEMES,
-                MatchThemes::names().into_iter().map(|s| s.to_string()).collect(),
-                self.game_config.themes as usize,
+                theme_names.clone(),
+                self.game_config.themes.index(),
+            ),
+            MenuItem::select_list(
+                THEME_SWITCH,
+                theme_switches.iter().map(|s| s.name()).collect(),
+                theme_switches.iter().position(|&s| s == self.config.playlist.switch).unwrap(),
+            ),
+            MenuItem::select_list(
+                SHUFFLE_THEMES,
+                vec![OFF.to_string(), ON.to_string()],
+                self.config.playlist.shuffle as usize,
             ),
             MenuItem::select_list(
                 MODE,
                 modes.iter().map(|m| m.name()).collect(),
                 modes.iter().position(|&m| m == self.game_config.rules).unwrap()
             ),
+            MenuItem::select_list(
+                RULES,
+                Ruleset::names().into_iter().map(|s| s.to_string()).collect(),
+                self.game_config.ruleset as usize,
+            ),
             MenuItem::select_list(
                 LEVEL,
-                (0..10).map(|i| i.to_string()).collect(),
+                (0..=MAX_START_LEVEL).map(|i| i.to_string()).collect(),
                 self.game_config.level as usize,
             ),
+            MenuItem::select_list(
+                HEIGHT,
+                (0..=MAX_B_TYPE_HEIGHT).map(|i| i.to_string()).collect(),
+                self.game_config.height as usize,
+            ),
+            MenuItem::select_list(SKIN, skin_names.clone(), skin_index),
             MenuItem::select(HIGH_SCORES),
+            MenuItem::select(CONTROLS),
+            MenuItem::select(SETTINGS),
             MenuItem::select(START),
             MenuItem::select(QUIT),
         ];
 
         if MAX_PLAYERS > 1 {
+            let player2_theme_names: Vec<String> = [SAME_THEMES.to_string()]
+                .into_iter()
+                .chain(theme_names.iter().cloned())
+                .collect();
+            menu_items.insert(
+                1,
+                MenuItem::select_list(
+                    PLAYER2_THEMES,
+                    player2_theme_names,
+                    self.game_config.player2_themes.map(|t| t.index() + 1).unwrap_or(0),
+                ),
+            );
             menu_items.insert(
                 0,
                 MenuItem::select_list(
@@ -259,8 +570,9 @@ impl TetrisSdl {
             &mut self.canvas,
             &self.ttf,
             &texture_creator,
+            UiStyle::new(self.config.video.ui),
             build_info::PKG_NAME.to_uppercase(),
-            None
+            self.reload_error.clone(),
         )?;
 
         particles.clear();
@@ -268,33 +580,83 @@ impl TetrisSdl {
 
         let mut frame_rate = FrameRate::new();
 
+        self.menu_sound.set_theme(self.game_config.themes)?;
         self.menu_sound.play_main_menu_music()?;
         loop {
             let delta = frame_rate.update()?;
 
-            for key in inputs.parse(self.event_pump.poll_iter()).into_iter() {
+            self.check_reload();
+            if self.pending_reload.is_some() {
+                return Ok(MainMenuAction::Reload);
+            }
+
+            for key in inputs.parse(poll_events(&mut self.event_pump, &mut self.gamepads)).into_iter() {
                 if key == MenuInputKey::Quit {
                     return Ok(MainMenuAction::Quit);
                 }
                 match menu.read_key(key) {
                     None => match key {
                         MenuInputKey::Start => {
-                            self.menu_sound.play_chime()?;
+                            self.menu_sound.play_start()?;
                             return Ok(MainMenuAction::Start);
                         }
                         _ => {}
                     },
                     Some((name, action)) => match name {
                         PLAYERS => self.game_config.players = action.parse::<u32>().unwrap(),
-                        THEMES => self.game_config.themes = MatchThemes::from_str(action).unwrap(),
+                        THEMES => {
+                            let theme_index = theme_names.iter().position(|n| n == action).unwrap();
+                            self.game_config.themes = MatchThemes::from_index(theme_index);
+                            self.menu_sound.set_theme(self.game_config.themes)?;
+                        }
+                        THEME_SWITCH => {
+                            let switch_index =
+                                theme_switches.iter().position(|s| s.name() == action).unwrap();
+                            let switch = theme_switches[switch_index];
+                            self.config.playlist.switch = switch;
+                            if !self.save_menu_config(|saved| saved.playlist.switch = switch) {
+                                return Ok(MainMenuAction::Reload);
+                            }
+                        }
+                        SHUFFLE_THEMES => {
+                            let shuffle = action == ON;
+                            self.config.playlist.shuffle = shuffle;
+                            if !self.save_menu_config(|saved| saved.playlist.shuffle = shuffle) {
+                                return Ok(MainMenuAction::Reload);
+                            }
+                        }
+                        PLAYER2_THEMES => {
+                            self.game_config.player2_themes = theme_names
+                                .iter()
+                                .position(|n| n == action)
+                                .map(MatchThemes::from_index);
+                        }
                         MODE => {
                             let mode_index =
                                 modes.iter().position(|&m| m.name() == action).unwrap();
                             self.game_config.rules = modes[mode_index];
                         }
+                        RULES => self.game_config.ruleset = Ruleset::from_str(action).unwrap(),
                         LEVEL => self.game_config.level = action.parse::<u32>().unwrap(),
+                        HEIGHT => self.game_config.height = action.parse::<u32>().unwrap(),
+                        SKIN => {
+                            let skin = if action == DEFAULT_SKIN {
+                                None
+                            } else {
+                                Some(action.to_string())
+                            };
+                            self.config.video.modern_skin = skin.clone();
+                            if !self.save_menu_config(|saved| saved.video.modern_skin = skin) {
+                                return Ok(MainMenuAction::Reload);
+                            }
+                        }
                         HIGH_SCORES => return Ok(MainMenuAction::ViewHighScores),
-                        START => return Ok(MainMenuAction::Start),
+                        CONTROLS => return Ok(MainMenuAction::Controls),
+                        SETTINGS => return Ok(MainMenuAction::Settings),
+                        START => {
+                            self.menu_sound.play_start()?;
+                            return Ok(MainMenuAction::Start);
+                        }
                         QUIT => return Ok(MainMenuAction::Quit),
                         _ => {}
                     },
@@ -319,9 +681,11 @@ impl TetrisSdl {
 
     pub fn view_high_score(&mut self, particles: &mut ParticleRender) -> Result<(), String> {
         let texture_creator = self.canvas.texture_creator();
-        let inputs = MenuInputContext::new(self.config.input);
-        let high_scores = HighScoreTable::load()?;
-        if high_scores.entries().is_empty() {
+        let inputs = MenuInputContext::new(&self.config.input);
+        let category = HighScoreCategory::from_game_config(self.game_config);
+        let high_scores = HighScoreTable::load(category)?;
+        // b-type keeps a table per level & height, so an empty one is shown to say it hasn't been played
+        if high_scores.is_empty() && !matches!(category, HighScoreCategory::BType { .. }) {
             return Ok(());
         }
 
@@ -330,7 +694,9 @@ impl TetrisSdl {
             &self.ttf,
             &texture_creator,
             self.canvas.window().size(),
+            category,
             None,
+            UiStyle::new(self.config.video.ui),
         )?;
 
         particles.clear();
@@ -342,7 +708,7 @@ impl TetrisSdl {
         'menu: loop {
             let delta = frame_rate.update()?;
 
-            let events = inputs.parse(self.event_pump.poll_iter());
+            let events = inputs.parse(poll_events(&mut self.event_pump, &mut self.gamepads));
             if !events.is_empty() {
                 // any button press
                 break 'menu;
@@ -361,24 +727,283 @@ impl TetrisSdl {
         Ok(())
     }
 
+    /// Rebind the keyboard controls, changes are only kept once saved
+    pub fn controls(&mut self, particles: &mut ParticleRender) -> Result<(), String> {
+        const HELP: &str = "select an action to rebind";
+
+        let texture_creator = self.canvas.texture_creator();
+        let style = UiStyle::new(self.config.video.ui);
+        let actions = ControlAction::all(MAX_PLAYERS);
+        let mut input = self.config.input.clone();
+        let mut inputs = MenuInputContext::new(&input);
+        // the action & which of its bindings is being replaced, or added when past the last
+        let mut capturing: Option<(ControlAction, usize)> = None;
+        let mut by_position = false;
+        let mut menu = controls_menu(
+            &mut self.canvas,
+            &self.ttf,
+            &texture_creator,
+            style,
+            &actions,
+            &input,
+            by_position,
+            HELP,
+            0,
+        )?;
+
+        particles.clear();
+        particles.add_source(self.orbit_particle_source());
+
+        let mut frame_rate = FrameRate::new();
+
+        loop {
+            let delta = frame_rate.update()?;
+
+            let events: Vec<InputEvent> =
+                poll_events(&mut self.event_pump, &mut self.gamepads).collect();
+            let mut status: Option<String> = None;
+            if let Some((action, entry)) = capturing {
+                for event in events {
+                    let InputEvent::Sdl(Event::KeyDown {
+                        keycode,
+                        scancode,
+                        repeat: false,
+                        ..
+                    }) = event
+                    else {
+                        continue;
+                    };
+                    match keycode {
+                        Some(Keycode::Escape) => {
+                            capturing = None;
+                            status = Some(HELP.to_string());
+                            break;
+                        }
+                        Some(Keycode::Backspace) => {
+                            action.unbind(&mut input, entry);
+                            inputs = MenuInputContext::new(&input);
+                            capturing = None;
+                            status = Some(format!("{} unbound", action.name()));
+                            self.menu_sound.play_chime()?;
+                            break;
+                        }
+                        _ => {}
+                    }
+                    let binding = if by_position {
+                        scancode
+                            .and_then(ScanKey::from_scancode)
+                            .map(|scancode| KeyBinding::Scancode { scancode })
+                    } else {
+                        keycode.and_then(GameKey::from_keycode).map(KeyBinding::Keycode)
+                    };
+                    let Some(binding) = binding else {
+                        continue;
+                    };
+
+                    let mut candidate = input.clone();
+                    status = Some(if !action.bind(&mut candidate, entry, binding) {
+                        format!("{} is already bound to {}", binding, action.name())
+                    } else {
+                        match candidate.check_bindings().into_iter().next() {
+                            None => {
+                                input = candidate;
+                                inputs = MenuInputContext::new(&input);
+                                format!("{} bound to {}", action.name(), binding)
+                            }
+                            Some((_, conflict)) => conflict,
+                        }
+                    });
+                    capturing = None;
+                    self.menu_sound.play_chime()?;
+                    break;
+                }
+            } else {
+                for key in inputs.parse(events.into_iter()) {
+                    if key == MenuInputKey::Quit {
+                        return Ok(());
+                    }
+                    // capture the selected binding before select moves on to the next one
+                    let action = actions.get(menu.current_row()).copied();
+                    if let Some(action) = action.filter(|_| key == MenuInputKey::Select) {
+                        let entry = menu.current_item();
+                        let bound = action.bindings(&input).map_or(0, |b| b.0.len());
+                        let unbind = if entry < bound { ", backspace to unbind" } else { "" };
+                        capturing = Some((action, entry));
+                        status = Some(format!("press a key for {}, escape to cancel{}", action.name(), unbind));
+                        self.menu_sound.play_chime()?;
+                        continue;
+                    }
+                    if let Some((name, value)) = menu.read_key(key) {
+                        match name {
+                            BIND_BY => by_position = value == BIND_BY_POSITION,
+                            RESET_CONTROLS => {
+                                let gamepad = input.gamepad.clone();
+                                input = Config::default().input;
+                                input.gamepad = gamepad;
+                                inputs = MenuInputContext::new(&input);
+                                status = Some("reset to defaults, save to keep".to_string());
+                            }
+                            SAVE_CONTROLS => {
+                                // only the controls are saved, not any other changes made in the menus
+                                match Config::update_file(|saved| saved.input = input.clone()) {
+                                    Ok(()) => {
+                                        self.config.input = input;
+                                        self.menu_sound.play_chime()?;
+                                        return Ok(());
+                                    }
+                                    Err(error) => status = Some(error),
+                                }
+                            }
+                            CANCEL_CONTROLS => return Ok(()),
+                            _ => {}
+                        }
+                    }
+                    self.menu_sound.play_chime()?;
+                }
+            }
+
+            if let Some(status) = status {
+                menu = controls_menu(
+                    &mut self.canvas,
+                    &self.ttf,
+                    &texture_creator,
+                    style,
+                    &actions,
+                    &input,
+                    by_position,
+                    &status,
+                    menu.current_row(),
+                )?;
+            }
+
+            self.canvas.set_draw_color(Color::BLACK);
+            self.canvas.clear();
+
+            // particles
+            particles.update(delta);
+            particles.draw(&mut self.canvas)?;
+
+            menu.draw(&mut self.canvas)?;
+
+            self.canvas.present();
+        }
+    }
+
+    /// Edit the video, audio & gameplay settings, returns true if the window must be recreated.
+    /// A match in play keeps the gameplay settings it started with so its seed still deals the same match
+    pub fn settings(&mut self, particles: &mut ParticleRender, mid_match: bool) -> Result<bool, String> {
+        const SETTINGS_HELP: &str = "video changes apply on save";
+        const MID_MATCH_HELP: &str = "video changes apply on save, gameplay from the next match";
+
+        let texture_creator = self.canvas.texture_creator();
+        let inputs = MenuInputContext::new(&self.config.input);
+        let original = Settings::from_config(&self.config);
+        let mut settings = original.clone();
+        let desktop = self.canvas.window().subsystem().desktop_display_mode(0)?;
+        let video_modes = video_modes(settings.mode, (desktop.w as u32, desktop.h as u32));
+
+        let mut menu = Menu::new(
+            settings.menu_items(&video_modes),
+            &mut self.canvas,
+            &self.ttf,
+            &texture_creator,
+            UiStyle::new(self.config.video.ui),
+            "SETTINGS".to_string(),
+            if mid_match { MID_MATCH_HELP } else { SETTINGS_HELP }.to_string(),
+        )?;
+
+        particles.clear();
+        particles.add_source(self.orbit_particle_source());
+
+        let mut frame_rate = FrameRate::new();
+
+        loop {
+            let delta = frame_rate.update()?;
+
+            let mut status: Option<String> = None;
+            for key in inputs.parse(poll_events(&mut self.event_pump, &mut self.gamepads)) {
+                if key == MenuInputKey::Quit {
+                    return self.cancel_settings(original);
+                }
+                if let Some((name, value)) = menu.read_key(key) {
+                    match name {
+                        SAVE_SETTINGS => {
+                            // only the settings are saved, not any other changes made in the menus
+                            match Config::update_file(|saved| settings.apply(saved)) {
+                                Ok(()) => {
+                                    settings.apply(&mut self.config);
+                                    self.menu_sound.play_chime()?;
+                                    return Ok(settings.is_video_changed(&original));
+                                }
+                                Err(error) => status = Some(error),
+                            }
+                        }
+                        CANCEL_SETTINGS => return self.cancel_settings(original),
+                        _ => {
+                            // audio & gameplay apply immediately so they can be heard & played before saving,
+                            // gameplay from the next match
+                            if settings.read(name, value, &video_modes) {
+                                self.config.audio = settings.audio;
+                                self.config.game = settings.game;
+                                set_volumes(settings.audio);
+                            }
+                        }
+                    }
+                }
+                self.menu_sound.play_chime()?;
+            }
+
+            if let Some(status) = status {
+                let current_row = menu.current_row();
+                menu = Menu::new(
+                    settings.menu_items(&video_modes),
+                    &mut self.canvas,
+                    &self.ttf,
+                    &texture_creator,
+                    UiStyle::new(self.config.video.ui),
+                    "SETTINGS".to_string(),
+                    status,
+                )?
+                .with_current_row(current_row);
+            }
+
+            self.canvas.set_draw_color(Color::BLACK);
+            self.canvas.clear();
+
+            // particles
+            particles.update(delta);
+            particles.draw(&mut self.canvas)?;
+
+            menu.draw(&mut self.canvas)?;
+
+            self.canvas.present();
+        }
+    }
+
+    fn cancel_settings(&mut self, original: Settings) -> Result<bool, String> {
+        self.config.audio = original.audio;
+        self.config.game = original.game;
+        set_volumes(original.audio);
+        Ok(false)
+    }
+
     pub fn new_high_score(
         &mut self,
         new_high_score: NewHighScore,
         particles: &mut ParticleRender,
     ) -> Result<(), String> {
         let texture_creator = self.canvas.texture_creator();
-        let inputs = MenuInputContext::new(self.config.input);
-        let high_scores = HighScoreTable::load()?;
-        if high_scores.entries().is_empty() {
-            return Ok(());
-        }
+        let inputs = MenuInputContext::new(&self.config.input);
+        let high_scores = HighScoreTable::load(new_high_score.category)?;
 
         let mut table = HighScoreRender::new(
             high_scores,
             &self.ttf,
             &texture_creator,
             self.canvas.window().size(),
+            new_high_score.category,
             Some(new_high_score),
+            UiStyle::new(self.config.video.ui),
         )?;
 
         particles.clear();
@@ -390,7 +1015,7 @@ impl TetrisSdl {
         'menu: loop {
             let delta = frame_rate.update()?;
 
-            for key in inputs.parse(self.event_pump.poll_iter()) {
+            for key in inputs.parse(poll_events(&mut self.event_pump, &mut self.gamepads)) {
                 let event = match key {
                     MenuInputKey::Up => table.up(),
                     MenuInputKey::Down => table.down(),
@@ -422,12 +1047,43 @@ impl TetrisSdl {
         }
 
         if let Some(new_entry) = table.new_entry() {
-            let mut high_scores = HighScoreTable::load().unwrap();
+            let mut high_scores = HighScoreTable::load(new_high_score.category).unwrap();
             high_scores.add_high_score(new_entry);
-            high_scores.save()
-        } else {
-            Ok(())
+            // e.g. the file is from a newer version, the game carries on without saving
+            if let Err(error) = high_scores.save(new_high_score.category) {
+                println!("{}", error);
+            }
         }
+        Ok(())
+    }
+
+    /// Plays matches until one ends without a restart, returns true to quit.
+    /// Restarts keep the game config & optionally the seed, the original seed is put back afterwards.
+    fn play(
+        &mut self,
+        all_themes: &AllThemes,
+        bg_particles: &mut ParticleRender,
+        fg_particles: &mut ParticleRender,
+    ) -> Result<bool, String> {
+        let seed = self.game_config.seed;
+        let quit = loop {
+            match self.game(all_themes, bg_particles, fg_particles)? {
+                PostGameAction::NewHighScore(high_score) => {
+                    self.new_high_score(high_score, bg_particles)?;
+                    break false;
+                }
+                PostGameAction::ReturnToMenu => break false,
+                PostGameAction::Restart { seed, high_score } => {
+                    if let Some(high_score) = high_score {
+                        self.new_high_score(high_score, bg_particles)?;
+                    }
+                    self.game_config.seed = seed;
+                }
+                PostGameAction::Quit => break true,
+            }
+        };
+        self.game_config.seed = seed;
+        Ok(quit)
     }
 
     pub fn game(
@@ -437,10 +1093,18 @@ impl TetrisSdl {
         fg_particles: &mut ParticleRender,
     ) -> Result<PostGameAction, String> {
         let texture_creator = self.canvas.texture_creator();
-        let mut inputs = GameInputContext::new(self.config.input);
-        let mut fixture = Match::new(self.game_config, self.config);
+        let mut inputs = GameInputContext::new(&self.config.input)?;
+        let mut fixture = Match::new(self.game_config, &self.config);
         let window_size = self.canvas.window().size();
-        let mut themes = ThemeContext::new(all_themes, &texture_creator, self.game_config, self.config, window_size)?;
+        let mut themes = ThemeContext::new(
+            all_themes,
+            &texture_creator,
+            self.game_config,
+            &self.config,
+            window_size,
+            self.theme_playlist(),
+            self.theme_crt_filters(),
+        )?;
 
         let mut player_textures = (0..self.game_config.players)
             .map(|_| {
@@ -468,16 +1132,33 @@ impl TetrisSdl {
         bg_particles.clear();
         bg_particles.add_source(self.orbit_particle_source());
 
-        themes.theme().music().play(-1)?;
-        let paused_screen =
-            PausedScreen::new(&mut self.canvas, &self.ttf, &texture_creator, window_size)?;
+        // the music follows the theme of the leading player
+        let mut is_critical = false;
+        // sdl mixer plays one track at a time, so the critical music fades in once the current track has faded out
+        let mut crossfading = false;
+        let mut music_theme = themes.player_theme(fixture.leader()).name();
+        themes.player_theme(fixture.leader()).music(is_critical).play(-1)?;
+        let menu_inputs = MenuInputContext::new(&self.config.input);
+        let mut paused_screen =
+            PausedScreen::new(
+                &mut self.canvas,
+                &self.ttf,
+                &texture_creator,
+                window_size,
+                UiStyle::new(self.config.video.ui),
+            )?;
 
         let mut player_hard_drop_animations: HashMap<u32, HardDropAnimation> = HashMap::new();
-        let mut max_level = 0;
+        let mut playlist_timer =
+            PlaylistTimer::new(self.config.playlist.switch, self.game_config.level, 0);
+        let rotate_themes = (1..=self.game_config.players)
+            .any(|player| self.game_config.player_themes(player) == MatchThemes::All);
         let mut frame_rate = FrameRate::new();
 
         loop {
             let delta = frame_rate.update()?;
+            self.check_reload();
+            fixture.update_play_time(delta);
 
             let mut to_emit_particles = vec![];
 
@@ -486,8 +1167,52 @@ impl TetrisSdl {
                 fixture.set_hard_dropping(*hard_dropping_player);
             }
 
-            let events = inputs
-                .update(delta, self.event_pump.poll_iter())
+            // while paused the menu keys drive the pause menu,
+            // the only game keys are closing the window & unless used by the menu unpausing & switching themes
+            let polled: Vec<InputEvent> =
+                poll_events(&mut self.event_pump, &mut self.gamepads).collect();
+            let is_paused = fixture.state().is_paused();
+            let menu_keys = if is_paused {
+                menu_inputs.parse(polled.iter().cloned())
+            } else {
+                vec![]
+            };
+            let keys: Vec<GameInputKey> = inputs
+                .update(delta, polled.into_iter())
+                .into_iter()
+                .filter(|&input| {
+                    !is_paused
+                        || input == GameInputKey::Quit
+                        || (menu_keys.is_empty()
+                            && matches!(
+                                input,
+                                GameInputKey::Pause
+                                    | GameInputKey::Retry
+                                    | GameInputKey::NextTheme
+                                    | GameInputKey::NextPlayerTheme { .. }
+                            ))
+                })
+                .collect();
+
+            // retry restarts straight away from any state, a finished match still enters its high score first
+            if keys.contains(&GameInputKey::Retry) {
+                let high_score = match fixture.state() {
+                    MatchState::GameOver { high_score } => high_score,
+                    _ => {
+                        println!("retrying, abandoned {}", fixture.summary());
+                        if let Err(error) = fixture.log_abandoned() {
+                            println!("abandoned attempt not logged, {}", error);
+                        }
+                        None
+                    }
+                };
+                return Ok(PostGameAction::Restart {
+                    seed: None,
+                    high_score,
+                });
+            }
+
+            let mut events = keys
                 .into_iter()
                 .flat_map(|input| match input {
                     GameInputKey::MoveLeft { player } => fixture.mut_game(player, |g| g.left()),
@@ -506,44 +1231,105 @@ impl TetrisSdl {
                     }
                     GameInputKey::Hold { player } => fixture.mut_game(player, |g| g.hold()),
                     GameInputKey::Pause => match fixture.state() {
-                        MatchState::Normal | MatchState::Paused => fixture.toggle_paused(),
+                        MatchState::Normal => {
+                            paused_screen.open();
+                            fixture.toggle_paused()
+                        }
+                        MatchState::Paused => fixture.toggle_paused(),
                         _ => None,
                     },
                     GameInputKey::Quit => Some(GameEvent::Quit),
-                    GameInputKey::ReturnToMenu => Some(GameEvent::ReturnToMenu),
-                    GameInputKey::NextTheme => Some(GameEvent::NextTheme),
-                })
-                .collect::<Vec<GameEvent>>();
+                    // quitting a match in play must be confirmed from the pause menu
+                    GameInputKey::ReturnToMenu => match fixture.state() {
+                        MatchState::Normal => {
+                            paused_screen.confirm_forfeit();
+                            fixture.toggle_paused()
+                        }
+                        _ => Some(GameEvent::ReturnToMenu),
+                    },
+                    GameInputKey::NextTheme => Some(GameEvent::NextTheme { player: None }),
+                    GameInputKey::NextPlayerTheme { player } => {
+                        Some(GameEvent::NextTheme { player: Some(player) })
+                    }
+                    GameInputKey::Retry => None,
+                }.map(|event| (input.player(), event)))
+                .collect::<Vec<(Option<u32>, GameEvent)>>();
 
-            for event in events.into_iter() {
+            for key in menu_keys {
+                self.menu_sound.play_chime()?;
+                match paused_screen.read_key(key) {
+                    Some(PauseAction::Resume) => {
+                        events.extend(fixture.toggle_paused().map(|event| (None, event)));
+                    }
+                    Some(PauseAction::Restart { same_seed }) => {
+                        return Ok(PostGameAction::Restart {
+                            seed: same_seed.then(|| fixture.seed()),
+                            high_score: None,
+                        });
+                    }
+                    Some(PauseAction::Settings) => {
+                        // the window cannot be recreated mid match so video changes wait for the main menu
+                        if self.settings(bg_particles, true)? {
+                            self.pending_reload = self.pending_reload.max(Some(Reload::Window));
+                        }
+                        frame_rate = FrameRate::new();
+                    }
+                    Some(PauseAction::Forfeit) => {
+                        return Ok(match fixture.forfeit() {
+                            Some(high_score) => PostGameAction::NewHighScore(high_score),
+                            None => PostGameAction::ReturnToMenu,
+                        });
+                    }
+                    None => {}
+                }
+            }
+
+            let leader = fixture.leader();
+            for (player, event) in events.into_iter() {
                 match event {
                     GameEvent::Quit => return Ok(PostGameAction::Quit),
-                    GameEvent::ReturnToMenu => return Ok(PostGameAction::ReturnToMenu), // even if high score?!
+                    GameEvent::ReturnToMenu => {
+                        // skipping the game over animation still enters any high score
+                        return Ok(match fixture.state() {
+                            MatchState::GameOver {
+                                high_score: Some(high_score),
+                            } => PostGameAction::NewHighScore(high_score),
+                            _ => PostGameAction::ReturnToMenu,
+                        });
+                    }
                     GameEvent::Paused => sdl2::mixer::Music::pause(),
                     GameEvent::UnPaused => sdl2::mixer::Music::resume(),
-                    GameEvent::NextTheme if !fixture.state().is_game_over() => {
-                        themes.start_fade(&mut self.canvas)?;
-                        themes.next();
+                    GameEvent::NextTheme { player } if !fixture.state().is_game_over() => {
+                        match player {
+                            None => themes.next_all(),
+                            Some(player) => themes.next(player),
+                        }
+                        themes.start_transition(&mut self.canvas)?;
 
                         // handle music
-                        match fixture.state() {
-                            MatchState::Normal => {
-                                themes.theme().music().fade_in(-1, 1000)?;
-                            }
-                            MatchState::Paused => {
-                                // switch music but pause it immediately
-                                themes.theme().music().play(-1)?;
-                                sdl2::mixer::Music::pause();
+                        let leader_theme = themes.player_theme(leader);
+                        if leader_theme.name() != music_theme {
+                            music_theme = leader_theme.name();
+                            crossfading = false;
+                            match fixture.state() {
+                                MatchState::Normal => {
+                                    leader_theme.music(is_critical).fade_in(-1, 1000)?;
+                                }
+                                MatchState::Paused => {
+                                    // switch music but pause it immediately
+                                    leader_theme.music(is_critical).play(-1)?;
+                                    sdl2::mixer::Music::pause();
+                                }
+                                _ => {}
                             }
-                            _ => {}
                         }
                     }
                     GameEvent::HardDrop {
                         player: player_id,
                         minos,
                         dropped_rows,
-                    } => {
-                        let theme = themes.current();
+                    } if !self.config.video.accessibility.reduced_motion => {
+                        let theme = themes.player(player_id);
                         let mino_rects = theme.mino_rects(player_id, minos);
                         let dropped_pixels = theme.rows_to_pixels(dropped_rows);
                         let hard_drop_animation = HardDropAnimation::new(
@@ -557,8 +1343,9 @@ impl TetrisSdl {
                     _ => {}
                 }
 
-                themes.theme().play_sound_effects(event)?;
-                if let Some(emit) = themes.theme().emit_particles(event) {
+                let theme = themes.player_theme(player.unwrap_or(leader));
+                theme.play_sound_effects(event)?;
+                if let Some(emit) = theme.emit_particles(event) {
                     to_emit_particles.push(emit);
                 }
             }
@@ -583,10 +1370,9 @@ impl TetrisSdl {
                         }
                     }
                 }
-                MatchState::Normal if !themes.is_fading() => {
+                MatchState::Normal if !themes.is_transitioning() => {
                     let mut garbage: Vec<(u32, u32)> = vec![];
                     let mut new_game_over: Option<u32> = None;
-                    let mut next_theme = false;
                     for player in fixture.players.iter_mut() {
                         if let Some(emit) = player.current_particles() {
                             to_emit_particles.push(emit);
@@ -611,51 +1397,55 @@ impl TetrisSdl {
                             }
                             GameEvent::Destroy(lines) => {
                                 if lines[0].is_some() {
-                                    player.animate_destroy(
-                                        themes.theme().destroy_animation_type(),
-                                        lines,
-                                    );
+                                    let mut destroy_type =
+                                        themes.player_theme(player.player).destroy_animation_type();
+                                    if self.config.video.accessibility.reduced_motion {
+                                        destroy_type = destroy_type.reduced_motion();
+                                    }
+                                    player.animate_destroy(destroy_type, lines);
                                 }
                             }
                             GameEvent::Destroyed {
-                                level_up,
                                 send_garbage_lines,
                                 ..
-                            } => {
-                                // if playing with all themes then the theme is auto switched after each level
-                                if self.game_config.themes == MatchThemes::All && level_up {
-                                    let level = player.game.level();
-                                    if level > max_level {
-                                        next_theme = true;
-                                        max_level = level;
-                                    }
-                                }
-
-                                if send_garbage_lines > 0 {
-                                    garbage.push((player.player, send_garbage_lines));
-                                }
+                            } if send_garbage_lines > 0 => {
+                                garbage.push((player.player, send_garbage_lines));
                             }
                             _ => {}
                         }
-                        themes.theme().play_sound_effects(event)?;
-                        if let Some(emit) = themes.theme().emit_particles(event) {
+                        let theme = themes.player_theme(player.player);
+                        theme.play_sound_effects(event)?;
+                        if let Some(emit) = theme.emit_particles(event) {
                             to_emit_particles.push(emit);
                         }
                     }
 
+                    // if playing with all themes then the theme is auto switched through the playlist
+                    let next_theme = rotate_themes && {
+                        let metrics = fixture.players.iter().map(|p| p.game.metrics());
+                        let level = metrics.clone().map(|m| m.level).max().unwrap();
+                        let lines = metrics.map(|m| m.lines).max().unwrap();
+                        playlist_timer.update(delta, level, lines)
+                    };
+
                     // maybe start game over
                     if let Some(winner) = fixture.check_for_winning_player() {
                         sdl2::mixer::Music::halt();
-                        fixture.set_winner(winner, themes.theme().game_over_animation_type());
+                        fixture.set_winner(winner, |loser| {
+                            themes.player_theme(loser).game_over_animation_type()
+                        });
                         let victory = GameEvent::Victory { player: winner };
-                        themes.theme().play_sound_effects(victory)?;
-                        if let Some(emit) = themes.theme().emit_particles(victory) {
+                        themes.player_theme(winner).play_sound_effects(victory)?;
+                        if let Some(emit) = themes.player_theme(winner).emit_particles(victory) {
                             to_emit_particles.push(emit);
                         }
                     } else if new_game_over.is_some() {
                         if let Some(loser) = new_game_over {
                             sdl2::mixer::Music::halt();
-                            fixture.set_game_over(loser, themes.theme().game_over_animation_type());
+                            fixture.set_game_over(
+                                loser,
+                                themes.player_theme(loser).game_over_animation_type(),
+                            );
                             let winners = fixture
                                 .players
                                 .iter()
@@ -663,7 +1453,7 @@ impl TetrisSdl {
                                 .filter(|p| *p != loser);
                             for winner in winners {
                                 let victory = GameEvent::Victory { player: winner };
-                                if let Some(emit) = themes.theme().emit_particles(victory) {
+                                if let Some(emit) = themes.player_theme(winner).emit_particles(victory) {
                                     to_emit_particles.push(emit);
                                 }
                             }
@@ -674,11 +1464,45 @@ impl TetrisSdl {
                             fixture.send_garbage(from_player, send_garbage_lines);
                         }
 
-                        // maybe change the theme
+                        // maybe switch to or from the critical music
+                        let stack_height = fixture.max_stack_height();
+                        let critical = if is_critical {
+                            stack_height >= CRITICAL_STACK_EXIT_HEIGHT
+                        } else {
+                            stack_height >= CRITICAL_STACK_HEIGHT
+                        };
+                        if critical != is_critical {
+                            is_critical = critical;
+                            let leader_theme = themes.player_theme(fixture.leader());
+                            if !next_theme && leader_theme.has_critical_music() {
+                                sdl2::mixer::Music::fade_out(CRITICAL_MUSIC_FADE_MS / 2)?;
+                                crossfading = true;
+                            }
+                        }
+                        if crossfading && !sdl2::mixer::Music::is_playing() {
+                            crossfading = false;
+                            themes
+                                .player_theme(fixture.leader())
+                                .music(is_critical)
+                                .fade_in(-1, CRITICAL_MUSIC_FADE_MS / 2)?;
+                        }
+
+                        // maybe change the theme of every player playing all themes
                         if next_theme {
-                            themes.start_fade(&mut self.canvas)?;
-                            themes.next();
-                            themes.theme().music().fade_in(-1, 1000)?;
+                            for player in 1..=self.game_config.players {
+                                if self.game_config.player_themes(player) == MatchThemes::All {
+                                    themes.next(player);
+                                }
+                            }
+                            themes.start_transition(&mut self.canvas)?;
+                        }
+
+                        // maybe follow a new leader or theme with the music
+                        let leader_theme = themes.player_theme(fixture.leader());
+                        if leader_theme.name() != music_theme {
+                            music_theme = leader_theme.name();
+                            crossfading = false;
+                            leader_theme.music(is_critical).fade_in(-1, 1000)?;
                         }
                     }
                 }
@@ -697,10 +1521,15 @@ impl TetrisSdl {
                 fg_particles.add_source(emit.into_source(&themes, &self.particle_scale));
             }
 
-            // clear
+            // clear, each player has the background colour of their own theme
             self.canvas
-                .set_draw_color(themes.theme().background_color());
+                .set_draw_color(themes.player_theme(1).background_color());
             self.canvas.clear();
+            for player in 2..=self.game_config.players {
+                self.canvas
+                    .set_draw_color(themes.player_theme(player).background_color());
+                self.canvas.fill_rect(themes.player_window(player))?;
+            }
 
             // draw bg particles
             if themes.render_bg_particles() {
@@ -717,7 +1546,7 @@ impl TetrisSdl {
                         {
                             let player = fixture.player(*player_id);
                             themes
-                                .theme()
+                                .player_theme(*player_id)
                                 .draw_background(texture_canvas, &player.game)
                                 .unwrap();
                         }
@@ -726,7 +1555,7 @@ impl TetrisSdl {
                         {
                             let player = fixture.player(*player_id);
                             themes
-                                .theme()
+                                .player_theme(*player_id)
                                 .draw_board(
                                     texture_canvas,
                                     &player.game,
@@ -761,6 +1590,8 @@ impl TetrisSdl {
                 fixture.player_mut(player_id).impact();
             }
 
+            themes.draw_crt_filters(&mut self.canvas)?;
+
             if fixture.state().is_paused() {
                 paused_screen.draw(&mut self.canvas)?;
             }
@@ -770,20 +1601,130 @@ impl TetrisSdl {
     }
 }
 
+/// Loads the config, a config with problems is reported & can be replaced by the defaults or quit
+fn load_config() -> Result<Option<Config>, String> {
+    let (config, errors) = Config::load()?;
+    if errors.is_empty() {
+        return Ok(Some(config));
+    }
+    for error in errors.iter() {
+        println!("{}", error);
+    }
+
+    let message = errors
+        .iter()
+        .map(|e| e.to_string())
+        .collect::<Vec<String>>()
+        .join("\n");
+    let buttons = [
+        ButtonData {
+            flags: MessageBoxButtonFlag::RETURNKEY_DEFAULT,
+            button_id: 1,
+            text: "keep defaults",
+        },
+        ButtonData {
+            flags: MessageBoxButtonFlag::ESCAPEKEY_DEFAULT,
+            button_id: 0,
+            text: "quit",
+        },
+    ];
+    let title = format!("{} config errors", build_info::PKG_NAME);
+    let body = format!("{}\n\nThe config file will not be changed.", message);
+    match show_message_box(MessageBoxFlag::WARNING, &buttons, &title, &body, None, None) {
+        Ok(ClickedButton::CustomButton(button)) if button.button_id == 1 => Ok(Some(config)),
+        Ok(_) => Ok(None),
+        // e.g. no window manager, the problems are already on stdout
+        Err(error) => {
+            println!("cannot show config errors, {}, loading defaults", error);
+            Ok(Some(config))
+        }
+    }
+}
+
+/// Prints any problems with the config file without changing it, an error if there are any
+fn check_config() -> Result<(), String> {
+    let errors = Config::check()?;
+    let path = config_path("config")?;
+    if errors.is_empty() {
+        println!("{} is valid", path.display());
+        return Ok(());
+    }
+    for error in errors.iter() {
+        println!("{}", error);
+    }
+    Err(format!("{} has {} problem(s)", path.display(), errors.len()))
+}
+
 fn main() -> Result<(), String> {
-    let mut rustris = TetrisSdl::new()?;
+    let cli = Cli::parse(std::env::args().skip(1))?;
+    if let Some(path) = cli.config_path {
+        set_config_path(path)?;
+    }
+    match cli.command {
+        Command::Help => {
+            println!("{}", USAGE);
+            return Ok(());
+        }
+        Command::Version => {
+            println!("{} {}", build_info::PKG_NAME, build_info::PKG_VERSION);
+            return Ok(());
+        }
+        Command::CheckConfig => return check_config(),
+        Command::Run => {}
+    }
+
+    let Some(mut config) = load_config()? else {
+        return Ok(());
+    };
+    if let Some(mode) = cli.video_mode {
+        config.video.mode = mode;
+    }
+    let mut rustris = TetrisSdl::new(config)?;
+    if let Some(start) = cli.start {
+        rustris.start_match(start)?;
+    }
+    rustris.video_override = cli.video_mode;
+    loop {
+        match run(&mut rustris)? {
+            RunExit::Quit => return Ok(()),
+            RunExit::RecreateWindow => rustris.recreate_window()?,
+            RunExit::ReloadThemes => {}
+        }
+    }
+}
+
+/// Runs the menus & games until quit or something needs rebuilding
+fn run(rustris: &mut TetrisSdl) -> Result<RunExit, String> {
     let texture_creator = rustris.canvas.texture_creator();
     let (_, window_height) = rustris.canvas.window().size();
-    let all_themes = AllThemes::new(
+    let (mut all_themes, failed_packs) = AllThemes::new(
         &mut rustris.canvas,
         &texture_creator,
         &rustris.ttf,
-        rustris.config,
+        &rustris.config,
         window_height,
+        &rustris.theme_packs,
     )?;
+    if !failed_packs.is_empty() {
+        // e.g. a theme pack that is half way through being edited, carry on without it until it is fixed
+        let mut theme_packs = std::mem::take(&mut rustris.theme_packs);
+        let mut problems = vec![];
+        for (index, error) in failed_packs.into_iter().rev() {
+            theme_packs.remove(index);
+            problems.insert(0, format!("Bad theme pack {}, skipping", error));
+        }
+        rustris.set_theme_packs(theme_packs, problems)?;
+    }
+    // reduced motion caps the particles & turns off background particles everywhere
+    let (max_particles_per_player, max_background_particles) =
+        if rustris.config.video.accessibility.reduced_motion {
+            (REDUCED_MOTION_MAX_PARTICLES_PER_PLAYER, 0)
+        } else {
+            (MAX_PARTICLES_PER_PLAYER, MAX_BACKGROUND_PARTICLES)
+        };
     let mut fg_particles = ParticleRender::new(
         &mut rustris.canvas,
-        Particles::new(MAX_PARTICLES_PER_PLAYER * MAX_PLAYERS as usize),
+        Particles::new(max_particles_per_player * MAX_PLAYERS as usize),
         &texture_creator,
         rustris.particle_scale,
         vec![],
@@ -791,26 +1732,57 @@ fn main() -> Result<(), String> {
 
     let mut bg_particles = ParticleRender::new(
         &mut rustris.canvas,
-        Particles::new(MAX_BACKGROUND_PARTICLES),
+        Particles::new(max_background_particles),
         &texture_creator,
         rustris.particle_scale,
         all_themes.all(),
     )?;
 
+    if let Some(game_config) = rustris.start_match.take() {
+        rustris.game_config = game_config;
+        rustris.play(&all_themes, &mut bg_particles, &mut fg_particles)?;
+        return Ok(RunExit::Quit);
+    }
+
+    let mut modern_skin = rustris.config.video.modern_skin.clone();
     loop {
-        match rustris.main_menu(&mut bg_particles)? {
+        let action = rustris.main_menu(&mut bg_particles)?;
+        if rustris.config.video.modern_skin != modern_skin {
+            modern_skin = rustris.config.video.modern_skin.clone();
+            all_themes.reload_modern(
+                &mut rustris.canvas,
+                &texture_creator,
+                &rustris.ttf,
+                &rustris.config,
+                window_height,
+            )?;
+            bg_particles.reload_theme(&mut rustris.canvas, &texture_creator, all_themes.modern())?;
+        }
+        match action {
             MainMenuAction::Start => {
-                match rustris.game(&all_themes, &mut fg_particles, &mut bg_particles)? {
-                    PostGameAction::NewHighScore(high_score) => {
-                        rustris.new_high_score(high_score, &mut bg_particles)?
-                    }
-                    PostGameAction::ReturnToMenu => (),
-                    PostGameAction::Quit => return Ok(()),
+                if rustris.play(&all_themes, &mut bg_particles, &mut fg_particles)? {
+                    return Ok(RunExit::Quit);
                 }
             }
             MainMenuAction::ViewHighScores => rustris.view_high_score(&mut bg_particles)?,
-            MainMenuAction::Quit => break
+            MainMenuAction::Controls => rustris.controls(&mut bg_particles)?,
+            MainMenuAction::Settings => {
+                if rustris.settings(&mut bg_particles, false)? {
+                    return Ok(RunExit::RecreateWindow);
+                }
+            }
+            MainMenuAction::Reload => {
+                // a reload of only the menu is applied by showing it again
+                if let Some(reload @ (Reload::Themes | Reload::Window)) = rustris.pending_reload.take() {
+                    let (theme_packs, problems) = ThemePack::discover();
+                    rustris.set_theme_packs(theme_packs, problems)?;
+                    return Ok(match reload {
+                        Reload::Window => RunExit::RecreateWindow,
+                        _ => RunExit::ReloadThemes,
+                    });
+                }
+            }
+            MainMenuAction::Quit => return Ok(RunExit::Quit),
         }
     }
-    Ok(())
 }
@@ -11,6 +11,7 @@ use sdl2::render::{BlendMode, Texture, TextureCreator, WindowCanvas};
 use sdl2::ttf::{Font, Sdl2TtfContext};
 use sdl2::video::WindowContext;
 use crate::theme::helper::{CanvasRenderer, TextureFactory};
+use crate::ui::{scroll_to, UiStyle};
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum MenuAction {
@@ -61,13 +62,14 @@ impl<'a> MenuRow<'a> {
         texture_creator: &'a TextureCreator<WindowContext>,
         font: &Font,
         item: MenuItem,
+        style: UiStyle,
     ) -> Result<Self, String> {
         let name_texture = Self::name_texture(
             canvas,
             texture_creator,
             font,
             &item.name,
-            Color::WHITE,
+            style.text_color(),
             None,
         )?;
         let selected_texture = Self::name_texture(
@@ -75,8 +77,8 @@ impl<'a> MenuRow<'a> {
             texture_creator,
             font,
             &item.name,
-            Color::BLACK,
-            Some(Color::WHITE),
+            style.selected_text_color(),
+            Some(style.selected_color()),
         )?;
 
         let mut action_textures = vec![];
@@ -86,7 +88,7 @@ impl<'a> MenuRow<'a> {
                     font,
                     texture_creator,
                     text,
-                    Color::WHITE,
+                    style.text_color(),
                 )?);
             }
         }
@@ -176,12 +178,19 @@ impl<'a> SnippedTexture<'a> {
 pub struct Menu<'a> {
     rows: Vec<MenuRow<'a>>,
     row_rects: Vec<Rect>,
+    /// height of a line of text in a row, rows with the value under the name have two lines
+    line_height: u32,
     current_row_id: usize,
+    /// offset of the visible part of the rows when they don't fit in the window
+    scroll: u32,
+    content_height: u32,
     title: SnippedTexture<'a>,
     subtitle: Option<SnippedTexture<'a>>,
     body: SnippedTexture<'a>,
     watermark: SnippedTexture<'a>,
     select_list_background: Texture<'a>,
+    panel_color: Option<Color>,
+    panel_padding: u32,
 }
 
 impl<'a> Menu<'a> {
@@ -190,13 +199,14 @@ impl<'a> Menu<'a> {
         canvas: &mut WindowCanvas,
         ttf: &Sdl2TtfContext,
         texture_creator: &'a TextureCreator<WindowContext>,
+        style: UiStyle,
         title_text: String,
         subtitle_text: ST,
     ) -> Result<Self, String> {
         assert!(!menu_items.is_empty());
 
         let (window_width, window_height) = canvas.window().size();
-        let font_size = window_width / 32;
+        let font_size = style.font_size(window_width, 32);
         let font = FontType::Retro.load(ttf, font_size)?;
 
         let vertical_gutter = font_size / 3;
@@ -204,49 +214,53 @@ impl<'a> Menu<'a> {
 
         let rows: Vec<MenuRow> = menu_items
             .into_iter()
-            .map(|mi| MenuRow::new(canvas, texture_creator, &font, mi).unwrap())
+            .map(|mi| MenuRow::new(canvas, texture_creator, &font, mi, style).unwrap())
             .collect();
 
-        let row_height = rows.iter().map(|r| r.name_height).max().unwrap();
-        let body_height =
-            row_height * rows.len() as u32 + vertical_gutter * (rows.len() - 1) as u32;
-
+        let line_height = rows.iter().map(|r| r.name_height).max().unwrap();
         let name_width = rows.iter().map(|r| r.name_width).max().unwrap();
         let max_action_width = rows.iter().map(|r| r.max_action_width()).max().unwrap();
-        // + body height as buffer for select list bg
-        let body_width = name_width + horizontal_gutter + max_action_width + row_height / 2;
-
-        let body_rect = Rect::from_center(
-            Rect::new(0, 0, window_width, window_height).center(),
-            body_width,
-            body_height,
-        );
+        // + half a row as buffer for select list bg
+        let wide_body_width = name_width + horizontal_gutter + max_action_width + line_height / 2;
+
+        // reflow the values under the names when the rows are too wide for the window
+        let max_body_width = window_width.saturating_sub(2 * font_size).max(name_width);
+        let is_stacked = wide_body_width > max_body_width;
+        let body_width = if is_stacked {
+            name_width.max(max_action_width + line_height).min(max_body_width)
+        } else {
+            wide_body_width
+        };
 
         let mut row_rects = vec![];
         let mut y = 0;
-        for _ in 0..rows.len() {
+        for row in rows.iter() {
+            let row_height = if is_stacked && !row.item.action.is_select() {
+                2 * line_height
+            } else {
+                line_height
+            };
             row_rects.push(Rect::new(0, y, body_width, row_height));
             y += row_height as i32 + vertical_gutter as i32;
         }
-
-        let body_texture = texture_creator.create_texture_target_blended(body_width, body_height)?;
+        let content_height = (y - vertical_gutter as i32) as u32;
 
         let watermark_font_size = 3 * font_size / 5;
         let watermark_font = FontType::Retro.load(ttf, watermark_font_size)?;
         let watermark = format!("{} v{} by {}", build_info::PKG_NAME, build_info::PKG_VERSION, build_info::PKG_AUTHORS);
         let watermark_texture =
-            FontTexture::from_string(&watermark_font, texture_creator, &watermark, Color::WHITE)?;
+            FontTexture::from_string(&watermark_font, texture_creator, &watermark, style.text_color())?;
         let watermark_rect = Rect::new(
-            (window_width - watermark_texture.width - watermark_font_size) as i32,
-            (window_height - watermark_texture.height - watermark_font_size) as i32,
+            window_width.saturating_sub(watermark_texture.width + watermark_font_size) as i32,
+            window_height.saturating_sub(watermark_texture.height + watermark_font_size) as i32,
             watermark_texture.width,
             watermark_texture.height,
         );
 
-        let title_font_size = window_width / 24;
+        let title_font_size = style.font_size(window_width, 24);
         let title_font = FontType::Retro.load(ttf, title_font_size)?;
         let title_texture =
-            FontTexture::from_string(&title_font, texture_creator, &title_text, Color::WHITE)?;
+            FontTexture::from_string(&title_font, texture_creator, &title_text, style.text_color())?;
         let title_rect = Rect::from_center(
             Rect::new(
                 0,
@@ -261,7 +275,7 @@ impl<'a> Menu<'a> {
 
         let subtitle = subtitle_text.into().map(|text| {
             let texture =
-                FontTexture::from_string(&font, texture_creator, &text, Color::WHITE).unwrap();
+                FontTexture::from_string(&font, texture_creator, &text, style.text_color()).unwrap();
             let rect = Rect::from_center(
                 Rect::new(
                     0,
@@ -276,12 +290,32 @@ impl<'a> Menu<'a> {
             SnippedTexture::new(texture.texture, rect)
         });
 
-        let mut select_list_background = texture_creator.create_texture_target_blended(body_width, row_height)?;
+        // the rows scroll between the titles & the watermark when they don't fit
+        let body_top = subtitle
+            .as_ref()
+            .map(|s| s.snip.bottom())
+            .unwrap_or(title_rect.bottom())
+            + vertical_gutter as i32;
+        let body_bottom = watermark_rect.top() - vertical_gutter as i32;
+        let body_height = content_height
+            .min((body_bottom - body_top).max(line_height as i32) as u32);
+        let mut body_rect = Rect::from_center(
+            Rect::new(0, 0, window_width, window_height).center(),
+            body_width,
+            body_height,
+        );
+        if body_rect.top() < body_top {
+            body_rect.set_y(body_top);
+        }
+
+        let body_texture = texture_creator.create_texture_target_blended(body_width, body_height)?;
+
+        let mut select_list_background = texture_creator.create_texture_target_blended(body_width, line_height)?;
         canvas
             .with_texture_canvas(&mut select_list_background, |c| {
                 c.clear_0();
                 
-                let rect = Rect::new(0, 0, body_width, row_height);
+                let rect = Rect::new(0, 0, body_width, line_height);
                 // TODO this lot is repeated, can be extracted into a canvas trait
                 let rad = rect.height() as i32 / 2;
                 let top_right = rect.top_right();
@@ -292,7 +326,7 @@ impl<'a> Menu<'a> {
                     bottom_left.x() as i16,
                     bottom_left.y() as i16,
                     rad as i16,
-                    Color::RGBA(0xff, 0xff, 0xff, 0x80),
+                    style.select_list_color(),
                 )
                     .unwrap();
             })
@@ -301,15 +335,42 @@ impl<'a> Menu<'a> {
         Ok(Self {
             rows,
             row_rects,
+            line_height,
             current_row_id: 0,
+            scroll: 0,
+            content_height,
             title: SnippedTexture::new(title_texture.texture, title_rect),
             subtitle,
             body: SnippedTexture::new(body_texture, body_rect),
             watermark: SnippedTexture::new(watermark_texture.texture, watermark_rect),
             select_list_background,
+            panel_color: style.panel_color(),
+            panel_padding: vertical_gutter,
         })
     }
 
+    /// Keeps the selection when a menu is rebuilt with new values
+    pub fn with_current_row(mut self, row_id: usize) -> Self {
+        self.set_current_row(row_id);
+        self
+    }
+
+    pub fn set_current_row(&mut self, row_id: usize) {
+        self.current_row_id = row_id.min(self.rows.len() - 1);
+    }
+
+    pub fn current_row(&self) -> usize {
+        self.current_row_id
+    }
+
+    /// Index of the selected value of the current row, always 0 for a select
+    pub fn current_item(&self) -> usize {
+        match &self.rows[self.current_row_id].item.action {
+            MenuAction::SelectList { current, .. } => *current,
+            MenuAction::Select => 0,
+        }
+    }
+
     pub fn up(&mut self) {
         self.current_row_id = match self.current_row_id {
             0 => self.rows.len() - 1,
@@ -382,12 +443,32 @@ impl<'a> Menu<'a> {
     }
 
     pub fn draw(&mut self, canvas: &mut WindowCanvas) -> Result<(), String> {
+        if let Some(panel_color) = self.panel_color {
+            let padding = self.panel_padding;
+            let mut panel = self.body.snip;
+            panel.offset(-(padding as i32), -(padding as i32));
+            panel.resize(panel.width() + 2 * padding, panel.height() + 2 * padding);
+            canvas.set_draw_color(panel_color);
+            canvas.fill_rect(panel)?;
+        }
+
         canvas.copy(&self.title.texture, None, self.title.snip)?;
 
         if let Some(subtitle) = self.subtitle.as_ref() {
             canvas.copy(&subtitle.texture, None, subtitle.snip)?;
         }
 
+        let current_rect = self.row_rects[self.current_row_id];
+        self.scroll = scroll_to(
+            self.scroll,
+            current_rect.top() as u32,
+            current_rect.bottom() as u32,
+            self.body.snip.height(),
+            self.content_height,
+        );
+        let scroll = self.scroll as i32;
+        let line_height = self.line_height;
+
         canvas
             .with_texture_canvas(&mut self.body.texture, |tc| {
                 tc.clear_0();
@@ -395,11 +476,13 @@ impl<'a> Menu<'a> {
                 for (row_id, (row, row_rect)) in
                 self.rows.iter().zip(self.row_rects.iter()).enumerate()
                 {
+                    let mut row_rect = *row_rect;
+                    row_rect.offset(0, -scroll);
                     let is_selected = row_id == self.current_row_id;
 
                     // draw select list background
                     if is_selected && !row.item.action.is_select() {
-                        tc.copy(&self.select_list_background, None, *row_rect)
+                        tc.copy(&self.select_list_background, None, row_rect)
                             .unwrap();
                     }
 
@@ -413,22 +496,28 @@ impl<'a> Menu<'a> {
                     };
                     tc.copy(name_texture, None, name_rect).unwrap();
 
-                    // draw value
+                    // draw value, on the last line of the row when reflowed
                     if let Some(current_action) = row.current_action_id() {
+                        let value_rect = Rect::new(
+                            row_rect.x,
+                            row_rect.bottom() - line_height as i32,
+                            row_rect.width(),
+                            line_height,
+                        );
                         let texture = &row.action_textures[current_action];
                         let offset_by_action_width = -1 * texture.width as i32;
                         let mut rect = Rect::from_enclose_points(
                             &[
-                                row_rect.top_right(),
-                                row_rect.bottom_right(),
-                                row_rect.top_right().offset(offset_by_action_width, 0),
-                                row_rect.bottom_right().offset(offset_by_action_width, 0),
+                                value_rect.top_right(),
+                                value_rect.bottom_right(),
+                                value_rect.top_right().offset(offset_by_action_width, 0),
+                                value_rect.bottom_right().offset(offset_by_action_width, 0),
                             ],
                             None,
                         )
                             .unwrap();
                         // move left a bit for the bg buffer
-                        rect.offset(-1 * row_rect.height() as i32 / 2, 0);
+                        rect.offset(-1 * value_rect.height() as i32 / 2, 0);
                         tc.copy(&texture.texture, None, rect).unwrap();
                     }
                 }
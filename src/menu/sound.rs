@@ -1,5 +1,8 @@
-use crate::config::AudioConfig;
-use crate::theme::sound::{load_sound, play_sound};
+use crate::config::MatchThemes;
+use crate::theme::nes::nes_sound;
+use crate::theme::pack::ThemePack;
+use crate::theme::snes::snes_sound;
+use crate::theme::sound::{load_sound, play_sound, MenuThemeSound};
 use sdl2::mixer::{Chunk, Music};
 
 const CHIME: &[u8] = include_bytes!("chime.ogg");
@@ -10,23 +13,78 @@ pub struct MenuSound {
     chime: Chunk,
     main_menu_music: Music<'static>,
     high_score_music: Music<'static>,
+    themes: Vec<(MatchThemes, MenuThemeSound)>,
+    theme: MatchThemes,
 }
 
 impl MenuSound {
-    pub fn new(config: AudioConfig) -> Result<Self, String> {
-        Ok(Self {
-            chime: load_sound(CHIME, config)?,
+    /// Theme packs with menu sounds that fail to load use the default menu sounds, returned as problems
+    pub fn new(theme_packs: &[ThemePack]) -> Result<(Self, Vec<String>), String> {
+        let mut themes = vec![
+            (MatchThemes::Nes, nes_sound().build_menu()?),
+            (MatchThemes::Snes, snes_sound().build_menu()?),
+        ];
+        let mut problems = vec![];
+        for (id, pack) in theme_packs.iter().enumerate() {
+            match pack.sound().build_menu() {
+                Ok(sound) => themes.push((MatchThemes::External(id), sound)),
+                Err(error) => problems.push(format!("Bad theme pack menu sound {}, {}", pack.name(), error)),
+            }
+        }
+        let menu_sound = Self {
+            chime: load_sound(CHIME)?,
             main_menu_music: Music::from_static_bytes(MAIN_MENU_MUSIC)?,
             high_score_music: Music::from_static_bytes(HIGH_SCORE_MUSIC)?,
-        })
+            themes,
+            theme: MatchThemes::All,
+        };
+        Ok((menu_sound, problems))
+    }
+
+    fn theme_sound(&self, theme: MatchThemes) -> Option<&MenuThemeSound> {
+        self.themes
+            .iter()
+            .find(|(t, _)| *t == theme)
+            .map(|(_, sound)| sound)
+    }
+
+    fn main_menu_music(&self, theme: MatchThemes) -> &Music<'static> {
+        self.theme_sound(theme)
+            .and_then(|sound| sound.music.as_deref())
+            .unwrap_or(&self.main_menu_music)
+    }
+
+    /// The theme whose menu music plays for the selected theme, none for the default menu music
+    fn main_menu_music_theme(&self, theme: MatchThemes) -> Option<MatchThemes> {
+        self.theme_sound(theme)
+            .and_then(|sound| sound.music.as_ref())
+            .map(|_| theme)
+    }
+
+    /// Switch to the menu sounds of the selected theme, changing the music if it differs
+    pub fn set_theme(&mut self, theme: MatchThemes) -> Result<(), String> {
+        let current = self.main_menu_music_theme(self.theme);
+        let next = self.main_menu_music_theme(theme);
+        self.theme = theme;
+        if current != next {
+            self.play_main_menu_music()?;
+        }
+        Ok(())
     }
 
     pub fn play_chime(&self) -> Result<(), String> {
         play_sound(&self.chime)
     }
 
+    pub fn play_start(&self) -> Result<(), String> {
+        match self.theme_sound(self.theme).and_then(|sound| sound.start.as_ref()) {
+            Some(start) => play_sound(start),
+            None => self.play_chime(),
+        }
+    }
+
     pub fn play_main_menu_music(&self) -> Result<(), String> {
-        self.main_menu_music.play(-1)
+        self.main_menu_music(self.theme).play(-1)
     }
 
     pub fn play_high_score_music(&self) -> Result<(), String> {
@@ -1,6 +1,6 @@
-use crate::config::InputConfig;
+use crate::config::{BoundKey, InputConfig, PadInput};
+use crate::gamepad::InputEvent;
 use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
 use std::collections::HashMap;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -15,28 +15,37 @@ pub enum MenuInputKey {
 }
 
 pub struct MenuInputContext {
-    mapping: HashMap<Keycode, MenuInputKey>,
+    mapping: HashMap<BoundKey, MenuInputKey>,
+    pad_mapping: HashMap<PadInput, MenuInputKey>,
 }
 
 impl MenuInputContext {
-    pub fn new(config: InputConfig) -> Self {
+    pub fn new(config: &InputConfig) -> Self {
         Self {
             mapping: config.menu_map(),
+            pad_mapping: config.gamepad_menu_map(),
         }
     }
 
-    pub fn parse<I>(&self, sdl_events: I) -> Vec<MenuInputKey>
+    pub fn parse<I>(&self, events: I) -> Vec<MenuInputKey>
     where
-        I: Iterator<Item = Event>,
+        I: Iterator<Item = InputEvent>,
     {
         let mut result: Vec<MenuInputKey> = vec![];
-        for event in sdl_events {
+        for event in events {
             let maybe_key = match event {
-                Event::KeyDown {
-                    keycode: Some(keycode),
+                InputEvent::Sdl(Event::KeyDown {
+                    keycode, scancode, ..
+                }) => BoundKey::from_event(keycode, scancode)
+                    .into_iter()
+                    .find_map(|bound| self.mapping.get(&bound).copied()),
+                InputEvent::Sdl(Event::Quit { .. }) => Some(MenuInputKey::Quit),
+                // any connected controller can drive the menus
+                InputEvent::Pad {
+                    input,
+                    pressed: true,
                     ..
-                } => self.mapping.get(&keycode).copied(),
-                Event::Quit { .. } => Some(MenuInputKey::Quit),
+                } => self.pad_mapping.get(&input).copied(),
                 _ => None,
             };
             if let Some(key) = maybe_key {
@@ -0,0 +1,303 @@
+use crate::build_info;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_yaml::{Mapping, Value};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+const VERSION_KEY: &str = "version";
+
+/// Upgrades a document from one version to the next, given the document of the defaults at the latest version
+pub type Migration = fn(&mut Value, &Value);
+
+/// Schema of a versioned yaml file, migration n upgrades version n to n + 1.
+/// Files written before versioning are version 0.
+pub struct Schema {
+    pub version: u32,
+    pub migrations: &'static [Migration],
+}
+
+pub const CONFIG_SCHEMA: Schema = Schema {
+    version: 2,
+    migrations: &[fill_defaults, add_retry_key],
+};
+
+pub const HIGH_SCORE_SCHEMA: Schema = Schema {
+    version: 1,
+    migrations: &[fill_defaults],
+};
+
+pub enum LoadError {
+    Yaml(serde_yaml::Error),
+    Io(String),
+}
+
+pub struct Loaded<T> {
+    pub value: T,
+    /// version of the file before any migrations
+    pub version: u32,
+}
+
+impl Schema {
+    /// Parses & migrates a document, a document from a newer version is parsed ignoring any unknown fields
+    pub fn parse<T: DeserializeOwned + Serialize + Default>(
+        &self,
+        yaml: &str,
+    ) -> Result<Loaded<T>, serde_yaml::Error> {
+        let mut document: Value = serde_yaml::from_str(yaml)?;
+        let version = document_version(&document);
+        if version >= self.version {
+            return Ok(Loaded {
+                value: serde_yaml::from_str(yaml)?,
+                version,
+            });
+        }
+
+        let defaults = serde_yaml::to_value(T::default())?;
+        for migration in self.migrations.iter().skip(version as usize) {
+            migration(&mut document, &defaults);
+        }
+        // deserialize from text so that errors still have the path of the field
+        let migrated = serde_yaml::to_string(&document)?;
+        Ok(Loaded {
+            value: serde_yaml::from_str(&migrated)?,
+            version,
+        })
+    }
+
+    /// Loads a file, creating it with the defaults if it doesn't exist. Nothing is written for an existing file.
+    pub fn load<T: DeserializeOwned + Serialize + Default>(
+        &self,
+        path: &Path,
+    ) -> Result<Loaded<T>, LoadError> {
+        let yaml = match fs::read_to_string(path) {
+            Ok(yaml) => yaml,
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                let value = T::default();
+                self.store(path, &value).map_err(LoadError::Io)?;
+                return Ok(Loaded {
+                    value,
+                    version: self.version,
+                });
+            }
+            Err(e) => return Err(LoadError::Io(e.to_string())),
+        };
+        let loaded = self.parse(&yaml).map_err(LoadError::Yaml)?;
+        if loaded.version > self.version {
+            println!(
+                "{} is from a newer version of {}, unknown fields are ignored & it will not be changed",
+                path.display(),
+                build_info::PKG_NAME
+            );
+        }
+        Ok(loaded)
+    }
+
+    /// Writes a loaded file that was migrated from an older version, keeping a backup of the original
+    pub fn upgrade_file<T: Serialize>(&self, path: &Path, loaded: &Loaded<T>) -> Result<(), String> {
+        if loaded.version >= self.version {
+            return Ok(());
+        }
+        let backup = backup_path(path, loaded.version);
+        fs::copy(path, &backup).map_err(|e| e.to_string())?;
+        self.store(path, &loaded.value)?;
+        println!(
+            "upgraded {} from version {} to {}, the original is at {}",
+            path.display(),
+            loaded.version,
+            self.version,
+            backup.display()
+        );
+        Ok(())
+    }
+
+    /// Writes the file with the current version, refusing to overwrite a file from a newer version
+    pub fn store<T: Serialize>(&self, path: &Path, value: &T) -> Result<(), String> {
+        if let Ok(existing) = fs::read_to_string(path) {
+            let version = serde_yaml::from_str::<Value>(&existing)
+                .map(|document| document_version(&document))
+                .unwrap_or(0);
+            if version > self.version {
+                return Err(format!(
+                    "not saved, {} is from a newer version of {}",
+                    path.display(),
+                    build_info::PKG_NAME
+                ));
+            }
+        }
+
+        let mut document = Mapping::new();
+        document.insert(VERSION_KEY.into(), self.version.into());
+        match serde_yaml::to_value(value).map_err(|e| e.to_string())? {
+            Value::Mapping(fields) => document.extend(fields),
+            _ => return Err("versioned files must be a mapping".to_string()),
+        }
+        let yaml = serde_yaml::to_string(&document).map_err(|e| e.to_string())?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        fs::write(path, yaml).map_err(|e| e.to_string())
+    }
+}
+
+fn document_version(document: &Value) -> u32 {
+    document
+        .get(VERSION_KEY)
+        .and_then(Value::as_u64)
+        .map(|version| version as u32)
+        .unwrap_or(0)
+}
+
+/// e.g. `config.yml` is backed up to `config.v0.yml.bak`
+fn backup_path(path: &Path, version: u32) -> PathBuf {
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_string())
+        .unwrap_or_default();
+    path.with_extension(format!("v{}.{}.bak", version, extension))
+}
+
+/// Adds any fields missing from older documents with their default values
+fn fill_defaults(document: &mut Value, defaults: &Value) {
+    if let (Value::Mapping(fields), Value::Mapping(default_fields)) = (document, defaults) {
+        for (key, default) in default_fields {
+            match fields.get_mut(key) {
+                Some(field) => fill_defaults(field, default),
+                None => {
+                    fields.insert(key.clone(), default.clone());
+                }
+            }
+        }
+    }
+}
+
+/// v2 added the retry key, it is left unbound if its default key is already bound to something else.
+/// Older migrations fill in the latest defaults so any retry key already present is not counted.
+fn add_retry_key(document: &mut Value, defaults: &Value) {
+    let default_retry = &defaults["input"]["retry"];
+    let is_bound = |input: &Value| match input {
+        Value::Mapping(fields) => fields
+            .iter()
+            .any(|(key, value)| key.as_str() != Some("retry") && contains(value, default_retry)),
+        _ => false,
+    };
+    let in_use = !matches!(default_retry, Value::Null | Value::Sequence(_))
+        && document.get("input").is_some_and(is_bound);
+    fill_defaults(document, defaults);
+    if in_use {
+        document["input"]["retry"] = Value::Null;
+    }
+}
+
+fn contains(value: &Value, target: &Value) -> bool {
+    match value {
+        Value::Sequence(values) => values.iter().any(|v| contains(v, target)),
+        Value::Mapping(fields) => fields.values().any(|v| contains(v, target)),
+        value => value == target,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Example {
+        name: String,
+        inner: Inner,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Inner {
+        a: u32,
+        b: u32,
+    }
+
+    impl Default for Example {
+        fn default() -> Self {
+            Self {
+                name: "default".to_string(),
+                inner: Inner { a: 1, b: 2 },
+            }
+        }
+    }
+
+    #[test]
+    fn unversioned_documents_are_migrated() {
+        let schema = Schema {
+            version: 1,
+            migrations: &[fill_defaults],
+        };
+        let loaded: Loaded<Example> = schema.parse("name: old\ninner:\n  a: 5\n").unwrap();
+        assert_eq!(loaded.version, 0);
+        assert_eq!(
+            loaded.value,
+            Example {
+                name: "old".to_string(),
+                inner: Inner { a: 5, b: 2 }
+            }
+        );
+    }
+
+    #[test]
+    fn current_documents_are_not_migrated() {
+        let schema = Schema {
+            version: 1,
+            migrations: &[fill_defaults],
+        };
+        let result = schema.parse::<Example>("version: 1\nname: new\ninner:\n  a: 5\n");
+        let error = result.err().unwrap().to_string();
+        assert!(error.starts_with("inner: missing field `b`"), "{}", error);
+    }
+
+    #[test]
+    fn future_documents_ignore_unknown_fields() {
+        let schema = Schema {
+            version: 1,
+            migrations: &[fill_defaults],
+        };
+        let loaded: Loaded<Example> = schema
+            .parse("version: 7\nname: new\ninner:\n  a: 5\n  b: 6\n  c: 7\nfuture: true\n")
+            .unwrap();
+        assert_eq!(loaded.version, 7);
+        assert_eq!(loaded.value.inner, Inner { a: 5, b: 6 });
+    }
+
+    #[test]
+    fn retry_key_is_only_added_if_unused() {
+        let defaults: Value = serde_yaml::from_str("input:\n  pause: F1\n  retry: F5\n").unwrap();
+
+        let mut unused: Value = serde_yaml::from_str("input:\n  pause: F1\n").unwrap();
+        add_retry_key(&mut unused, &defaults);
+        assert_eq!(unused["input"]["retry"], Value::from("F5"));
+
+        let mut used: Value = serde_yaml::from_str("input:\n  pause: [ F1, F5 ]\n").unwrap();
+        add_retry_key(&mut used, &defaults);
+        assert_eq!(used["input"]["retry"], Value::Null);
+    }
+
+    #[test]
+    fn unversioned_configs_get_the_retry_key() {
+        let default_retry = crate::config::Config::default().input.retry;
+
+        let loaded: Loaded<crate::config::Config> =
+            CONFIG_SCHEMA.parse("input:\n  pause: F1\n").unwrap();
+        assert_eq!(loaded.version, 0);
+        assert_eq!(loaded.value.input.retry, default_retry);
+
+        let loaded: Loaded<crate::config::Config> =
+            CONFIG_SCHEMA.parse("input:\n  pause: [ F1, F5 ]\n").unwrap();
+        assert!(loaded.value.input.retry.0.is_empty());
+    }
+
+    #[test]
+    fn backup_path_keeps_the_version() {
+        assert_eq!(
+            backup_path(Path::new("/a/config.yml"), 0),
+            PathBuf::from("/a/config.v0.yml.bak")
+        );
+    }
+}
@@ -63,6 +63,21 @@ impl<'a> ParticleRender<'a> {
         })
     }
 
+    /// Replace the tetromino sprites of a theme that has been rebuilt
+    pub fn reload_theme(
+        &mut self,
+        canvas: &mut WindowCanvas,
+        texture_creator: &'a TextureCreator<WindowContext>,
+        theme: &Theme<'a>,
+    ) -> Result<(), String> {
+        if let Some(sprite_sheet) = self.tetromino_sprite_sheets.get_mut(&theme.name()) {
+            *sprite_sheet = theme
+                .sprite_sheet()
+                .flatten(canvas, texture_creator, MinoType::Normal)?;
+        }
+        Ok(())
+    }
+
     pub fn clear(&mut self) {
         self.particles.clear();
     }
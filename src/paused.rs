@@ -1,13 +1,39 @@
-use crate::font::{FontTexture, FontType};
-use sdl2::pixels::Color;
-use sdl2::rect::Rect;
+use crate::menu::{Menu, MenuItem};
+use crate::menu_input::MenuInputKey;
+use crate::theme::helper::TextureFactory;
+use crate::ui::UiStyle;
 use sdl2::render::{BlendMode, Texture, TextureCreator, WindowCanvas};
 use sdl2::ttf::Sdl2TtfContext;
 use sdl2::video::WindowContext;
-use crate::theme::helper::TextureFactory;
 
+const RESUME: &str = "resume";
+const RESTART: &str = "restart";
+const SEED: &str = "seed";
+const NEW_SEED: &str = "new";
+const SAME_SEED: &str = "same";
+const SETTINGS: &str = "settings";
+const QUIT_TO_MENU: &str = "quit to menu";
+const NO: &str = "no";
+const YES: &str = "yes";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PauseAction {
+    Resume,
+    /// restart with the same game config, optionally dealing the same tetrominoes & garbage
+    Restart { same_seed: bool },
+    Settings,
+    /// end the match & return to the menu
+    Forfeit,
+}
+
+/// The pause menu drawn over the paused match, restarting & forfeiting must be confirmed
 pub struct PausedScreen<'a> {
-    texture: Texture<'a>,
+    background: Texture<'a>,
+    menu: Menu<'a>,
+    confirm_restart: Menu<'a>,
+    confirm_forfeit: Menu<'a>,
+    confirming: Option<PauseAction>,
+    same_seed: bool,
 }
 
 impl<'a> PausedScreen<'a> {
@@ -16,28 +42,130 @@ impl<'a> PausedScreen<'a> {
         ttf: &Sdl2TtfContext,
         texture_creator: &'a TextureCreator<WindowContext>,
         (window_width, window_height): (u32, u32),
+        style: UiStyle,
     ) -> Result<Self, String> {
-        let font = FontType::Bold.load(ttf, window_width / 24)?;
-        let font_texture =
-            FontTexture::from_string(&font, texture_creator, "Paused", Color::BLACK)?;
-        let font_rect = Rect::from_center(
-            (window_width as i32 / 2, window_height as i32 / 2),
-            font_texture.width,
-            font_texture.height,
-        );
-
-        let mut texture = texture_creator.create_texture_target_blended(window_width, window_height)?;
+        let mut background =
+            texture_creator.create_texture_target_blended(window_width, window_height)?;
         canvas
-            .with_texture_canvas(&mut texture, |c| {
-                c.set_draw_color(Color::RGBA(0xff, 0xff, 0xff, 0xdd));
+            .with_texture_canvas(&mut background, |c| {
+                c.set_draw_color(style.paused_color());
                 c.clear();
-                c.copy(&font_texture.texture, None, font_rect).unwrap();
             })
             .map_err(|e| e.to_string())?;
-        Ok(Self { texture })
+
+        let menu = Menu::new(
+            vec![
+                MenuItem::select(RESUME),
+                MenuItem::select(RESTART),
+                MenuItem::select_list(SEED, vec![NEW_SEED.to_string(), SAME_SEED.to_string()], 0),
+                MenuItem::select(SETTINGS),
+                MenuItem::select(QUIT_TO_MENU),
+            ],
+            canvas,
+            ttf,
+            texture_creator,
+            style,
+            "PAUSED".to_string(),
+            None,
+        )?;
+        let confirm = |canvas: &mut WindowCanvas, title: &str, subtitle: &str| {
+            Menu::new(
+                vec![MenuItem::select(NO), MenuItem::select(YES)],
+                canvas,
+                ttf,
+                texture_creator,
+                style,
+                title.to_string(),
+                subtitle.to_string(),
+            )
+        };
+        let confirm_restart = confirm(canvas, "RESTART?", "the current match will be lost")?;
+        let confirm_forfeit = confirm(canvas, "QUIT TO MENU?", "the current match is forfeit")?;
+
+        Ok(Self {
+            background,
+            menu,
+            confirm_restart,
+            confirm_forfeit,
+            confirming: None,
+            same_seed: false,
+        })
+    }
+
+    /// Shows the pause menu from the top
+    pub fn open(&mut self) {
+        self.confirming = None;
+        self.menu.set_current_row(0);
+    }
+
+    /// Asks to confirm forfeiting, e.g. when quit is pressed mid match
+    pub fn confirm_forfeit(&mut self) {
+        self.confirm(PauseAction::Forfeit);
+    }
+
+    /// Reads a menu key, quit backs out of a confirmation & then resumes
+    pub fn read_key(&mut self, key: MenuInputKey) -> Option<PauseAction> {
+        if let Some(action) = self.confirming {
+            let menu = match action {
+                PauseAction::Forfeit => &mut self.confirm_forfeit,
+                _ => &mut self.confirm_restart,
+            };
+            let answer = match key {
+                MenuInputKey::Quit => Some(NO),
+                key => menu.read_key(key).map(|(name, _)| name),
+            };
+            return match answer {
+                Some(YES) => {
+                    self.confirming = None;
+                    Some(action)
+                }
+                Some(_) => {
+                    self.confirming = None;
+                    None
+                }
+                None => None,
+            };
+        }
+
+        if key == MenuInputKey::Quit {
+            return Some(PauseAction::Resume);
+        }
+        match self.menu.read_key(key)? {
+            (RESUME, _) => Some(PauseAction::Resume),
+            (RESTART, _) => {
+                self.confirm(PauseAction::Restart {
+                    same_seed: self.same_seed,
+                });
+                None
+            }
+            (SEED, value) => {
+                self.same_seed = value == SAME_SEED;
+                None
+            }
+            (SETTINGS, _) => Some(PauseAction::Settings),
+            (QUIT_TO_MENU, _) => {
+                self.confirm_forfeit();
+                None
+            }
+            _ => None,
+        }
+    }
+
+    pub fn draw(&mut self, canvas: &mut WindowCanvas) -> Result<(), String> {
+        canvas.copy(&self.background, None, None)?;
+        match self.confirming {
+            Some(PauseAction::Forfeit) => self.confirm_forfeit.draw(canvas),
+            Some(_) => self.confirm_restart.draw(canvas),
+            None => self.menu.draw(canvas),
+        }
     }
 
-    pub fn draw(&self, canvas: &mut WindowCanvas) -> Result<(), String> {
-        canvas.copy(&self.texture, None, None)
+    /// Confirmations always start on no
+    fn confirm(&mut self, action: PauseAction) {
+        self.confirming = Some(action);
+        match action {
+            PauseAction::Forfeit => self.confirm_forfeit.set_current_row(0),
+            _ => self.confirm_restart.set_current_row(0),
+        }
     }
 }
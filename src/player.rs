@@ -2,18 +2,24 @@ use crate::animation::destroy::{DestroyAnimation, DestroyAnimationType};
 use crate::animation::game_over::{GameOverAnimate, GameOverAnimation, GameOverAnimationType};
 use crate::animation::impact::ImpactAnimation;
 use crate::animation::{TextureAnimate, TextureAnimation};
-use crate::config::{Config, GameConfig, MatchRules};
+use crate::config::{config_sibling_path, Config, GameConfig, MatchRules};
 use crate::event::GameEvent;
 use crate::game::board::{compact_destroy_lines, DestroyLines};
-use crate::game::random::RandomTetromino;
+use crate::game::random::{RandomMode, RandomTetromino};
+use crate::game::ruleset::Ruleset;
 use crate::game::{Game, GameMetrics};
 use crate::high_score::table::HighScoreTable;
-use crate::high_score::NewHighScore;
+use crate::high_score::{HighScoreCategory, NewHighScore};
 
 use rand::Rng;
 
 use crate::particles::prescribed::{PlayerParticleTarget, PlayerTargetedParticles};
-use std::time::Duration;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Abandoned attempts are appended to this file next to the config
+const ABANDONED_LOG: &str = "abandoned.log";
 
 pub struct Player {
     pub player: u32,
@@ -25,10 +31,10 @@ pub struct Player {
 }
 
 impl Player {
-    pub fn new(player: u32, random: RandomTetromino, level: u32) -> Self {
+    pub fn new(player: u32, random: RandomTetromino, level: u32, ruleset: Ruleset) -> Self {
         Self {
             player,
-            game: Game::new(player, level, random),
+            game: Game::new(player, level, random, ruleset),
             destroy_animation: None,
             game_over_animation: None,
             impact_animation: ImpactAnimation::new(),
@@ -125,30 +131,56 @@ impl MatchState {
 pub struct Match {
     pub players: Vec<Player>,
     high_scores: HighScoreTable,
+    high_score_category: HighScoreCategory,
     state: MatchState,
     rules: MatchRules,
+    seed: u64,
+    /// time in play, not counting pauses
+    play_time: Duration,
 }
 
 impl Match {
-    pub fn new(game_config: GameConfig, config: Config) -> Self {
+    pub fn new(game_config: GameConfig, config: &Config) -> Self {
         if game_config.players == 0 {
             panic!("must have at least one player")
         }
 
-        let randoms = config.game.random_mode.build(
+        let random_mode = if game_config.ruleset.is_classic() {
+            RandomMode::Nes
+        } else {
+            config.game.random_mode
+        };
+        let seed = game_config.seed.unwrap_or_else(|| rand::thread_rng().gen());
+        let randoms = random_mode.build(
             game_config.players as usize,
             config.game.min_garbage_per_hole,
+            seed,
         );
 
+        let mut players = randoms
+            .into_iter()
+            .enumerate()
+            .map(|(pid, rand)| {
+                Player::new(pid as u32 + 1, rand, game_config.level, game_config.ruleset)
+            })
+            .collect::<Vec<Player>>();
+
+        if game_config.rules.is_b_type() {
+            // all players share a seed so all get the same garbage
+            for player in players.iter_mut() {
+                player.game.fill_b_type_garbage(game_config.height);
+            }
+        }
+
+        let high_score_category = HighScoreCategory::from_game_config(game_config);
         Self {
-            players: randoms
-                .into_iter()
-                .enumerate()
-                .map(|(pid, rand)| Player::new(pid as u32 + 1, rand, game_config.level))
-                .collect::<Vec<Player>>(),
-            high_scores: HighScoreTable::load().unwrap(),
+            players,
+            high_scores: HighScoreTable::load(high_score_category).unwrap(),
+            high_score_category,
             state: MatchState::Normal,
             rules: game_config.rules,
+            seed,
+            play_time: Duration::ZERO,
         }
     }
 
@@ -181,6 +213,56 @@ impl Match {
         self.state
     }
 
+    pub fn update_play_time(&mut self, delta: Duration) {
+        if self.state == MatchState::Normal {
+            self.play_time += delta;
+        }
+    }
+
+    /// The time in play & the level, lines & score of each player e.g. to log an abandoned match
+    pub fn summary(&self) -> String {
+        let seconds = self.play_time.as_secs_f64();
+        let players = self
+            .players
+            .iter()
+            .map(|p| {
+                let metrics = p.game.metrics();
+                format!(
+                    "player {} level {}, {} lines, {} points",
+                    metrics.player, metrics.level, metrics.lines, metrics.score
+                )
+            })
+            .collect::<Vec<String>>()
+            .join(", ");
+        format!(
+            "{} after {}:{:06.3}, {}",
+            self.rules.name(),
+            (seconds / 60.0) as u32,
+            seconds % 60.0,
+            players
+        )
+    }
+
+    /// Appends the summary to the abandoned attempts log, console output is lost on windows
+    pub fn log_abandoned(&self) -> Result<(), String> {
+        let path = config_sibling_path(ABANDONED_LOG)?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| e.to_string())?
+            .as_secs();
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| writeln!(file, "{} {}", utc_date_time(now), self.summary()))
+            .map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Seed of the tetrominoes & garbage, a match with the same config & seed deals the same pieces
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
     pub fn check_for_winning_player(&self) -> Option<u32> {
         match self.rules {
             MatchRules::ScoreSprint {
@@ -195,6 +277,9 @@ impl Match {
             }
             MatchRules::LineSprint {
                 lines: sprint_lines,
+            }
+            | MatchRules::BType {
+                lines: sprint_lines,
             } => {
                 let best_game = self.most_lines();
                 if best_game.lines >= sprint_lines {
@@ -207,29 +292,59 @@ impl Match {
         }
     }
 
-    pub fn set_winner(&mut self, player: u32, animation_type: GameOverAnimationType) {
+    pub fn set_winner<F>(&mut self, player: u32, animation_type: F)
+    where
+        F: Fn(u32) -> GameOverAnimationType,
+    {
         for losing_player in self.players.iter_mut().filter(|p| p.player != player) {
-            losing_player.animate_game_over(animation_type);
+            losing_player.animate_game_over(animation_type(losing_player.player));
         }
-        self.state = MatchState::GameOver { high_score: None };
-    }
-
-    pub fn set_game_over(&mut self, player: u32, animation_type: GameOverAnimationType) {
-        let best_game = self.highest_score();
 
-        let high_score = if self.high_scores.is_high_score(best_game.score) {
-            Some(NewHighScore::new(best_game.player, best_game.score))
+        let high_score = if self.rules.is_b_type() {
+            // only a completed b-type game is scored, including a bonus for the remaining stack
+            let winning_game = &mut self.player_mut(player).game;
+            winning_game.add_b_type_bonus();
+            let score = winning_game.metrics().score;
+            if self.high_scores.is_high_score(score) {
+                Some(NewHighScore::new(player, score, self.high_score_category))
+            } else {
+                None
+            }
         } else {
             None
         };
-
         self.state = MatchState::GameOver { high_score };
+    }
+
+    pub fn set_game_over(&mut self, player: u32, animation_type: GameOverAnimationType) {
+        self.state = MatchState::GameOver {
+            high_score: self.game_over_high_score(),
+        };
         self.players
             .get_mut(player as usize - 1)
             .unwrap()
             .animate_game_over(animation_type);
     }
 
+    /// Ends the match early, it is scored as if the leader topped out
+    pub fn forfeit(&mut self) -> Option<NewHighScore> {
+        let high_score = self.game_over_high_score();
+        self.state = MatchState::GameOver { high_score };
+        high_score
+    }
+
+    fn game_over_high_score(&self) -> Option<NewHighScore> {
+        let best_game = self.highest_score();
+        if self.rules.is_b_type() {
+            // topping out of a b-type game is not scored
+            None
+        } else if self.high_scores.is_high_score(best_game.score) {
+            Some(NewHighScore::new(best_game.player, best_game.score, self.high_score_category))
+        } else {
+            None
+        }
+    }
+
     pub fn mut_game<F>(&mut self, player: u32, mut f: F) -> Option<GameEvent>
     where
         F: FnMut(&mut Game) -> Option<GameEvent>,
@@ -280,6 +395,25 @@ impl Match {
             .send_garbage(garbage_lines);
     }
 
+    /// The player with the highest score, ties go to the lowest player
+    pub fn leader(&self) -> u32 {
+        let best_game = self.highest_score();
+        self.players
+            .iter()
+            .find(|p| p.game.metrics().score == best_game.score)
+            .unwrap()
+            .player
+    }
+
+    /// The stack height of the player in the most danger
+    pub fn max_stack_height(&self) -> u32 {
+        self.players
+            .iter()
+            .map(|p| p.game.stack_height())
+            .max()
+            .unwrap()
+    }
+
     fn highest_score(&self) -> GameMetrics {
         self.players
             .iter()
@@ -296,3 +430,38 @@ impl Match {
             .unwrap()
     }
 }
+
+/// e.g. `2024-05-01 13:45:00`, from days to the civil calendar as in Howard Hinnant's date algorithms
+fn utc_date_time(unix_seconds: u64) -> String {
+    let days = (unix_seconds / 86400) as i64 + 719468;
+    let seconds = unix_seconds % 86400;
+    let era = days / 146097;
+    let day_of_era = days - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    format!(
+        "{}-{:02}-{:02} {:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn utc_date_time_from_unix_seconds() {
+        assert_eq!(utc_date_time(0), "1970-01-01 00:00:00");
+        assert_eq!(utc_date_time(951782400), "2000-02-29 00:00:00");
+        assert_eq!(utc_date_time(1714571100), "2024-05-01 13:45:00");
+    }
+}
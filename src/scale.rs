@@ -19,7 +19,7 @@ impl Scale {
         game_size: (u32, u32),
         window_size: (u32, u32),
         block_size: u32,
-        config: VideoConfig,
+        config: &VideoConfig,
         theme: ThemeName
     ) -> Self {
         let (window_width, window_height) = window_size;
@@ -52,6 +52,11 @@ impl Scale {
         }
     }
 
+    /// size of a source pixel in the window
+    pub fn pixel_size(&self) -> u32 {
+        self.integer_scale.unwrap_or(self.scale.round() as u32).max(1)
+    }
+
     /// splits the entire window up into horizontally stacked chunks equally between players
     pub fn player_window(&self, player: u32) -> Rect {
         let player_chunk_width = self.window_width / self.players;
@@ -0,0 +1,204 @@
+use crate::config::{AudioConfig, Config, GameplayConfig, VideoMode};
+use crate::game::random::RandomMode;
+use crate::menu::MenuItem;
+
+pub const VIDEO_MODE: &str = "video mode";
+pub const VSYNC: &str = "vsync";
+pub const INTEGER_SCALE: &str = "integer scale";
+pub const MUSIC_VOLUME: &str = "music volume";
+pub const EFFECTS_VOLUME: &str = "effects volume";
+pub const RANDOM_MODE: &str = "random mode";
+pub const GARBAGE_PER_HOLE: &str = "garbage per hole";
+pub const SAVE_SETTINGS: &str = "save";
+pub const CANCEL_SETTINGS: &str = "cancel";
+
+const OFF: &str = "off";
+const ON: &str = "on";
+const VOLUME_STEPS: usize = 10;
+const MAX_GARBAGE_PER_HOLE: u32 = 20;
+
+/// The values editable on the settings screen
+#[derive(Clone, Debug, PartialEq)]
+pub struct Settings {
+    pub mode: VideoMode,
+    pub vsync: bool,
+    pub integer_scale: bool,
+    pub audio: AudioConfig,
+    pub game: GameplayConfig,
+}
+
+impl Settings {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            mode: config.video.mode,
+            vsync: config.video.vsync,
+            integer_scale: config.video.integer_scale,
+            audio: config.audio,
+            game: config.game,
+        }
+    }
+
+    pub fn apply(&self, config: &mut Config) {
+        config.video.mode = self.mode;
+        config.video.vsync = self.vsync;
+        config.video.integer_scale = self.integer_scale;
+        config.audio = self.audio;
+        config.game = self.game;
+    }
+
+    /// Video changes need the window to be recreated
+    pub fn is_video_changed(&self, other: &Self) -> bool {
+        self.mode != other.mode
+            || self.vsync != other.vsync
+            || self.integer_scale != other.integer_scale
+    }
+
+    pub fn menu_items(&self, video_modes: &[VideoMode]) -> Vec<MenuItem> {
+        let garbage_per_hole = garbage_per_hole_options(self.game.min_garbage_per_hole);
+        vec![
+            MenuItem::select_list(
+                VIDEO_MODE,
+                video_modes.iter().map(|m| m.name()).collect(),
+                video_modes.iter().position(|&m| m == self.mode).unwrap_or(0),
+            ),
+            MenuItem::select_list(VSYNC, on_off(), self.vsync as usize),
+            MenuItem::select_list(INTEGER_SCALE, on_off(), self.integer_scale as usize),
+            MenuItem::select_list(MUSIC_VOLUME, slider_labels(), slider_step(self.audio.music_volume)),
+            MenuItem::select_list(
+                EFFECTS_VOLUME,
+                slider_labels(),
+                slider_step(self.audio.effects_volume),
+            ),
+            MenuItem::select_list(
+                RANDOM_MODE,
+                RandomMode::ALL.iter().map(|m| m.name().to_string()).collect(),
+                RandomMode::ALL
+                    .iter()
+                    .position(|&m| m == self.game.random_mode)
+                    .unwrap(),
+            ),
+            MenuItem::select_list(
+                GARBAGE_PER_HOLE,
+                garbage_per_hole.iter().map(|i| i.to_string()).collect(),
+                garbage_per_hole
+                    .iter()
+                    .position(|&i| i == self.game.min_garbage_per_hole)
+                    .unwrap(),
+            ),
+            MenuItem::select(SAVE_SETTINGS),
+            MenuItem::select(CANCEL_SETTINGS),
+        ]
+    }
+
+    /// Update from a changed menu item, returns false if the item is not a setting
+    pub fn read(&mut self, name: &str, value: &str, video_modes: &[VideoMode]) -> bool {
+        match name {
+            VIDEO_MODE => {
+                if let Some(mode) = video_modes.iter().find(|m| m.name() == value) {
+                    self.mode = *mode;
+                }
+            }
+            VSYNC => self.vsync = value == ON,
+            INTEGER_SCALE => self.integer_scale = value == ON,
+            MUSIC_VOLUME => self.audio.music_volume = slider_value(value),
+            EFFECTS_VOLUME => self.audio.effects_volume = slider_value(value),
+            RANDOM_MODE => {
+                if let Some(mode) = RandomMode::ALL.iter().find(|m| m.name() == value) {
+                    self.game.random_mode = *mode;
+                }
+            }
+            GARBAGE_PER_HOLE => {
+                if let Ok(garbage) = value.parse::<u32>() {
+                    self.game.min_garbage_per_hole = garbage;
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// The current mode followed by common windowed & fullscreen modes at the desktop resolution
+pub fn video_modes(current: VideoMode, desktop: (u32, u32)) -> Vec<VideoMode> {
+    let (width, height) = desktop;
+    let mut result = vec![current];
+    for mode in [
+        VideoMode::Window { width: 1280, height: 720 },
+        VideoMode::Window { width: 1920, height: 1080 },
+        VideoMode::FullScreen { width, height },
+        VideoMode::FullScreenDesktop,
+    ] {
+        if !result.contains(&mode) {
+            result.push(mode);
+        }
+    }
+    result
+}
+
+fn on_off() -> Vec<String> {
+    vec![OFF.to_string(), ON.to_string()]
+}
+
+fn garbage_per_hole_options(current: u32) -> Vec<u32> {
+    let mut result: Vec<u32> = (1..=MAX_GARBAGE_PER_HOLE).collect();
+    if !result.contains(&current) {
+        result.push(current);
+        result.sort();
+    }
+    result
+}
+
+fn slider_labels() -> Vec<String> {
+    (0..=VOLUME_STEPS)
+        .map(|step| format!("{}{}", "=".repeat(step), "-".repeat(VOLUME_STEPS - step)))
+        .collect()
+}
+
+fn slider_step(value: f64) -> usize {
+    ((value * VOLUME_STEPS as f64).round() as usize).min(VOLUME_STEPS)
+}
+
+fn slider_value(label: &str) -> f64 {
+    label.chars().filter(|&c| c == '=').count() as f64 / VOLUME_STEPS as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slider_round_trips_volume() {
+        let labels = slider_labels();
+        assert_eq!(labels.len(), VOLUME_STEPS + 1);
+        assert_eq!(labels[slider_step(0.5)], "=====-----");
+        assert_eq!(slider_value(&labels[slider_step(0.5)]), 0.5);
+        assert_eq!(slider_step(1.5), VOLUME_STEPS);
+    }
+
+    #[test]
+    fn video_modes_include_current_once() {
+        let current = VideoMode::Window { width: 1280, height: 720 };
+        let modes = video_modes(current, (2560, 1440));
+        assert_eq!(modes[0], current);
+        assert_eq!(modes.iter().filter(|&&m| m == current).count(), 1);
+        assert!(modes.contains(&VideoMode::FullScreen { width: 2560, height: 1440 }));
+    }
+
+    #[test]
+    fn reading_settings() {
+        let config = Config::default();
+        let modes = video_modes(config.video.mode, (1920, 1080));
+        let mut settings = Settings::from_config(&config);
+        let original = settings.clone();
+
+        assert!(settings.read(MUSIC_VOLUME, "==--------", &modes));
+        assert_eq!(settings.audio.music_volume, 0.2);
+        assert!(settings.read(RANDOM_MODE, "nes", &modes));
+        assert_eq!(settings.game.random_mode, RandomMode::Nes);
+        assert!(!settings.is_video_changed(&original));
+
+        assert!(settings.read(VIDEO_MODE, "fullscreen desktop", &modes));
+        assert!(settings.is_video_changed(&original));
+        assert!(!settings.read(SAVE_SETTINGS, "", &modes));
+    }
+}
@@ -1,7 +1,9 @@
 use crate::config::Config;
 use crate::theme::gb::game_boy_theme;
+use crate::theme::gbc::game_boy_color_theme;
 use crate::theme::modern::modern_theme;
 use crate::theme::nes::nes_theme;
+use crate::theme::pack::ThemePack;
 use crate::theme::snes::snes_theme;
 use crate::theme::Theme;
 use sdl2::render::{TextureCreator, WindowCanvas};
@@ -10,32 +12,74 @@ use sdl2::video::WindowContext;
 
 pub struct AllThemes<'a> {
     game_boy: Theme<'a>,
+    game_boy_color: Theme<'a>,
     nes: Theme<'a>,
     snes: Theme<'a>,
     modern: Theme<'a>,
+    packs: Vec<Theme<'a>>,
 }
 
 impl<'a> AllThemes<'a> {
+    /// Theme packs that fail to build are left out, returned as their index & the problem
     pub fn new(
         canvas: &mut WindowCanvas,
         texture_creator: &'a TextureCreator<WindowContext>,
         ttf: &Sdl2TtfContext,
-        config: Config,
+        config: &Config,
         window_height: u32,
-    ) -> Result<Self, String> {
+        theme_packs: &[ThemePack],
+    ) -> Result<(Self, Vec<(usize, String)>), String> {
         let game_boy = game_boy_theme(canvas, texture_creator, config)?;
+        let game_boy_color = game_boy_color_theme(canvas, texture_creator, config)?;
         let nes = nes_theme(canvas, texture_creator, config)?;
         let snes = snes_theme(canvas, texture_creator, config)?;
         let modern = modern_theme(canvas, texture_creator, ttf, config, window_height)?;
-        Ok(Self {
+        let mut packs = vec![];
+        let mut failed_packs = vec![];
+        for (index, pack) in theme_packs.iter().enumerate() {
+            match pack.theme(canvas, texture_creator, config) {
+                Ok(theme) => packs.push(theme),
+                Err(error) => failed_packs.push((index, format!("{}, {}", pack.name(), error))),
+            }
+        }
+        let all_themes = Self {
             game_boy,
+            game_boy_color,
             nes,
             snes,
             modern,
-        })
+            packs,
+        };
+        Ok((all_themes, failed_packs))
+    }
+
+    /// Rebuild the modern theme e.g. after changing the block skin
+    pub fn reload_modern(
+        &mut self,
+        canvas: &mut WindowCanvas,
+        texture_creator: &'a TextureCreator<WindowContext>,
+        ttf: &Sdl2TtfContext,
+        config: &Config,
+        window_height: u32,
+    ) -> Result<(), String> {
+        self.modern = modern_theme(canvas, texture_creator, ttf, config, window_height)?;
+        Ok(())
+    }
+
+    pub fn modern(&self) -> &Theme<'a> {
+        &self.modern
     }
 
     pub fn all(&self) -> Vec<&Theme<'a>> {
-        vec![&self.game_boy, &self.nes, &self.snes, &self.modern]
+        vec![
+            &self.game_boy,
+            &self.game_boy_color,
+            &self.nes,
+            &self.snes,
+            &self.modern,
+        ]
+        .into_iter()
+        .chain(self.packs.iter())
+        .collect()
     }
 }
@@ -6,6 +6,7 @@ use sdl2::rect::{Point, Rect};
 use sdl2::render::{BlendMode, Texture, TextureCreator, WindowCanvas};
 use sdl2::ttf::Sdl2TtfContext;
 use sdl2::video::WindowContext;
+use std::borrow::Cow;
 use std::collections::HashMap;
 use crate::theme::helper::{CanvasRenderer, TextureFactory};
 
@@ -106,7 +107,7 @@ impl FontSprite {
 
 pub enum FontRenderOptions {
     Sprites {
-        file_bytes: &'static [u8],
+        file_bytes: Cow<'static, [u8]>,
         sprites: Vec<FontSprite>,
         spacing: u32,
     },
@@ -116,13 +117,14 @@ impl FontRenderOptions {
     pub fn build<'a>(
         &self,
         texture_creator: &'a TextureCreator<WindowContext>,
+        palette: &[(Color, Color)],
     ) -> Result<FontRender<'a>, String> {
         match self {
             FontRenderOptions::Sprites {
                 file_bytes,
                 sprites,
                 spacing,
-            } => FontRender::from_sprites(texture_creator, file_bytes, sprites.clone(), *spacing),
+            } => FontRender::from_sprites(texture_creator, file_bytes, sprites.clone(), *spacing, palette),
         }
     }
 }
@@ -149,11 +151,12 @@ pub struct FontRender<'a> {
 impl<'a> FontRender<'a> {
     pub fn from_sprites(
         texture_creator: &'a TextureCreator<WindowContext>,
-        sprite_file: &'static [u8],
+        sprite_file: &[u8],
         sprites: Vec<FontSprite>,
         spacing: u32,
+        palette: &[(Color, Color)],
     ) -> Result<Self, String> {
-        let texture = texture_creator.load_texture_bytes_blended(sprite_file)?;
+        let texture = texture_creator.load_texture_bytes_remapped(sprite_file, palette)?;
         let sprites = sprites.iter().map(|&x| (x.value, x.snip)).collect();
         Ok(Self {
             texture,
@@ -1,12 +1,13 @@
 use crate::animation::destroy::DestroyAnimationType;
 use crate::animation::game_over::GameOverAnimationType;
-use crate::config::Config;
+use crate::config::{Config, GameBoyPalette, ThemeTransition};
 use crate::theme::retro::{retro_theme, RetroThemeOptions};
 use std::convert::TryInto;
 use std::iter::Iterator;
 
 use crate::theme::font::{alpha_sprites, FontRenderOptions, MetricSnips};
 use crate::theme::geometry::VISIBLE_BUFFER;
+use crate::theme::helper::Palette;
 use crate::theme::sound::SoundThemeOptions;
 use crate::theme::sprite_sheet::TetrominoSpriteSheetMeta;
 use crate::theme::{Theme, ThemeName};
@@ -37,20 +38,79 @@ const ALPHA_PIXELS: u32 = 6;
 const BLOCK_PIXELS: u32 = 8;
 const BUFFER_PIXELS: u32 = VISIBLE_BUFFER * BLOCK_PIXELS;
 
+// the four shades used by all game boy assets, lightest first
+const SHADES: [Color; 4] = [
+    Color::RGB(255, 255, 255),
+    Color::RGB(170, 170, 170),
+    Color::RGB(85, 85, 85),
+    Color::RGB(0, 0, 0),
+];
+
+fn palette(game_boy_palette: GameBoyPalette) -> Palette {
+    let colors = match game_boy_palette {
+        GameBoyPalette::Default => return vec![],
+        GameBoyPalette::DmgGreen => [0x9bbc0f, 0x8bac0f, 0x306230, 0x0f380f],
+        GameBoyPalette::PocketGrey => [0xc4cfa1, 0x8b956d, 0x4d533c, 0x1f1f1f],
+        GameBoyPalette::Light => [0x7ff5d0, 0x3ec09c, 0x1a7a60, 0x043c2e],
+    };
+    SHADES
+        .into_iter()
+        .zip(colors)
+        .map(|(from, to)| (from, Color::RGB((to >> 16) as u8, (to >> 8) as u8, to as u8)))
+        .collect()
+}
+
 fn char_snip(row: i32, col: i32) -> Point {
     // characters are in row x col with 8 pixels between columns and 7 pixels between rows
     Point::new(1 + col * 8, 45 + row * 7)
 }
 
+/// The art & sound of a game boy theme, every sheet shares the layout of the game boy sheets
+pub struct GameBoyAssets {
+    pub sprites: &'static [u8],
+    pub background: &'static [u8],
+    pub board: &'static [u8],
+    pub game_over: &'static [u8],
+    pub background_color: Color,
+    pub sound: SoundThemeOptions,
+}
+
 pub fn game_boy_theme<'a>(
     canvas: &mut WindowCanvas,
     texture_creator: &'a TextureCreator<WindowContext>,
-    config: Config,
+    config: &Config,
 ) -> Result<Theme<'a>, String> {
-    let options = RetroThemeOptions::new(
-        ThemeName::GameBoy,
+    let assets = GameBoyAssets {
+        sprites: SPRITES,
+        background: BACKGROUND_FILE,
+        board: BOARD_FILE,
+        game_over: GAME_OVER_FILE,
+        background_color: Color::WHITE,
+        sound: SoundThemeOptions::default(
+            MUSIC,
+            MOVE_SOUND,
+            ROTATE_SOUND,
+            LOCK_SOUND,
+            SEND_GARBAGE_SOUND,
+            [CLEAR_SOUND, CLEAR_SOUND, CLEAR_SOUND, TETRIS_SOUND],
+            LEVEL_UP_SOUND,
+            GAME_OVER_SOUND,
+            PAUSE_SOUND,
+            VICTORY_SOUND,
+        )
+        .with_stack_drop(STACK_DROP_SOUND),
+    };
+    let options = game_boy_options(ThemeName::GameBoy, assets)
+        .with_palette(palette(config.video.game_boy_palette));
+    retro_theme(canvas, texture_creator, options, config)
+}
+
+/// Retro theme options of the game boy layout
+pub fn game_boy_options(name: ThemeName, assets: GameBoyAssets) -> RetroThemeOptions {
+    RetroThemeOptions::new(
+        name,
         TetrominoSpriteSheetMeta::new(
-            SPRITES,
+            assets.sprites,
             BLOCK_PIXELS,
             [
                 Point::new(1, 35),
@@ -67,9 +127,9 @@ pub fn game_boy_theme<'a>(
             (34, 35),
             0x30,
         ),
-        BACKGROUND_FILE,
-        BOARD_FILE,
-        GAME_OVER_FILE,
+        assets.background,
+        assets.board,
+        assets.game_over,
         [
             Rect::new(162, 11 + BUFFER_PIXELS as i32, 32, 32),
             Rect::new(162, 48 + BUFFER_PIXELS as i32, 32, 32),
@@ -79,7 +139,7 @@ pub fn game_boy_theme<'a>(
         ],
         Rect::new(12, 101 + BUFFER_PIXELS as i32, 32, 32),
         FontRenderOptions::Sprites {
-            file_bytes: SPRITES,
+            file_bytes: assets.sprites.into(),
             sprites: alpha_sprites(
                 (0..10)
                     .map(|i| char_snip(3, i))
@@ -96,24 +156,10 @@ pub fn game_boy_theme<'a>(
         MetricSnips::right((39, 78), 999),
         Point::new(55, 0),
         Point::new(8, 0),
-        Color::WHITE,
+        assets.background_color,
         DestroyAnimationType::Flash,
         GameOverAnimationType::CurtainUp,
-        SoundThemeOptions::default(
-            config.audio,
-            MUSIC,
-            MOVE_SOUND,
-            ROTATE_SOUND,
-            LOCK_SOUND,
-            SEND_GARBAGE_SOUND,
-            [CLEAR_SOUND, CLEAR_SOUND, CLEAR_SOUND, TETRIS_SOUND],
-            LEVEL_UP_SOUND,
-            GAME_OVER_SOUND,
-            PAUSE_SOUND,
-            VICTORY_SOUND,
-        )
-        .with_stack_drop(STACK_DROP_SOUND),
-    );
-
-    retro_theme(canvas, texture_creator, options)
+        assets.sound,
+    )
+    .with_transition(ThemeTransition::PaletteFade)
 }
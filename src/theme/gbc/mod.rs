@@ -0,0 +1,56 @@
+use crate::config::Config;
+use crate::theme::gb::{game_boy_options, GameBoyAssets};
+use crate::theme::retro::retro_theme;
+use crate::theme::sound::SoundThemeOptions;
+use crate::theme::{Theme, ThemeName};
+use sdl2::pixels::Color;
+use sdl2::render::{TextureCreator, WindowCanvas};
+use sdl2::video::WindowContext;
+
+const SPRITES: &[u8] = include_bytes!("sprites.png");
+const BACKGROUND_FILE: &[u8] = include_bytes!("background.png");
+const BOARD_FILE: &[u8] = include_bytes!("board.png");
+const GAME_OVER_FILE: &[u8] = include_bytes!("game-over.png");
+
+const GAME_OVER_SOUND: &[u8] = include_bytes!("game-over.ogg");
+const LEVEL_UP_SOUND: &[u8] = include_bytes!("level-up.ogg");
+const CLEAR_SOUND: &[u8] = include_bytes!("line-clear.ogg");
+const LOCK_SOUND: &[u8] = include_bytes!("lock.ogg");
+const MOVE_SOUND: &[u8] = include_bytes!("move.ogg");
+const MUSIC: &[u8] = include_bytes!("music.ogg");
+const PAUSE_SOUND: &[u8] = include_bytes!("pause.ogg");
+const ROTATE_SOUND: &[u8] = include_bytes!("rotate.ogg");
+const SEND_GARBAGE_SOUND: &[u8] = include_bytes!("send-garbage.ogg");
+const STACK_DROP_SOUND: &[u8] = include_bytes!("stack-drop.ogg");
+const TETRIS_SOUND: &[u8] = include_bytes!("tetris.ogg");
+const VICTORY_SOUND: &[u8] = include_bytes!("victory.ogg");
+
+pub fn game_boy_color_theme<'a>(
+    canvas: &mut WindowCanvas,
+    texture_creator: &'a TextureCreator<WindowContext>,
+    config: &Config,
+) -> Result<Theme<'a>, String> {
+    // the color sheets are recolors of the game boy sheets, so they share its layout
+    let assets = GameBoyAssets {
+        sprites: SPRITES,
+        background: BACKGROUND_FILE,
+        board: BOARD_FILE,
+        game_over: GAME_OVER_FILE,
+        background_color: Color::RGB(0xf8, 0xf8, 0xe8),
+        sound: SoundThemeOptions::default(
+            MUSIC,
+            MOVE_SOUND,
+            ROTATE_SOUND,
+            LOCK_SOUND,
+            SEND_GARBAGE_SOUND,
+            [CLEAR_SOUND, CLEAR_SOUND, CLEAR_SOUND, TETRIS_SOUND],
+            LEVEL_UP_SOUND,
+            GAME_OVER_SOUND,
+            PAUSE_SOUND,
+            VICTORY_SOUND,
+        )
+        .with_stack_drop(STACK_DROP_SOUND),
+    };
+    let options = game_boy_options(ThemeName::GameBoyColor, assets);
+    retro_theme(canvas, texture_creator, options, config)
+}
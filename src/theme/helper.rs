@@ -1,9 +1,52 @@
-use sdl2::image::LoadTexture;
+use sdl2::image::{ImageRWops, LoadTexture};
 use sdl2::pixels::Color;
+use sdl2::pixels::PixelFormatEnum;
 use sdl2::pixels::PixelFormatEnum::RGBA8888;
+use sdl2::rect::Rect;
 use sdl2::render::{BlendMode, Canvas, Texture, TextureCreator};
+use sdl2::rwops::RWops;
 use sdl2::video::{Window, WindowContext};
 
+/// Colour remapping as (from, to) pairs, alpha is ignored when matching & preserved when remapping
+pub type Palette = Vec<(Color, Color)>;
+
+pub fn remap_color(palette: &[(Color, Color)], color: Color) -> Color {
+    palette
+        .iter()
+        .find(|(from, _)| from.rgb() == color.rgb())
+        .map(|(_, to)| Color::RGBA(to.r, to.g, to.b, color.a))
+        .unwrap_or(color)
+}
+
+/// Apply the first palette and then the second
+pub fn compose_palettes(first: &[(Color, Color)], second: &[(Color, Color)]) -> Palette {
+    let mut result: Palette = first
+        .iter()
+        .map(|(from, to)| (*from, remap_color(second, *to)))
+        .collect();
+    for (from, to) in second {
+        if !first.iter().any(|(f, _)| f.rgb() == from.rgb()) {
+            result.push((*from, *to));
+        }
+    }
+    result
+}
+
+pub fn luminance(color: Color) -> f64 {
+    0.299 * color.r as f64 + 0.587 * color.g as f64 + 0.114 * color.b as f64
+}
+
+/// Replace the colour with a tint, shaded by the luminance of the colour relative to the average of the sprite
+pub fn tint_color(tint: Color, color: Color, average_luminance: f64) -> Color {
+    let shade = if average_luminance > 0.0 {
+        luminance(color) / average_luminance
+    } else {
+        1.0
+    };
+    let channel = |value: u8| (value as f64 * shade).round().clamp(0.0, 255.0) as u8;
+    Color::RGBA(channel(tint.r), channel(tint.g), channel(tint.b), color.a)
+}
+
 pub trait TextureQuery {
     fn size(&self) -> (u32, u32);
 }
@@ -18,6 +61,13 @@ impl TextureQuery for Texture<'_> {
 pub trait TextureFactory {
     fn create_texture_target_blended(&self, width: u32, height: u32) -> Result<Texture, String>;
     fn load_texture_bytes_blended(&self, buf: &[u8]) -> Result<Texture, String>;
+    fn load_texture_bytes_remapped(&self, buf: &[u8], palette: &[(Color, Color)]) -> Result<Texture<'_>, String>;
+    fn load_texture_bytes_tinted(
+        &self,
+        buf: &[u8],
+        palette: &[(Color, Color)],
+        tints: &[(Rect, Color)],
+    ) -> Result<Texture<'_>, String>;
 }
 
 impl TextureFactory for TextureCreator<WindowContext> {
@@ -34,6 +84,66 @@ impl TextureFactory for TextureCreator<WindowContext> {
         texture.set_blend_mode(BlendMode::Blend);
         Ok(texture)
     }
+
+    fn load_texture_bytes_remapped(&self, buf: &[u8], palette: &[(Color, Color)]) -> Result<Texture<'_>, String> {
+        self.load_texture_bytes_tinted(buf, palette, &[])
+    }
+
+    /// Remap the palette and then tint each rect of the texture
+    fn load_texture_bytes_tinted(
+        &self,
+        buf: &[u8],
+        palette: &[(Color, Color)],
+        tints: &[(Rect, Color)],
+    ) -> Result<Texture<'_>, String> {
+        if palette.is_empty() && tints.is_empty() {
+            return self.load_texture_bytes_blended(buf);
+        }
+
+        let mut surface = RWops::from_bytes(buf)?
+            .load()?
+            .convert_format(PixelFormatEnum::RGBA32)?;
+        let pitch = surface.pitch() as usize;
+        let bounds = surface.rect();
+        surface.with_lock_mut(|pixels| {
+            for pixel in pixels.chunks_exact_mut(4) {
+                let color = remap_color(palette, Color::RGBA(pixel[0], pixel[1], pixel[2], pixel[3]));
+                pixel[0] = color.r;
+                pixel[1] = color.g;
+                pixel[2] = color.b;
+            }
+
+            for (rect, tint) in tints {
+                let Some(rect) = rect.intersection(bounds) else {
+                    continue;
+                };
+                let offsets = (rect.top()..rect.bottom())
+                    .flat_map(|y| (rect.left()..rect.right()).map(move |x| y as usize * pitch + x as usize * 4))
+                    .collect::<Vec<usize>>();
+                let color_at = |pixels: &[u8], i: usize| Color::RGBA(pixels[i], pixels[i + 1], pixels[i + 2], pixels[i + 3]);
+                let opaque = offsets
+                    .iter()
+                    .map(|i| color_at(pixels, *i))
+                    .filter(|c| c.a > 0)
+                    .collect::<Vec<Color>>();
+                if opaque.is_empty() {
+                    continue;
+                }
+                let average_luminance = opaque.iter().map(|c| luminance(*c)).sum::<f64>() / opaque.len() as f64;
+                for i in offsets {
+                    let color = tint_color(*tint, color_at(pixels, i), average_luminance);
+                    pixels[i] = color.r;
+                    pixels[i + 1] = color.g;
+                    pixels[i + 2] = color.b;
+                }
+            }
+        });
+        let mut texture = self
+            .create_texture_from_surface(&surface)
+            .map_err(|e| e.to_string())?;
+        texture.set_blend_mode(BlendMode::Blend);
+        Ok(texture)
+    }
 }
 
 pub trait CanvasRenderer {
@@ -47,4 +157,35 @@ impl CanvasRenderer for Canvas<Window> {
         self.clear();
         self.set_draw_color(draw_color);
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: Color = Color::RGB(255, 0, 0);
+    const GREEN: Color = Color::RGB(0, 255, 0);
+    const BLUE: Color = Color::RGB(0, 0, 255);
+
+    #[test]
+    fn remaps_color_preserving_alpha() {
+        let palette = vec![(RED, GREEN)];
+        assert_eq!(remap_color(&palette, Color::RGBA(255, 0, 0, 0x50)), Color::RGBA(0, 255, 0, 0x50));
+        assert_eq!(remap_color(&palette, BLUE), BLUE);
+    }
+
+    #[test]
+    fn tints_keep_relative_shading() {
+        let grey = Color::RGBA(100, 100, 100, 0x80);
+        assert_eq!(tint_color(BLUE, grey, 100.0), Color::RGBA(0, 0, 255, 0x80));
+        assert_eq!(tint_color(Color::RGB(100, 50, 0), grey, 200.0), Color::RGBA(50, 25, 0, 0x80));
+        assert_eq!(tint_color(Color::RGB(200, 100, 0), grey, 50.0), Color::RGBA(255, 200, 0, 0x80));
+    }
+
+    #[test]
+    fn composes_palettes() {
+        let palette = compose_palettes(&[(RED, GREEN)], &[(GREEN, BLUE), (BLUE, RED)]);
+        assert_eq!(remap_color(&palette, RED), BLUE);
+        assert_eq!(remap_color(&palette, GREEN), BLUE);
+        assert_eq!(remap_color(&palette, BLUE), RED);
+    }
+}
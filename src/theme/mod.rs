@@ -1,6 +1,7 @@
 use crate::animation::destroy::DestroyAnimationType;
 use crate::animation::game_over::{GameOverAnimate, GameOverAnimationType};
 use crate::animation::TextureAnimate;
+use crate::config::ThemeTransition;
 use crate::event::GameEvent;
 
 use crate::game::board::BOARD_WIDTH;
@@ -23,9 +24,11 @@ use crate::theme::helper::{CanvasRenderer, TextureFactory};
 pub mod all;
 pub mod font;
 pub mod gb;
+pub mod gbc;
 pub mod geometry;
 pub mod modern;
 pub mod nes;
+pub mod pack;
 mod retro;
 pub mod snes;
 pub mod sound;
@@ -33,14 +36,18 @@ pub mod sprite_sheet;
 pub mod helper;
 
 const VISIBLE_PEEK: usize = 5;
+const FADE_STEPS: u32 = 8;
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
 pub enum ThemeName {
     GameBoy,
+    GameBoyColor,
     Nes,
     Snes,
     #[default]
     Modern,
+    /// Theme pack loaded from disk
+    External(usize),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -72,12 +79,28 @@ pub fn create_mask_texture<'a>(
     Ok(mask_texture)
 }
 
+/// Copies of a mask texture at increasing alpha, to fade lines without mutating the mask
+pub fn create_fade_textures<'a>(
+    canvas: &mut WindowCanvas,
+    texture_creator: &'a TextureCreator<WindowContext>,
+    mask_texture: &Texture,
+) -> Result<Vec<Texture<'a>>, String> {
+    (1..=FADE_STEPS)
+        .map(|step| {
+            let mut texture = create_mask_texture(canvas, texture_creator, mask_texture)?;
+            texture.set_alpha_mod((255 * step / FADE_STEPS) as u8);
+            Ok(texture)
+        })
+        .collect()
+}
+
 pub struct Theme<'a> {
     name: ThemeName,
     sprite_sheet: TetrominoSpriteSheet<'a>,
     geometry: BoardGeometry,
     board_texture: Texture<'a>,
     board_mask_texture: Texture<'a>, // same as board texture but with blend mode set to none
+    board_fade_textures: Vec<Texture<'a>>,
     board_snip: Rect,
     background_texture: Texture<'a>,
     background_size: (u32, u32),
@@ -95,6 +118,7 @@ pub struct Theme<'a> {
     ghost_mino_type: MinoType,
     tetromino_scale_type: TetrominoScaleType,
     particle_color: Option<Color>,
+    transition: ThemeTransition,
 }
 
 impl<'a> Theme<'a> {
@@ -102,6 +126,10 @@ impl<'a> Theme<'a> {
         self.name
     }
 
+    pub fn transition(&self) -> ThemeTransition {
+        self.transition
+    }
+
     pub fn geometry(&self) -> &BoardGeometry {
         &self.geometry
     }
@@ -132,7 +160,7 @@ impl<'a> Theme<'a> {
         )?;
 
         if let Some(hold_shape) = metrics.hold {
-            self.draw_tetromino(canvas, hold_shape, self.hold_snip, false)?;
+            self.draw_tetromino(canvas, hold_shape, self.hold_snip, false, metrics.level)?;
         }
 
         for (index, (peek_shape, peek_rect)) in metrics
@@ -142,7 +170,7 @@ impl<'a> Theme<'a> {
             .zip(self.peek_snips)
             .enumerate()
         {
-            self.draw_tetromino(canvas, peek_shape, peek_rect, index == 0)?;
+            self.draw_tetromino(canvas, peek_shape, peek_rect, index == 0, metrics.level)?;
         }
 
         self.font
@@ -191,6 +219,11 @@ impl<'a> Theme<'a> {
                         // simulate alpha by copying over the board background
                         canvas.copy(&self.board_mask_texture, line_snip, line_snip)?;
                     }
+                    TextureAnimate::FadeAlpha { alpha } => {
+                        let steps = self.board_fade_textures.len();
+                        let step = ((alpha * steps as f64).ceil() as usize).clamp(1, steps);
+                        canvas.copy(&self.board_fade_textures[step - 1], line_snip, line_snip)?;
+                    }
                     TextureAnimate::FillAlphaRectangle { width } => {
                         // simulate alpha by copying over the board background
                         let row_rect = self.geometry.line_snip(j);
@@ -218,7 +251,7 @@ impl<'a> Theme<'a> {
         for j in curtain_range {
             for i in 0..BOARD_WIDTH {
                 let point = self.geometry.mino_point(i, j);
-                self.sprite_sheet.draw_garbage(canvas, point)?;
+                self.sprite_sheet.draw_garbage(canvas, point, game.level())?;
             }
         }
 
@@ -233,8 +266,13 @@ impl<'a> Theme<'a> {
         self.game_over_animation
     }
 
-    pub fn music(&self) -> &Music {
-        self.sound.music()
+    /// The main music track or the critical track if the stack is high and this theme has one
+    pub fn music(&self, critical: bool) -> &Music {
+        self.sound.music(critical)
+    }
+
+    pub fn has_critical_music(&self) -> bool {
+        self.sound.has_critical_music()
     }
 
     pub fn play_sound_effects(&self, event: GameEvent) -> Result<(), String> {
@@ -291,19 +329,21 @@ impl<'a> Theme<'a> {
         shape: TetrominoShape,
         snip: Rect,
         is_peek0: bool,
+        level: u32,
     ) -> Result<(), String> {
         match self.tetromino_scale_type {
             TetrominoScaleType::Fill { peek0_scale, .. } if is_peek0 => self
                 .sprite_sheet
-                .draw_tetromino_fill(canvas, shape, MinoType::Normal, snip, peek0_scale),
+                .draw_tetromino_fill(canvas, shape, MinoType::Normal, snip, peek0_scale, level),
             TetrominoScaleType::Fill { default_scale, .. } => self
                 .sprite_sheet
-                .draw_tetromino_fill(canvas, shape, MinoType::Normal, snip, default_scale),
+                .draw_tetromino_fill(canvas, shape, MinoType::Normal, snip, default_scale, level),
             TetrominoScaleType::Center => self.sprite_sheet.draw_tetromino_in_center(
                 canvas,
                 shape,
                 MinoType::Normal,
                 snip.center(),
+                level,
             ),
         }
     }
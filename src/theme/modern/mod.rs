@@ -1,21 +1,33 @@
 use crate::animation::destroy::DestroyAnimationType;
 use crate::animation::game_over::GameOverAnimationType;
-use crate::config::Config;
+use crate::config::{config_sibling_path, ColorBlindPalette, Config, ThemeTransition};
 use crate::font::FontType;
+use crate::game::tetromino::TetrominoShape;
 
 use crate::theme::font::{FontRender, MetricSnips};
 use crate::theme::geometry::{BoardGeometry, VISIBLE_BOARD_HEIGHT};
 use crate::theme::sound::SoundThemeOptions;
 use crate::theme::sprite_sheet::{MinoType, TetrominoSpriteSheet, TetrominoSpriteSheetMeta};
-use crate::theme::{create_mask_texture, TetrominoScaleType, Theme, ThemeName, VISIBLE_PEEK};
+use crate::theme::{create_fade_textures, create_mask_texture, TetrominoScaleType, Theme, ThemeName, VISIBLE_PEEK};
+use sdl2::image::LoadTexture;
 use sdl2::pixels::Color;
 use sdl2::rect::{Point, Rect};
 use sdl2::render::{BlendMode, TextureCreator, WindowCanvas};
 use sdl2::ttf::Sdl2TtfContext;
 use sdl2::video::WindowContext;
 use crate::theme::helper::{CanvasRenderer, TextureFactory};
+use std::borrow::Cow;
+use std::fs;
+use std::path::PathBuf;
 
 const SPRITES: &[u8] = include_bytes!("sprites.png");
+const SPRITE_BLOCK_PIXELS: u32 = 48;
+const SPRITE_GHOST_ALPHA: u8 = 0x50;
+
+// skins must cover the 8 x 2 grid of blocks: garbage then each shape, normal above stack
+const SKINS_DIR: &str = "skins";
+const SKIN_MIN_WIDTH: u32 = 4 + 56 * 7 + SPRITE_BLOCK_PIXELS;
+const SKIN_MIN_HEIGHT: u32 = 4 + 56 + SPRITE_BLOCK_PIXELS;
 
 const GAME_OVER_SOUND: &[u8] = include_bytes!("game-over.ogg");
 const LEVEL_UP_SOUND: &[u8] = include_bytes!("level-up.ogg");
@@ -58,6 +70,113 @@ fn mino(col: i32) -> (Point, Point) {
     (block(0, col), block(1, col))
 }
 
+fn sprite_sheet_meta<F: Into<Cow<'static, [u8]>>>(sprite_file: F) -> TetrominoSpriteSheetMeta {
+    TetrominoSpriteSheetMeta::new(
+        sprite_file,
+        SPRITE_BLOCK_PIXELS,
+        mino(6),
+        mino(1),
+        mino(3),
+        mino(7),
+        mino(2),
+        mino(4),
+        mino(5),
+        block(0, 0),
+        SPRITE_GHOST_ALPHA,
+    )
+}
+
+/// Piece colours for each type of colour blindness, none keeps the colours of the sprites
+fn shape_tints(palette: ColorBlindPalette) -> Vec<(TetrominoShape, Color)> {
+    let colors = match palette {
+        ColorBlindPalette::Default => return vec![],
+        // Okabe-Ito
+        ColorBlindPalette::Deuteranopia => [0x56b4e9, 0x0072b2, 0xe69f00, 0xf0e442, 0x009e73, 0xcc79a7, 0xd55e00],
+        // Tol muted, avoiding dark reds
+        ColorBlindPalette::Protanopia => [0x88ccee, 0x332288, 0xddcc77, 0xeeeeee, 0x117733, 0xaa4499, 0x999933],
+        // reds & teals that stay apart without blue cones
+        ColorBlindPalette::Tritanopia => [0x5ec8d8, 0x20306b, 0xf07f5e, 0xededed, 0x3b7a57, 0xd81b60, 0x8c1c13],
+    };
+    [
+        TetrominoShape::I,
+        TetrominoShape::J,
+        TetrominoShape::L,
+        TetrominoShape::O,
+        TetrominoShape::S,
+        TetrominoShape::T,
+        TetrominoShape::Z,
+    ]
+    .into_iter()
+    .zip(colors)
+    .map(|(shape, color)| (shape, Color::RGB((color >> 16) as u8, (color >> 8) as u8, color as u8)))
+    .collect()
+}
+
+fn accessible_sprite_sheet_meta(meta: TetrominoSpriteSheetMeta, config: &Config) -> TetrominoSpriteSheetMeta {
+    meta.with_glyphs(config.video.accessibility.mino_glyphs)
+        .with_shape_tints(shape_tints(config.video.accessibility.modern_palette))
+}
+
+pub fn skins_path() -> Result<PathBuf, String> {
+    config_sibling_path(SKINS_DIR)
+}
+
+/// Names of all block skin pngs in the skins directory
+pub fn skin_names() -> Vec<String> {
+    let entries = match skins_path().and_then(|dir| fs::read_dir(dir).map_err(|e| e.to_string())) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+    let mut names = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("png")))
+        .filter_map(|path| path.file_stem().map(|stem| stem.to_string_lossy().to_string()))
+        .collect::<Vec<String>>();
+    names.sort();
+    names
+}
+
+fn skin_sprite_sheet<'a>(
+    canvas: &mut WindowCanvas,
+    texture_creator: &'a TextureCreator<WindowContext>,
+    name: &str,
+    config: &Config,
+    block_size: u32,
+) -> Result<TetrominoSpriteSheet<'a>, String> {
+    let path = skins_path()?.join(format!("{}.png", name));
+    let bytes = fs::read(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let query = texture_creator.load_texture_bytes(&bytes)?.query();
+    if query.width < SKIN_MIN_WIDTH || query.height < SKIN_MIN_HEIGHT {
+        return Err(format!(
+            "{}: expected at least {}x{} pixels, got {}x{}",
+            path.display(),
+            SKIN_MIN_WIDTH,
+            SKIN_MIN_HEIGHT,
+            query.width,
+            query.height
+        ));
+    }
+    let meta = accessible_sprite_sheet_meta(sprite_sheet_meta(bytes), config);
+    TetrominoSpriteSheet::new(canvas, texture_creator, meta, block_size)
+}
+
+fn modern_sprite_sheet<'a>(
+    canvas: &mut WindowCanvas,
+    texture_creator: &'a TextureCreator<WindowContext>,
+    config: &Config,
+    block_size: u32,
+) -> Result<TetrominoSpriteSheet<'a>, String> {
+    if let Some(name) = &config.video.modern_skin {
+        match skin_sprite_sheet(canvas, texture_creator, name, config, block_size) {
+            Ok(sprite_sheet) => return Ok(sprite_sheet),
+            Err(error) => println!("Cannot load skin {}, {}, using the default", name, error),
+        }
+    }
+    let meta = accessible_sprite_sheet_meta(sprite_sheet_meta(SPRITES), config);
+    TetrominoSpriteSheet::new(canvas, texture_creator, meta, block_size)
+}
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 enum GameMetricType {
     Score,
@@ -160,7 +279,7 @@ pub fn modern_theme<'a>(
     canvas: &mut WindowCanvas,
     texture_creator: &'a TextureCreator<WindowContext>,
     ttf: &Sdl2TtfContext,
-    config: Config,
+    config: &Config,
     window_height: u32
 ) -> Result<Theme<'a>, String> {
     let block_size = (window_height as f64
@@ -255,19 +374,6 @@ pub fn modern_theme<'a>(
         .try_into()
         .unwrap();
 
-    let sprite_sheet_meta = TetrominoSpriteSheetMeta::new(
-        SPRITES,
-        48,
-        mino(6),
-        mino(1),
-        mino(3),
-        mino(7),
-        mino(2),
-        mino(4),
-        mino(5),
-        block(0, 0),
-        0x50,
-    );
     let mut borders = vec![];
 
     let step = BOARD_BOARDER_SHADOW / border_weight as u8;
@@ -313,6 +419,7 @@ pub fn modern_theme<'a>(
         })
         .map_err(|e| e.to_string())?;
     let board_mask_texture = create_mask_texture(canvas, texture_creator, &board_texture)?;
+    let board_fade_textures = create_fade_textures(canvas, texture_creator, &board_texture)?;
 
     let mut bg_texture = texture_creator.create_texture_target_blended(background_width, background_height)?;
     canvas
@@ -365,14 +472,10 @@ pub fn modern_theme<'a>(
 
     Ok(Theme {
         name: ThemeName::Modern,
-        sprite_sheet: TetrominoSpriteSheet::new(
-            canvas,
-            texture_creator,
-            sprite_sheet_meta,
-            block_size,
-        )?,
+        sprite_sheet: modern_sprite_sheet(canvas, texture_creator, config, block_size)?,
         board_texture,
         board_mask_texture,
+        board_fade_textures,
         background_texture: bg_texture,
         geometry,
         background_size: (background_width, background_height),
@@ -397,7 +500,6 @@ pub fn modern_theme<'a>(
             .value,
         game_over,
         sound: SoundThemeOptions::default(
-            config.audio,
             MUSIC,
             MOVE_SOUND,
             ROTATE_SOUND,
@@ -430,5 +532,6 @@ pub fn modern_theme<'a>(
             peek0_scale: BIG_TETROMINO_PREFERRED_BLOCK_SCALE,
         },
         particle_color: Some(Color::WHITE),
+        transition: ThemeTransition::Fade,
     })
 }
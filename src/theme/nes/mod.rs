@@ -1,8 +1,9 @@
 use crate::animation::destroy::DestroyAnimationType;
 use crate::animation::game_over::GameOverAnimationType;
-use crate::config::Config;
+use crate::config::{Config, ThemeTransition};
 use crate::theme::font::{alpha_sprites, FontRenderOptions, MetricSnips};
 use crate::theme::geometry::VISIBLE_BUFFER;
+use crate::theme::helper::Palette;
 use crate::theme::retro::{retro_theme, RetroThemeOptions};
 use crate::theme::sound::SoundThemeOptions;
 use crate::theme::sprite_sheet::TetrominoSpriteSheetMeta;
@@ -23,6 +24,9 @@ const CLEAR_SOUND: &[u8] = include_bytes!("line-clear.ogg");
 const LOCK_SOUND: &[u8] = include_bytes!("lock.ogg");
 const MOVE_SOUND: &[u8] = include_bytes!("move.ogg");
 const MUSIC: &[u8] = include_bytes!("music.ogg");
+const CRITICAL_MUSIC: &[u8] = include_bytes!("music_crit.ogg");
+const MENU_MUSIC: &[u8] = include_bytes!("menu.ogg");
+const MENU_START_SOUND: &[u8] = include_bytes!("menu-start.ogg");
 const PAUSE_SOUND: &[u8] = include_bytes!("pause.ogg");
 const ROTATE_SOUND: &[u8] = include_bytes!("rotate.ogg");
 const SEND_GARBAGE_SOUND: &[u8] = include_bytes!("send-garbage.ogg");
@@ -37,6 +41,28 @@ fn mino(i: i32, j: i32) -> Point {
     Point::new(i * BLOCK_PIXELS as i32, j * BLOCK_PIXELS as i32)
 }
 
+// the two block colours for each level, the palette repeats every 10 levels
+const LEVEL_COLORS: [(Color, Color); 10] = [
+    (Color::RGB(0x42, 0x42, 0xff), Color::RGB(0x63, 0xad, 0xff)),
+    (Color::RGB(0x10, 0x94, 0x00), Color::RGB(0x8c, 0xd6, 0x00)),
+    (Color::RGB(0x9c, 0x18, 0xce), Color::RGB(0xef, 0x6b, 0xff)),
+    (Color::RGB(0x42, 0x42, 0xff), Color::RGB(0x5a, 0xe7, 0x31)),
+    (Color::RGB(0xb5, 0x21, 0x7b), Color::RGB(0x42, 0xde, 0x84)),
+    (Color::RGB(0x42, 0xde, 0x84), Color::RGB(0x94, 0x94, 0xff)),
+    (Color::RGB(0xa0, 0x23, 0x1e), Color::RGB(0x50, 0x50, 0x50)),
+    (Color::RGB(0x73, 0x29, 0xff), Color::RGB(0x6b, 0x00, 0x42)),
+    (Color::RGB(0x42, 0x42, 0xff), Color::RGB(0xb5, 0x31, 0x21)),
+    (Color::RGB(0xb5, 0x31, 0x21), Color::RGB(0xe7, 0x9c, 0x21)),
+];
+
+fn level_palettes() -> Vec<Palette> {
+    let (base_primary, base_secondary) = LEVEL_COLORS[0];
+    LEVEL_COLORS
+        .iter()
+        .map(|(primary, secondary)| vec![(base_primary, *primary), (base_secondary, *secondary)])
+        .collect()
+}
+
 fn char_snip(row: i32, col: i32) -> Point {
     Point::new(col * 8, 111 + row * 8)
 }
@@ -44,7 +70,7 @@ fn char_snip(row: i32, col: i32) -> Point {
 pub fn nes_theme<'a>(
     canvas: &mut WindowCanvas,
     texture_creator: &'a TextureCreator<WindowContext>,
-    config: Config,
+    config: &Config,
 ) -> Result<Theme<'a>, String> {
     let options = RetroThemeOptions::new(
         ThemeName::Nes,
@@ -60,7 +86,8 @@ pub fn nes_theme<'a>(
             mino(1, 0),
             mino(0, 0),
             0x50,
-        ),
+        )
+        .with_level_palettes(level_palettes()),
         BACKGROUND_FILE,
         BOARD_FILE,
         GAME_OVER_FILE,
@@ -73,7 +100,7 @@ pub fn nes_theme<'a>(
         ],
         Rect::new(16, 127 + BUFFER_PIXELS as i32, 32, 32),
         FontRenderOptions::Sprites {
-            file_bytes: SPRITES,
+            file_bytes: SPRITES.into(),
             sprites: alpha_sprites(
                 (0..10)
                     .map(|i| char_snip(0, i))
@@ -93,19 +120,26 @@ pub fn nes_theme<'a>(
         Color::RGB(0x74, 0x74, 0x74),
         DestroyAnimationType::Sweep,
         GameOverAnimationType::CurtainDown,
-        SoundThemeOptions::default(
-            config.audio,
-            MUSIC,
-            MOVE_SOUND,
-            ROTATE_SOUND,
-            LOCK_SOUND,
-            SEND_GARBAGE_SOUND,
-            [CLEAR_SOUND, CLEAR_SOUND, CLEAR_SOUND, TETRIS_SOUND],
-            LEVEL_UP_SOUND,
-            GAME_OVER_SOUND,
-            PAUSE_SOUND,
-            VICTORY_SOUND,
-        ),
-    );
-    retro_theme(canvas, texture_creator, options)
+        nes_sound(),
+    )
+    .with_transition(ThemeTransition::Blinds);
+    retro_theme(canvas, texture_creator, options, config)
+}
+
+pub fn nes_sound() -> SoundThemeOptions {
+    SoundThemeOptions::default(
+        MUSIC,
+        MOVE_SOUND,
+        ROTATE_SOUND,
+        LOCK_SOUND,
+        SEND_GARBAGE_SOUND,
+        [CLEAR_SOUND, CLEAR_SOUND, CLEAR_SOUND, TETRIS_SOUND],
+        LEVEL_UP_SOUND,
+        GAME_OVER_SOUND,
+        PAUSE_SOUND,
+        VICTORY_SOUND,
+    )
+    .with_critical_music(CRITICAL_MUSIC)
+    .with_menu_music(MENU_MUSIC)
+    .with_menu_start(MENU_START_SOUND)
 }
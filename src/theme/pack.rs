@@ -0,0 +1,534 @@
+use crate::animation::destroy::DestroyAnimationType;
+use crate::animation::game_over::GameOverAnimationType;
+use crate::config::{config_sibling_path, Config, ThemeTransition};
+use crate::theme::font::{alpha_sprites, FontRenderOptions, MetricSnips};
+use crate::theme::retro::{retro_theme, RetroThemeOptions};
+use crate::theme::sound::SoundThemeOptions;
+use crate::theme::sprite_sheet::{MinoPoints, TetrominoSpriteSheetMeta};
+use crate::theme::{Theme, ThemeName, VISIBLE_PEEK};
+use sdl2::rect::{Point, Rect};
+use sdl2::render::{TextureCreator, WindowCanvas};
+use sdl2::video::WindowContext;
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+const THEMES_DIR: &str = "themes";
+const MANIFEST_FILE: &str = "theme.yml";
+
+type PointManifest = (i32, i32);
+type RectManifest = (i32, i32, u32, u32);
+type ColorManifest = (u8, u8, u8);
+
+/// Sprite points of a single tetromino, either one point for every mino or one per mino
+#[derive(Debug, Clone, Deserialize)]
+pub struct MinoManifest {
+    normal: Vec<PointManifest>,
+    #[serde(default)]
+    stack: Option<Vec<PointManifest>>,
+}
+
+impl MinoManifest {
+    fn points(points: &[PointManifest]) -> Result<Vec<Point>, String> {
+        match points.len() {
+            1 | 4 => Ok(points.iter().map(|&p| p.into()).collect()),
+            n => Err(format!("expected 1 or 4 mino points, got {}", n)),
+        }
+    }
+
+    fn mino_points(&self) -> Result<MinoPoints, String> {
+        let normal = Self::points(&self.normal)?;
+        let stack = match &self.stack {
+            None => normal.clone(),
+            Some(stack) => Self::points(stack)?,
+        };
+        match (normal.len(), stack.len()) {
+            (1, 1) => Ok((normal[0], stack[0]).into()),
+            (4, 4) => Ok((
+                <[Point; 4]>::try_from(normal).unwrap(),
+                <[Point; 4]>::try_from(stack).unwrap(),
+            )
+                .into()),
+            _ => Err("normal and stack mino points must have the same length".to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SpritesManifest {
+    file: String,
+    block_size: u32,
+    i: MinoManifest,
+    j: MinoManifest,
+    l: MinoManifest,
+    o: MinoManifest,
+    s: MinoManifest,
+    t: MinoManifest,
+    z: MinoManifest,
+    garbage: PointManifest,
+    ghost_alpha: u8,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MetricAlignManifest {
+    Left,
+    Right,
+    ZeroFill,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct MetricManifest {
+    point: PointManifest,
+    max_value: u32,
+    align: MetricAlignManifest,
+}
+
+impl From<MetricManifest> for MetricSnips {
+    fn from(value: MetricManifest) -> Self {
+        match value.align {
+            MetricAlignManifest::Left => MetricSnips::left(value.point, value.max_value),
+            MetricAlignManifest::Right => MetricSnips::right(value.point, value.max_value),
+            MetricAlignManifest::ZeroFill => MetricSnips::zero_fill(value.point, value.max_value),
+        }
+    }
+}
+
+/// Digit sprites 0-9 of the font
+#[derive(Debug, Clone, Deserialize)]
+pub struct FontManifest {
+    file: String,
+    digits: [PointManifest; 10],
+    width: u32,
+    height: u32,
+    spacing: u32,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DestroyAnimationManifest {
+    Flash,
+    Fade,
+    Sweep,
+    Particles { color: ColorManifest },
+}
+
+impl From<DestroyAnimationManifest> for DestroyAnimationType {
+    fn from(value: DestroyAnimationManifest) -> Self {
+        match value {
+            DestroyAnimationManifest::Flash => DestroyAnimationType::Flash,
+            DestroyAnimationManifest::Fade => DestroyAnimationType::Fade,
+            DestroyAnimationManifest::Sweep => DestroyAnimationType::Sweep,
+            DestroyAnimationManifest::Particles { color } => DestroyAnimationType::Particles {
+                color: color.into(),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GameOverAnimationManifest {
+    CurtainUp,
+    CurtainDown,
+}
+
+impl From<GameOverAnimationManifest> for GameOverAnimationType {
+    fn from(value: GameOverAnimationManifest) -> Self {
+        match value {
+            GameOverAnimationManifest::CurtainUp => GameOverAnimationType::CurtainUp,
+            GameOverAnimationManifest::CurtainDown => GameOverAnimationType::CurtainDown,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SoundManifest {
+    music: String,
+    #[serde(rename = "move")]
+    move_tetromino: String,
+    rotate: String,
+    lock: String,
+    send_garbage: String,
+    clear: [String; 4], // single, double, triple, tetris
+    level_up: String,
+    game_over: String,
+    pause: String,
+    victory: String,
+    #[serde(default)]
+    stack_drop: Option<String>,
+    #[serde(default)]
+    hard_drop: Option<String>,
+    #[serde(default)]
+    hold: Option<String>,
+    #[serde(default)]
+    critical_music: Option<String>,
+    #[serde(default)]
+    menu_music: Option<String>,
+    #[serde(default)]
+    menu_start: Option<String>,
+}
+
+impl SoundManifest {
+    fn files(&self) -> Vec<&String> {
+        [
+            &self.music,
+            &self.move_tetromino,
+            &self.rotate,
+            &self.lock,
+            &self.send_garbage,
+            &self.level_up,
+            &self.game_over,
+            &self.pause,
+            &self.victory,
+        ]
+        .into_iter()
+        .chain(self.clear.iter())
+        .chain(
+            [
+                &self.stack_drop,
+                &self.hard_drop,
+                &self.hold,
+                &self.critical_music,
+                &self.menu_music,
+                &self.menu_start,
+            ]
+            .into_iter()
+            .flatten(),
+        )
+        .collect()
+    }
+}
+
+/// The theme.yml manifest of a theme pack, all files are relative to the pack directory
+#[derive(Debug, Clone, Deserialize)]
+pub struct ThemePackManifest {
+    name: String,
+    sprites: SpritesManifest,
+    background: String,
+    board: String,
+    game_over: String,
+    peek: [RectManifest; VISIBLE_PEEK],
+    hold: RectManifest,
+    font: FontManifest,
+    score: MetricManifest,
+    levels: MetricManifest,
+    lines: MetricManifest,
+    board_point: PointManifest,
+    game_point: PointManifest,
+    background_color: ColorManifest,
+    destroy_animation: DestroyAnimationManifest,
+    game_over_animation: GameOverAnimationManifest,
+    #[serde(default)]
+    transition: ThemeTransition,
+    sound: SoundManifest,
+}
+
+impl ThemePackManifest {
+    pub fn parse(yaml: &str) -> Result<Self, String> {
+        let manifest: Self = serde_yaml::from_str(yaml).map_err(|e| e.to_string())?;
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("name cannot be empty".to_string());
+        }
+        if self.sprites.block_size == 0 {
+            return Err("sprites.block_size must be greater than zero".to_string());
+        }
+        for (name, mino) in self.minos() {
+            mino.mino_points()
+                .map_err(|e| format!("sprites.{}: {}", name, e))?;
+        }
+        Ok(())
+    }
+
+    fn minos(&self) -> [(&str, &MinoManifest); 7] {
+        let sprites = &self.sprites;
+        [
+            ("i", &sprites.i),
+            ("j", &sprites.j),
+            ("l", &sprites.l),
+            ("o", &sprites.o),
+            ("s", &sprites.s),
+            ("t", &sprites.t),
+            ("z", &sprites.z),
+        ]
+    }
+
+    fn files(&self) -> Vec<&String> {
+        [
+            &self.sprites.file,
+            &self.background,
+            &self.board,
+            &self.game_over,
+            &self.font.file,
+        ]
+        .into_iter()
+        .chain(self.sound.files())
+        .collect()
+    }
+}
+
+/// A theme loaded from a directory of PNGs, OGGs and a theme.yml manifest
+pub struct ThemePack {
+    id: usize,
+    manifest: ThemePackManifest,
+    files: HashMap<String, Vec<u8>>,
+}
+
+impl ThemePack {
+    pub fn load(id: usize, dir: &Path) -> Result<Self, String> {
+        let manifest_path = dir.join(MANIFEST_FILE);
+        let yaml = fs::read_to_string(&manifest_path)
+            .map_err(|e| format!("{}: {}", manifest_path.display(), e))?;
+        let manifest = ThemePackManifest::parse(&yaml)
+            .map_err(|e| format!("{}: {}", manifest_path.display(), e))?;
+
+        let mut files = HashMap::new();
+        for file in manifest.files() {
+            if files.contains_key(file) {
+                continue;
+            }
+            let path = pack_file_path(dir, file)
+                .map_err(|e| format!("{}: {}", manifest_path.display(), e))?;
+            let bytes = fs::read(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            files.insert(file.clone(), bytes);
+        }
+
+        Ok(Self { id, manifest, files })
+    }
+
+    /// Load every theme pack in the themes directory next to the config file,
+    /// packs that fail to load are skipped & returned as problems
+    pub fn discover() -> (Vec<Self>, Vec<String>) {
+        let dir = match themes_path() {
+            Ok(dir) if dir.is_dir() => dir,
+            _ => return (vec![], vec![]),
+        };
+
+        let mut pack_dirs = match fs::read_dir(&dir) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.join(MANIFEST_FILE).is_file())
+                .collect::<Vec<PathBuf>>(),
+            Err(error) => {
+                return (vec![], vec![format!("Cannot read themes at {}, {}", dir.display(), error)]);
+            }
+        };
+        pack_dirs.sort();
+
+        let mut packs: Vec<Self> = vec![];
+        let mut problems = vec![];
+        for pack_dir in pack_dirs {
+            match Self::load(packs.len(), &pack_dir) {
+                Ok(pack) => packs.push(pack),
+                Err(error) => problems.push(format!("Bad theme pack {}, skipping", error)),
+            }
+        }
+        (packs, problems)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.manifest.name
+    }
+
+    /// A copy of the file for a theme to own, so reloading a pack frees the old files with the old themes
+    fn file(&self, name: &String) -> Cow<'static, [u8]> {
+        Cow::Owned(self.files[name].clone())
+    }
+
+    pub fn sound(&self) -> SoundThemeOptions {
+        let sound = &self.manifest.sound;
+        let mut options = SoundThemeOptions::default(
+            self.file(&sound.music),
+            self.file(&sound.move_tetromino),
+            self.file(&sound.rotate),
+            self.file(&sound.lock),
+            self.file(&sound.send_garbage),
+            sound.clear.each_ref().map(|f| self.file(f)),
+            self.file(&sound.level_up),
+            self.file(&sound.game_over),
+            self.file(&sound.pause),
+            self.file(&sound.victory),
+        );
+        if let Some(file) = &sound.stack_drop {
+            options = options.with_stack_drop(self.file(file));
+        }
+        if let Some(file) = &sound.hard_drop {
+            options = options.with_hard_drop(self.file(file));
+        }
+        if let Some(file) = &sound.hold {
+            options = options.with_hold(self.file(file));
+        }
+        if let Some(file) = &sound.critical_music {
+            options = options.with_critical_music(self.file(file));
+        }
+        if let Some(file) = &sound.menu_music {
+            options = options.with_menu_music(self.file(file));
+        }
+        if let Some(file) = &sound.menu_start {
+            options = options.with_menu_start(self.file(file));
+        }
+        options
+    }
+
+    fn sprite_sheet_meta(&self) -> TetrominoSpriteSheetMeta {
+        let sprites = &self.manifest.sprites;
+        // validated when the manifest was parsed
+        let [i, j, l, o, s, t, z] = self.manifest.minos().map(|(_, m)| m.mino_points().unwrap());
+        TetrominoSpriteSheetMeta::new(
+            self.file(&sprites.file),
+            sprites.block_size,
+            i,
+            j,
+            l,
+            o,
+            s,
+            t,
+            z,
+            sprites.garbage,
+            sprites.ghost_alpha,
+        )
+    }
+
+    pub fn theme<'a>(
+        &self,
+        canvas: &mut WindowCanvas,
+        texture_creator: &'a TextureCreator<WindowContext>,
+        config: &Config,
+    ) -> Result<Theme<'a>, String> {
+        let m = &self.manifest;
+        let options = RetroThemeOptions::new(
+            ThemeName::External(self.id),
+            self.sprite_sheet_meta(),
+            self.file(&m.background),
+            self.file(&m.board),
+            self.file(&m.game_over),
+            m.peek.map(|(x, y, w, h)| Rect::new(x, y, w, h)),
+            Rect::new(m.hold.0, m.hold.1, m.hold.2, m.hold.3),
+            FontRenderOptions::Sprites {
+                file_bytes: self.file(&m.font.file),
+                sprites: alpha_sprites(m.font.digits.map(|p| p.into()), m.font.width, m.font.height),
+                spacing: m.font.spacing,
+            },
+            m.score.into(),
+            m.levels.into(),
+            m.lines.into(),
+            m.board_point.into(),
+            m.game_point.into(),
+            m.background_color.into(),
+            m.destroy_animation.into(),
+            m.game_over_animation.into(),
+            self.sound(),
+        )
+        .with_transition(m.transition);
+        retro_theme(canvas, texture_creator, options, config)
+    }
+}
+
+/// The path of a file named in a manifest, which must stay inside the pack directory
+fn pack_file_path(dir: &Path, file: &str) -> Result<PathBuf, String> {
+    let inside = Path::new(file)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    if !inside {
+        return Err(format!("{} is not a file inside the theme pack", file));
+    }
+    Ok(dir.join(file))
+}
+
+pub fn themes_path() -> Result<PathBuf, String> {
+    config_sibling_path(THEMES_DIR)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sdl2::pixels::Color;
+
+    const MANIFEST: &str = r#"
+name: test
+sprites:
+  file: sprites.png
+  block_size: 8
+  i: { normal: [[0, 0], [8, 0], [16, 0], [24, 0]] }
+  j: { normal: [[0, 8]] }
+  l: { normal: [[0, 8]], stack: [[8, 8]] }
+  o: { normal: [[0, 16]] }
+  s: { normal: [[0, 16]] }
+  t: { normal: [[0, 24]] }
+  z: { normal: [[0, 24]] }
+  garbage: [0, 32]
+  ghost_alpha: 80
+background: background.png
+board: board.png
+game_over: game-over.png
+peek: [[0, 0, 32, 32], [0, 32, 32, 32], [0, 64, 32, 32], [0, 96, 32, 32], [0, 128, 32, 32]]
+hold: [40, 0, 32, 32]
+font:
+  file: sprites.png
+  digits: [[0, 40], [8, 40], [16, 40], [24, 40], [32, 40], [40, 40], [48, 40], [56, 40], [64, 40], [72, 40]]
+  width: 7
+  height: 7
+  spacing: 1
+score: { point: [8, 24], max_value: 999999, align: zero_fill }
+levels: { point: [20, 72], max_value: 999, align: right }
+lines: { point: [20, 91], max_value: 999, align: left }
+board_point: [66, 0]
+game_point: [7, 0]
+background_color: [116, 116, 116]
+destroy_animation: !particles { color: [255, 0, 0] }
+game_over_animation: curtain_down
+sound:
+  music: music.ogg
+  move: move.ogg
+  rotate: rotate.ogg
+  lock: lock.ogg
+  send_garbage: send-garbage.ogg
+  clear: [clear.ogg, clear.ogg, clear.ogg, tetris.ogg]
+  level_up: level-up.ogg
+  game_over: game-over.ogg
+  pause: pause.ogg
+  victory: victory.ogg
+  menu_music: menu.ogg
+"#;
+
+    #[test]
+    fn parses_manifest() {
+        let manifest = ThemePackManifest::parse(MANIFEST).unwrap();
+        assert_eq!(manifest.name, "test");
+        assert_eq!(
+            DestroyAnimationType::from(manifest.destroy_animation),
+            DestroyAnimationType::Particles {
+                color: Color::RGB(255, 0, 0)
+            }
+        );
+        assert_eq!(
+            MetricSnips::from(manifest.score),
+            MetricSnips::zero_fill((8, 24), 999999)
+        );
+        assert!(manifest.files().contains(&&"menu.ogg".to_string()));
+    }
+
+    #[test]
+    fn rejects_bad_mino_points() {
+        let yaml = MANIFEST.replace("{ normal: [[0, 8]] }", "{ normal: [[0, 8], [8, 8]] }");
+        let error = ThemePackManifest::parse(&yaml).unwrap_err();
+        assert!(error.contains("sprites.j"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_files_outside_the_pack() {
+        let dir = Path::new("themes/test");
+        assert_eq!(pack_file_path(dir, "sound/music.ogg"), Ok(dir.join("sound/music.ogg")));
+        assert!(pack_file_path(dir, "../other/music.ogg").is_err());
+        assert!(pack_file_path(dir, "sound/../../music.ogg").is_err());
+        assert!(pack_file_path(dir, "/etc/passwd").is_err());
+    }
+}
@@ -1,23 +1,25 @@
 use crate::animation::destroy::DestroyAnimationType;
 use crate::animation::game_over::GameOverAnimationType;
+use crate::config::{Config, ThemeTransition};
 use crate::theme::font::{FontRenderOptions, MetricSnips};
 use crate::theme::geometry::BoardGeometry;
 use crate::theme::sound::SoundThemeOptions;
 use crate::theme::sprite_sheet::{MinoType, TetrominoSpriteSheet, TetrominoSpriteSheetMeta};
-use crate::theme::{create_mask_texture, TetrominoScaleType, Theme, ThemeName, VISIBLE_PEEK};
+use crate::theme::{create_fade_textures, create_mask_texture, TetrominoScaleType, Theme, ThemeName, VISIBLE_PEEK};
 use sdl2::pixels::Color;
 use sdl2::rect::{Point, Rect};
 use sdl2::render::{BlendMode, Texture, TextureCreator, WindowCanvas};
 use sdl2::video::WindowContext;
-use crate::theme::helper::{CanvasRenderer, TextureFactory};
+use std::borrow::Cow;
+use crate::theme::helper::{remap_color, CanvasRenderer, Palette, TextureFactory};
 
 pub struct RetroThemeOptions {
     name: ThemeName,
     block_size: u32,
     sprite_sheet_meta: TetrominoSpriteSheetMeta,
-    background_file: &'static [u8],
-    board_file: &'static [u8],
-    game_over_file: &'static [u8],
+    background_file: Cow<'static, [u8]>,
+    board_file: Cow<'static, [u8]>,
+    game_over_file: Cow<'static, [u8]>,
     geometry: BoardGeometry,
     peek_snips: [Rect; VISIBLE_PEEK],
     hold_snip: Rect,
@@ -30,15 +32,17 @@ pub struct RetroThemeOptions {
     destroy_animation: DestroyAnimationType,
     game_over_animation: GameOverAnimationType,
     sound: SoundThemeOptions,
+    palette: Palette,
+    transition: ThemeTransition,
 }
 
 impl RetroThemeOptions {
     pub fn new(
         name: ThemeName,
         sprite_sheet_meta: TetrominoSpriteSheetMeta,
-        background_file: &'static [u8],
-        board_file: &'static [u8],
-        game_over_file: &'static [u8],
+        background_file: impl Into<Cow<'static, [u8]>>,
+        board_file: impl Into<Cow<'static, [u8]>>,
+        game_over_file: impl Into<Cow<'static, [u8]>>,
         peek_snips: [Rect; 5],
         hold_snip: Rect,
         font_options: FontRenderOptions,
@@ -59,9 +63,9 @@ impl RetroThemeOptions {
             name,
             block_size,
             sprite_sheet_meta,
-            background_file,
-            board_file,
-            game_over_file,
+            background_file: background_file.into(),
+            board_file: board_file.into(),
+            game_over_file: game_over_file.into(),
             geometry,
             peek_snips,
             hold_snip,
@@ -74,17 +78,31 @@ impl RetroThemeOptions {
             destroy_animation,
             game_over_animation,
             sound,
+            palette: vec![],
+            transition: ThemeTransition::Fade,
         }
     }
+
+    pub fn with_transition(mut self, transition: ThemeTransition) -> Self {
+        self.transition = transition;
+        self
+    }
+
+    /// Remap the colours of every texture in the theme
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self
+    }
 }
 
 pub fn plus_buffer<'a>(
     canvas: &mut WindowCanvas,
     texture_creator: &'a TextureCreator<WindowContext>,
     buffer_height: u32,
-    file_bytes: &'static [u8],
+    file_bytes: &[u8],
+    palette: &[(Color, Color)],
 ) -> Result<(Texture<'a>, u32, u32), String> {
-    let raw = texture_creator.load_texture_bytes_blended(file_bytes)?;
+    let raw = texture_creator.load_texture_bytes_remapped(file_bytes, palette)?;
     let query = raw.query();
     let mut texture = texture_creator.create_texture_target_blended(query.width, query.height + buffer_height)?;
     canvas
@@ -105,11 +123,16 @@ pub fn retro_theme<'a>(
     canvas: &mut WindowCanvas,
     texture_creator: &'a TextureCreator<WindowContext>,
     options: RetroThemeOptions,
+    config: &Config,
 ) -> Result<Theme<'a>, String> {
     let sprite_sheet = TetrominoSpriteSheet::new(
         canvas,
         texture_creator,
-        options.sprite_sheet_meta.clone(),
+        options
+            .sprite_sheet_meta
+            .clone()
+            .with_palette(&options.palette)
+            .with_glyphs(config.video.accessibility.mino_glyphs),
         options.block_size,
     )?;
 
@@ -117,10 +140,12 @@ pub fn retro_theme<'a>(
         canvas,
         texture_creator,
         options.geometry.buffer_height(),
-        options.board_file,
+        &options.board_file,
+        &options.palette,
     )?;
 
     let board_mask_texture = create_mask_texture(canvas, texture_creator, &board_texture)?;
+    let board_fade_textures = create_fade_textures(canvas, texture_creator, &board_texture)?;
 
     let board_snip = Rect::new(
         options.board_point.x(),
@@ -133,12 +158,13 @@ pub fn retro_theme<'a>(
         canvas,
         texture_creator,
         options.geometry.buffer_height(),
-        options.background_file,
+        &options.background_file,
+        &options.palette,
     )?;
 
-    let font = options.font_options.build(texture_creator)?;
+    let font = options.font_options.build(texture_creator, &options.palette)?;
 
-    let game_over = texture_creator.load_texture_bytes_blended(options.game_over_file)?;
+    let game_over = texture_creator.load_texture_bytes_remapped(&options.game_over_file, &options.palette)?;
     let sound = options.sound.clone().build()?;
 
     Ok(Theme {
@@ -147,6 +173,7 @@ pub fn retro_theme<'a>(
         geometry: options.geometry,
         board_texture,
         board_mask_texture,
+        board_fade_textures,
         board_snip,
         background_texture,
         background_size: (bg_width, bg_height),
@@ -158,11 +185,12 @@ pub fn retro_theme<'a>(
         game_over,
         font,
         sound,
-        background_color: options.background_color,
+        background_color: remap_color(&options.palette, options.background_color),
         destroy_animation: options.destroy_animation,
         game_over_animation: options.game_over_animation,
         ghost_mino_type: MinoType::Ghost,
         tetromino_scale_type: TetrominoScaleType::Center,
         particle_color: None,
+        transition: options.transition,
     })
 }
@@ -1,6 +1,6 @@
 use crate::animation::destroy::DestroyAnimationType;
 use crate::animation::game_over::GameOverAnimationType;
-use crate::config::Config;
+use crate::config::{Config, ThemeTransition};
 use crate::theme::font::{alpha_sprites, FontRenderOptions, MetricSnips};
 use crate::theme::geometry::VISIBLE_BUFFER;
 use crate::theme::retro::{retro_theme, RetroThemeOptions};
@@ -23,6 +23,8 @@ const CLEAR_SOUND: &[u8] = include_bytes!("line-clear.ogg");
 const LOCK_SOUND: &[u8] = include_bytes!("lock.ogg");
 const MOVE_SOUND: &[u8] = include_bytes!("move.ogg");
 const MUSIC: &[u8] = include_bytes!("music.ogg");
+const CRITICAL_MUSIC: &[u8] = include_bytes!("music-critical.ogg");
+const MENU_MUSIC: &[u8] = include_bytes!("music-menu.ogg");
 const PAUSE_SOUND: &[u8] = include_bytes!("pause.ogg");
 const ROTATE_SOUND: &[u8] = include_bytes!("rotate.ogg");
 const SEND_GARBAGE_SOUND: &[u8] = include_bytes!("send-garbage.ogg");
@@ -46,7 +48,7 @@ fn char_snip(row: i32, col: i32) -> Point {
 pub fn snes_theme<'a>(
     canvas: &mut WindowCanvas,
     texture_creator: &'a TextureCreator<WindowContext>,
-    config: Config,
+    config: &Config,
 ) -> Result<Theme<'a>, String> {
     let options = RetroThemeOptions::new(
         ThemeName::Snes,
@@ -75,7 +77,7 @@ pub fn snes_theme<'a>(
         ],
         Rect::new(19, 133 + BUFFER_PIXELS as i32, 32, 32),
         FontRenderOptions::Sprites {
-            file_bytes: SPRITES,
+            file_bytes: SPRITES.into(),
             sprites: alpha_sprites(
                 (0..10)
                     .map(|i| char_snip(0, i))
@@ -95,20 +97,26 @@ pub fn snes_theme<'a>(
         Color::RGB(0x74, 0x74, 0x74),
         DestroyAnimationType::Sweep,
         GameOverAnimationType::CurtainDown,
-        SoundThemeOptions::default(
-            config.audio,
-            MUSIC,
-            MOVE_SOUND,
-            ROTATE_SOUND,
-            LOCK_SOUND,
-            SEND_GARBAGE_SOUND,
-            [CLEAR_SOUND, CLEAR_SOUND, CLEAR_SOUND, TETRIS_SOUND],
-            LEVEL_UP_SOUND,
-            GAME_OVER_SOUND,
-            PAUSE_SOUND,
-            VICTORY_SOUND,
-        )
-        .with_stack_drop(STACK_DROP_SOUND),
-    );
-    retro_theme(canvas, texture_creator, options)
+        snes_sound(),
+    )
+    .with_transition(ThemeTransition::Dissolve);
+    retro_theme(canvas, texture_creator, options, config)
+}
+
+pub fn snes_sound() -> SoundThemeOptions {
+    SoundThemeOptions::default(
+        MUSIC,
+        MOVE_SOUND,
+        ROTATE_SOUND,
+        LOCK_SOUND,
+        SEND_GARBAGE_SOUND,
+        [CLEAR_SOUND, CLEAR_SOUND, CLEAR_SOUND, TETRIS_SOUND],
+        LEVEL_UP_SOUND,
+        GAME_OVER_SOUND,
+        PAUSE_SOUND,
+        VICTORY_SOUND,
+    )
+    .with_stack_drop(STACK_DROP_SOUND)
+    .with_critical_music(CRITICAL_MUSIC)
+    .with_menu_music(MENU_MUSIC)
 }
@@ -1,5 +1,7 @@
 use crate::config::AudioConfig;
 use crate::event::GameEvent;
+use std::borrow::Cow;
+use std::ops::Deref;
 
 use rand::{thread_rng, Rng};
 use sdl2::get_error;
@@ -7,10 +9,14 @@ use sdl2::mixer::{Chunk, Music};
 use sdl2::rwops::RWops;
 use sdl2::sys::mixer;
 
-pub fn load_sound(buffer: &[u8], config: AudioConfig) -> Result<Chunk, String> {
-    let mut chunk = chunk_from_buffer(buffer)?;
-    chunk.set_volume(config.effects_volume());
-    Ok(chunk)
+pub fn load_sound(buffer: &[u8]) -> Result<Chunk, String> {
+    chunk_from_buffer(buffer)
+}
+
+/// Sounds are loaded at full volume & played through the mixer channels so volume changes apply immediately
+pub fn set_volumes(config: AudioConfig) {
+    Music::set_volume(config.music_volume());
+    sdl2::mixer::Channel::all().set_volume(config.effects_volume());
 }
 
 pub fn play_sound(chunk: &Chunk) -> Result<(), String> {
@@ -30,89 +36,149 @@ fn chunk_from_buffer(buffer: &[u8]) -> Result<Chunk, String> {
 
 #[derive(Debug, Clone)]
 pub struct SoundThemeOptions {
-    config: AudioConfig,
-    music: &'static [u8],
-    move_tetromino: &'static [u8],
-    rotate: &'static [u8],
-    lock: &'static [u8],
-    send_garbage: Vec<&'static [u8]>,
-    clear: [&'static [u8]; 4], // single, double, triple, tetris
-    level_up: &'static [u8],
-    game_over: &'static [u8],
-    pause: &'static [u8],
-    victory: &'static [u8],
-    stack_drop: Option<&'static [u8]>,
-    hard_drop: Option<&'static [u8]>,
-    hold: Option<&'static [u8]>,
+    music: Cow<'static, [u8]>,
+    move_tetromino: Cow<'static, [u8]>,
+    rotate: Cow<'static, [u8]>,
+    lock: Cow<'static, [u8]>,
+    send_garbage: Vec<Cow<'static, [u8]>>,
+    clear: [Cow<'static, [u8]>; 4], // single, double, triple, tetris
+    level_up: Cow<'static, [u8]>,
+    game_over: Cow<'static, [u8]>,
+    pause: Cow<'static, [u8]>,
+    victory: Cow<'static, [u8]>,
+    stack_drop: Option<Cow<'static, [u8]>>,
+    hard_drop: Option<Cow<'static, [u8]>>,
+    hold: Option<Cow<'static, [u8]>>,
+    critical_music: Option<Cow<'static, [u8]>>,
+    menu_music: Option<Cow<'static, [u8]>>,
+    menu_start: Option<Cow<'static, [u8]>>,
 }
 
 impl SoundThemeOptions {
     pub fn default(
-        config: AudioConfig,
-        music: &'static [u8],
-        move_tetromino: &'static [u8],
-        rotate: &'static [u8],
-        lock: &'static [u8],
-        send_garbage: &'static [u8],
-        clear: [&'static [u8]; 4], // single, double, triple, tetris
-        level_up: &'static [u8],
-        game_over: &'static [u8],
-        pause: &'static [u8],
-        victory: &'static [u8],
+        music: impl Into<Cow<'static, [u8]>>,
+        move_tetromino: impl Into<Cow<'static, [u8]>>,
+        rotate: impl Into<Cow<'static, [u8]>>,
+        lock: impl Into<Cow<'static, [u8]>>,
+        send_garbage: impl Into<Cow<'static, [u8]>>,
+        clear: [impl Into<Cow<'static, [u8]>>; 4], // single, double, triple, tetris
+        level_up: impl Into<Cow<'static, [u8]>>,
+        game_over: impl Into<Cow<'static, [u8]>>,
+        pause: impl Into<Cow<'static, [u8]>>,
+        victory: impl Into<Cow<'static, [u8]>>,
     ) -> Self {
         Self {
-            config,
-            music,
-            move_tetromino,
-            rotate,
-            lock,
-            send_garbage: vec![send_garbage],
-            clear,
-            level_up,
-            game_over,
-            pause,
-            victory,
+            music: music.into(),
+            move_tetromino: move_tetromino.into(),
+            rotate: rotate.into(),
+            lock: lock.into(),
+            send_garbage: vec![send_garbage.into()],
+            clear: clear.map(Into::into),
+            level_up: level_up.into(),
+            game_over: game_over.into(),
+            pause: pause.into(),
+            victory: victory.into(),
             stack_drop: None,
             hard_drop: None,
             hold: None,
+            critical_music: None,
+            menu_music: None,
+            menu_start: None,
         }
     }
 
-    pub fn with_stack_drop(mut self, value: &'static [u8]) -> Self {
-        self.stack_drop = Some(value);
+    pub fn with_stack_drop(mut self, value: impl Into<Cow<'static, [u8]>>) -> Self {
+        self.stack_drop = Some(value.into());
+        self
+    }
+
+    pub fn with_hard_drop(mut self, value: impl Into<Cow<'static, [u8]>>) -> Self {
+        self.hard_drop = Some(value.into());
+        self
+    }
+
+    pub fn with_hold(mut self, value: impl Into<Cow<'static, [u8]>>) -> Self {
+        self.hold = Some(value.into());
         self
     }
 
-    pub fn with_hard_drop(mut self, value: &'static [u8]) -> Self {
-        self.hard_drop = Some(value);
+    /// Music played instead of the main track while the stack is dangerously high
+    pub fn with_critical_music(mut self, value: impl Into<Cow<'static, [u8]>>) -> Self {
+        self.critical_music = Some(value.into());
         self
     }
 
-    pub fn with_hold(mut self, value: &'static [u8]) -> Self {
-        self.hold = Some(value);
+    /// Music played on the main menu while this theme is selected
+    pub fn with_menu_music(mut self, value: impl Into<Cow<'static, [u8]>>) -> Self {
+        self.menu_music = Some(value.into());
         self
     }
 
-    pub fn with_alt_send_garbage(mut self, value: &'static [u8]) -> Self {
-        self.send_garbage.push(value);
+    /// Sound played when starting a match from the main menu while this theme is selected
+    pub fn with_menu_start(mut self, value: impl Into<Cow<'static, [u8]>>) -> Self {
+        self.menu_start = Some(value.into());
         self
     }
 
-    fn load_music<'a>(&self) -> Result<Music<'a>, String> {
-        Music::from_static_bytes(self.music)
+    pub fn with_alt_send_garbage(mut self, value: impl Into<Cow<'static, [u8]>>) -> Self {
+        self.send_garbage.push(value.into());
+        self
     }
 
     fn load_sound(&self, buffer: &[u8]) -> Result<Chunk, String> {
-        load_sound(buffer, self.config)
+        load_sound(buffer)
     }
 
     pub fn build(self) -> Result<SoundTheme, String> {
         SoundTheme::new(self)
     }
+
+    pub fn build_menu(&self) -> Result<MenuThemeSound, String> {
+        Ok(MenuThemeSound {
+            music: self.menu_music.clone().map(ThemeMusic::new).transpose()?,
+            start: self.menu_start.as_ref().map(|p| self.load_sound(p)).transpose()?,
+        })
+    }
 }
 
-pub struct SoundTheme {
+/// Music is streamed from its bytes while it plays so it keeps hold of them, e.g. the files of a theme pack
+pub struct ThemeMusic {
+    // declared first so it's dropped before the bytes it streams from
     music: Music<'static>,
+    _bytes: Cow<'static, [u8]>,
+}
+
+impl ThemeMusic {
+    pub fn new(bytes: Cow<'static, [u8]>) -> Result<Self, String> {
+        let music = match &bytes {
+            Cow::Borrowed(bytes) => Music::from_static_bytes(bytes)?,
+            Cow::Owned(bytes) => {
+                // SAFETY: the bytes are on the heap so don't move with self & outlive the music, see field order
+                let bytes: &'static [u8] = unsafe { std::slice::from_raw_parts(bytes.as_ptr(), bytes.len()) };
+                Music::from_static_bytes(bytes)?
+            }
+        };
+        Ok(Self { music, _bytes: bytes })
+    }
+}
+
+impl Deref for ThemeMusic {
+    type Target = Music<'static>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.music
+    }
+}
+
+/// Optional main menu assets of a theme, loaded without building the rest of the theme
+pub struct MenuThemeSound {
+    pub music: Option<ThemeMusic>,
+    pub start: Option<Chunk>,
+}
+
+pub struct SoundTheme {
+    music: ThemeMusic,
+    critical_music: Option<ThemeMusic>,
     move_tetromino: Chunk,
     rotate: Chunk,
     lock: Chunk,
@@ -129,30 +195,39 @@ pub struct SoundTheme {
 
 impl SoundTheme {
     pub fn new(options: SoundThemeOptions) -> Result<Self, String> {
-        let o = options.clone();
+        let o = &options;
         Ok(Self {
-            music: options.load_music()?,
-            move_tetromino: options.load_sound(o.move_tetromino)?,
-            rotate: options.load_sound(o.rotate)?,
-            lock: options.load_sound(o.lock)?,
+            move_tetromino: o.load_sound(&o.move_tetromino)?,
+            rotate: o.load_sound(&o.rotate)?,
+            lock: o.load_sound(&o.lock)?,
             send_garbage: o
                 .send_garbage
-                .into_iter()
-                .map(|p| options.load_sound(p).unwrap())
+                .iter()
+                .map(|p| o.load_sound(p).unwrap())
                 .collect(),
-            clear: o.clear.map(|p| options.load_sound(p).unwrap()),
-            level_up: options.load_sound(o.level_up)?,
-            game_over: options.load_sound(o.game_over)?,
-            pause: options.load_sound(o.pause)?,
-            victory: options.load_sound(o.victory)?,
-            stack_drop: o.stack_drop.map(|p| options.load_sound(p).unwrap()),
-            hard_drop: o.hard_drop.map(|p| options.load_sound(p).unwrap()),
-            hold: o.hold.map(|p| options.load_sound(p).unwrap()),
+            clear: o.clear.each_ref().map(|p| o.load_sound(p).unwrap()),
+            level_up: o.load_sound(&o.level_up)?,
+            game_over: o.load_sound(&o.game_over)?,
+            pause: o.load_sound(&o.pause)?,
+            victory: o.load_sound(&o.victory)?,
+            stack_drop: o.stack_drop.as_ref().map(|p| o.load_sound(p).unwrap()),
+            hard_drop: o.hard_drop.as_ref().map(|p| o.load_sound(p).unwrap()),
+            hold: o.hold.as_ref().map(|p| o.load_sound(p).unwrap()),
+            // the music takes the bytes it streams from
+            critical_music: options.critical_music.map(ThemeMusic::new).transpose()?,
+            music: ThemeMusic::new(options.music)?,
         })
     }
 
-    pub fn music(&self) -> &Music {
-        &self.music
+    pub fn music(&self, critical: bool) -> &Music {
+        match &self.critical_music {
+            Some(critical_music) if critical => critical_music,
+            _ => &self.music,
+        }
+    }
+
+    pub fn has_critical_music(&self) -> bool {
+        self.critical_music.is_some()
     }
 
     pub fn receive_event(&self, event: GameEvent) -> Result<(), String> {
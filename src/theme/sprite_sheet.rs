@@ -8,10 +8,16 @@ use bitflags::Flags;
 use sdl2::image::LoadTexture;
 use sdl2::pixels::Color;
 use sdl2::rect::{Point, Rect};
-use sdl2::render::{BlendMode, Texture, TextureCreator, WindowCanvas};
-use sdl2::video::WindowContext;
+use sdl2::render::{BlendMode, Canvas, Texture, TextureCreator, WindowCanvas};
+use sdl2::video::{Window, WindowContext};
+use std::borrow::Cow;
 use std::collections::HashMap;
-use crate::theme::helper::{CanvasRenderer, TextureFactory};
+use crate::theme::helper::{compose_palettes, CanvasRenderer, Palette, TextureFactory};
+
+// glyphs are drawn on an 8 x 8 grid of cells over each mino
+const GLYPH_GRID: u32 = 8;
+const GLYPH_COLOR: Color = Color::RGBA(0xff, 0xff, 0xff, 0xe0);
+const GLYPH_OUTLINE_COLOR: Color = Color::RGBA(0, 0, 0, 0xa0);
 
 struct TetrominoTexture<'a> {
     perimeter: Texture<'a>,
@@ -45,7 +51,7 @@ pub enum MinoType {
 
 #[derive(Debug, Clone)]
 pub struct TetrominoSpriteSheetMeta {
-    sprite_file: &'static [u8],
+    sprite_file: Cow<'static, [u8]>,
     source_block_size: u32,
     i: MinoPoints,
     j: MinoPoints,
@@ -56,6 +62,9 @@ pub struct TetrominoSpriteSheetMeta {
     z: MinoPoints,
     garbage: Point,
     ghost_alpha: u8,
+    level_palettes: Vec<Palette>,
+    glyphs: bool,
+    shape_tints: Vec<(TetrominoShape, Color)>,
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -116,8 +125,8 @@ impl From<([Point; 4], [Point; 4])> for MinoPoints {
 }
 
 impl TetrominoSpriteSheetMeta {
-    pub fn new<I, J, L, O, S, T, Z, G>(
-        sprite_file: &'static [u8],
+    pub fn new<F, I, J, L, O, S, T, Z, G>(
+        sprite_file: F,
         source_block_size: u32,
         i: I,
         j: J,
@@ -130,6 +139,7 @@ impl TetrominoSpriteSheetMeta {
         ghost_alpha: u8,
     ) -> Self
     where
+        F: Into<Cow<'static, [u8]>>,
         I: Into<MinoPoints>,
         J: Into<MinoPoints>,
         L: Into<MinoPoints>,
@@ -140,7 +150,7 @@ impl TetrominoSpriteSheetMeta {
         G: Into<Point>,
     {
         Self {
-            sprite_file,
+            sprite_file: sprite_file.into(),
             source_block_size,
             i: i.into(),
             j: j.into(),
@@ -151,9 +161,57 @@ impl TetrominoSpriteSheetMeta {
             z: z.into(),
             garbage: garbage.into(),
             ghost_alpha,
+            level_palettes: vec![],
+            glyphs: false,
+            shape_tints: vec![],
         }
     }
 
+    /// Draw a different glyph on every mino of each shape so they can be told apart without colour
+    pub fn with_glyphs(mut self, glyphs: bool) -> Self {
+        self.glyphs = glyphs;
+        self
+    }
+
+    /// Recolour the sprites of each shape, keeping the shading
+    pub fn with_shape_tints(mut self, shape_tints: Vec<(TetrominoShape, Color)>) -> Self {
+        self.shape_tints = shape_tints;
+        self
+    }
+
+    fn tint_rects(&self) -> Vec<(Rect, Color)> {
+        self.shape_tints
+            .iter()
+            .flat_map(|(shape, tint)| {
+                let normal = self.snips(*shape, MinoType::Normal);
+                let stack = self.snips(*shape, MinoType::Stack);
+                normal.into_iter().chain(stack).map(move |rect| (rect, *tint))
+            })
+            .collect()
+    }
+
+    /// Remap the sprite colours by level, cycling through the palettes
+    pub fn with_level_palettes(mut self, level_palettes: Vec<Palette>) -> Self {
+        self.level_palettes = level_palettes;
+        self
+    }
+
+    /// Remap the sprite colours for all levels, applied after any level palettes
+    pub fn with_palette(mut self, palette: &[(Color, Color)]) -> Self {
+        if palette.is_empty() {
+            return self;
+        }
+        self.level_palettes = if self.level_palettes.is_empty() {
+            vec![palette.to_vec()]
+        } else {
+            self.level_palettes
+                .iter()
+                .map(|level_palette| compose_palettes(level_palette, palette))
+                .collect()
+        };
+        self
+    }
+
     fn snips(&self, shape: TetrominoShape, mino_type: MinoType) -> [Rect; 4] {
         let points = self.shape(shape).of_type(mino_type);
         points
@@ -205,12 +263,16 @@ impl<'a> FlatTetrominoSpriteSheet<'a> {
     }
 }
 
-pub struct TetrominoSpriteSheet<'a> {
+struct SpritePalette<'a> {
     tetrominos: HashMap<TetrominoShape, TetrominoTexture<'a>>,
-    block_size: u32,
     garbage: Texture<'a>,
 }
 
+pub struct TetrominoSpriteSheet<'a> {
+    palettes: Vec<SpritePalette<'a>>,
+    block_size: u32,
+}
+
 fn draw_sprites<'a>(
     canvas: &mut WindowCanvas,
     texture_creator: &'a TextureCreator<WindowContext>,
@@ -232,6 +294,77 @@ fn draw_sprites<'a>(
     Ok(texture)
 }
 
+/// Cells of the glyph of each shape as (x, y, width, height), all glyphs look the same when rotated
+fn glyph_cells(shape: TetrominoShape) -> &'static [(i32, i32, u32, u32)] {
+    match shape {
+        // dot
+        TetrominoShape::I => &[(3, 3, 2, 2)],
+        // hollow square
+        TetrominoShape::O => &[(2, 2, 4, 1), (2, 5, 4, 1), (2, 3, 1, 2), (5, 3, 1, 2)],
+        // plus
+        TetrominoShape::T => &[(2, 3, 4, 2), (3, 2, 2, 4)],
+        // cross
+        TetrominoShape::S => &[
+            (2, 2, 1, 1), (3, 3, 1, 1), (4, 4, 1, 1), (5, 5, 1, 1),
+            (5, 2, 1, 1), (4, 3, 1, 1), (3, 4, 1, 1), (2, 5, 1, 1),
+        ],
+        // corners
+        TetrominoShape::Z => &[(2, 2, 1, 1), (5, 2, 1, 1), (2, 5, 1, 1), (5, 5, 1, 1)],
+        // diamond
+        TetrominoShape::J => &[
+            (3, 2, 2, 1), (2, 3, 1, 2), (5, 3, 1, 2), (3, 5, 2, 1),
+        ],
+        // dice five
+        TetrominoShape::L => &[(2, 2, 1, 1), (5, 2, 1, 1), (2, 5, 1, 1), (5, 5, 1, 1), (3, 3, 2, 2)],
+    }
+}
+
+/// Glyph cells scaled to a mino
+fn glyph_rects(shape: TetrominoShape, mino: Rect) -> Vec<Rect> {
+    let cell = (mino.width() / GLYPH_GRID).max(1);
+    glyph_cells(shape)
+        .iter()
+        .map(|(x, y, w, h)| {
+            Rect::new(
+                mino.x() + x * cell as i32,
+                mino.y() + y * cell as i32,
+                w * cell,
+                h * cell,
+            )
+        })
+        .collect()
+}
+
+fn draw_glyphs(c: &mut Canvas<Window>, shape: TetrominoShape, mino_rects: [Rect; 4]) {
+    let blend_mode = c.blend_mode();
+    c.set_blend_mode(BlendMode::Blend);
+    for mino in mino_rects {
+        let rects = glyph_rects(shape, mino);
+        c.set_draw_color(GLYPH_OUTLINE_COLOR);
+        for rect in rects.iter() {
+            let mut outline = *rect;
+            outline.offset(-1, -1);
+            outline.resize(rect.width() + 2, rect.height() + 2);
+            c.fill_rect(outline).unwrap();
+        }
+        c.set_draw_color(GLYPH_COLOR);
+        c.fill_rects(&rects).unwrap();
+    }
+    c.set_blend_mode(blend_mode);
+}
+
+fn with_glyphs<'a>(
+    canvas: &mut WindowCanvas,
+    mut texture: Texture<'a>,
+    shape: TetrominoShape,
+    mino_rects: [Rect; 4],
+) -> Result<Texture<'a>, String> {
+    canvas
+        .with_texture_canvas(&mut texture, |c| draw_glyphs(c, shape, mino_rects))
+        .map_err(|e| e.to_string())?;
+    Ok(texture)
+}
+
 fn draw_perimeter<'a>(
     canvas: &mut WindowCanvas,
     texture_creator: &'a TextureCreator<WindowContext>,
@@ -287,15 +420,14 @@ fn draw_perimeter<'a>(
     Ok(texture)
 }
 
-impl<'a> TetrominoSpriteSheet<'a> {
-    pub fn new(
+impl<'a> SpritePalette<'a> {
+    fn new(
         canvas: &mut WindowCanvas,
         texture_creator: &'a TextureCreator<WindowContext>,
-        meta: TetrominoSpriteSheetMeta,
+        meta: &TetrominoSpriteSheetMeta,
+        sprite_src: &Texture,
         block_size: u32,
     ) -> Result<Self, String> {
-        let sprite_src = texture_creator.load_texture_bytes_blended(meta.sprite_file)?;
-
         let mut textures = HashMap::new();
         for shape in TetrominoShape::ALL.iter().copied() {
             let normal_minos = shape.meta().normal_minos();
@@ -317,15 +449,24 @@ impl<'a> TetrominoSpriteSheet<'a> {
                 .try_into()
                 .unwrap();
 
+            let draw = |canvas: &mut WindowCanvas, texture: Texture<'a>| {
+                if meta.glyphs {
+                    with_glyphs(canvas, texture, shape, mino_rects)
+                } else {
+                    Ok(texture)
+                }
+            };
+
             let normal = draw_sprites(
                 canvas,
                 texture_creator,
-                &sprite_src,
+                sprite_src,
                 meta.snips(shape, MinoType::Normal),
                 mino_rects,
                 width,
                 height,
             )?;
+            let normal = draw(canvas, normal)?;
 
             let mut ghost = texture_creator.create_texture_target_blended(width, height)?;
             ghost.set_alpha_mod(meta.ghost_alpha);
@@ -336,36 +477,24 @@ impl<'a> TetrominoSpriteSheet<'a> {
                 })
                 .map_err(|e| e.to_string())?;
 
+            let perimeter = draw_perimeter(canvas, texture_creator, shape, mino_rects, width, height)?;
+            let stack = draw_sprites(
+                canvas,
+                texture_creator,
+                sprite_src,
+                meta.snips(shape, MinoType::Stack),
+                mino_rects,
+                width,
+                height,
+            )?;
+
             textures.insert(
                 shape,
                 TetrominoTexture {
-                    perimeter: draw_perimeter(
-                        canvas,
-                        texture_creator,
-                        shape,
-                        mino_rects,
-                        width,
-                        height,
-                    )?,
-                    normal: draw_sprites(
-                        canvas,
-                        texture_creator,
-                        &sprite_src,
-                        meta.snips(shape, MinoType::Normal),
-                        mino_rects,
-                        width,
-                        height,
-                    )?,
+                    perimeter: draw(canvas, perimeter)?,
+                    normal,
                     ghost,
-                    stack: draw_sprites(
-                        canvas,
-                        texture_creator,
-                        &sprite_src,
-                        meta.snips(shape, MinoType::Stack),
-                        mino_rects,
-                        width,
-                        height,
-                    )?,
+                    stack: draw(canvas, stack)?,
                     snips: mino_rects,
                     width,
                     height,
@@ -378,19 +507,56 @@ impl<'a> TetrominoSpriteSheet<'a> {
         canvas
             .with_texture_canvas(&mut garbage, |c| {
                 c.clear_0();
-                c.copy(&sprite_src, meta.garbage_snip(), None).unwrap();
+                c.copy(sprite_src, meta.garbage_snip(), None).unwrap();
             })
             .map_err(|e| e.to_string())?;
 
         Ok(Self {
             tetrominos: textures,
-            block_size,
             garbage,
         })
     }
+}
+
 
-    pub fn draw_garbage(&self, canvas: &mut WindowCanvas, dest: Point) -> Result<(), String> {
-        canvas.copy(&self.garbage, None, self.mino_rect(dest))
+impl<'a> TetrominoSpriteSheet<'a> {
+    pub fn new(
+        canvas: &mut WindowCanvas,
+        texture_creator: &'a TextureCreator<WindowContext>,
+        meta: TetrominoSpriteSheetMeta,
+        block_size: u32,
+    ) -> Result<Self, String> {
+        let palettes = if meta.level_palettes.is_empty() {
+            vec![vec![]]
+        } else {
+            meta.level_palettes.clone()
+        };
+
+        let tints = meta.tint_rects();
+        let mut sprite_palettes = vec![];
+        for palette in palettes.iter() {
+            let sprite_src = texture_creator.load_texture_bytes_tinted(&meta.sprite_file, palette, &tints)?;
+            sprite_palettes.push(SpritePalette::new(
+                canvas,
+                texture_creator,
+                &meta,
+                &sprite_src,
+                block_size,
+            )?);
+        }
+
+        Ok(Self {
+            palettes: sprite_palettes,
+            block_size,
+        })
+    }
+
+    fn palette(&self, level: u32) -> &SpritePalette<'a> {
+        &self.palettes[level as usize % self.palettes.len()]
+    }
+
+    pub fn draw_garbage(&self, canvas: &mut WindowCanvas, dest: Point, level: u32) -> Result<(), String> {
+        canvas.copy(&self.palette(level).garbage, None, self.mino_rect(dest))
     }
 
     pub fn draw_tetromino_in_center(
@@ -399,8 +565,9 @@ impl<'a> TetrominoSpriteSheet<'a> {
         shape: TetrominoShape,
         mino_type: MinoType,
         dest: Point,
+        level: u32,
     ) -> Result<(), String> {
-        let tetromino = self.tetrominos.get(&shape).unwrap();
+        let tetromino = self.palette(level).tetrominos.get(&shape).unwrap();
         let rect = Rect::from_center(dest, tetromino.width, tetromino.height);
         canvas.copy(tetromino.texture(mino_type), None, rect)
     }
@@ -412,8 +579,9 @@ impl<'a> TetrominoSpriteSheet<'a> {
         mino_type: MinoType,
         dest: Rect,
         max_scale: f64,
+        level: u32,
     ) -> Result<(), String> {
-        let tetromino = self.tetrominos.get(&shape).unwrap();
+        let tetromino = self.palette(level).tetrominos.get(&shape).unwrap();
         let scale_x = dest.width() as f64 / tetromino.width as f64;
         let scale_y = dest.height() as f64 / tetromino.height as f64;
         let scale = scale_x.min(scale_y).min(max_scale);
@@ -432,22 +600,23 @@ impl<'a> TetrominoSpriteSheet<'a> {
         geometry: &BoardGeometry,
         ghost_type: MinoType,
     ) -> Result<(), String> {
+        let level = game.level();
         for j in 0..VISIBLE_BOARD_HEIGHT {
             for (i, block) in game.row(j).iter().copied().enumerate() {
                 let point = geometry.mino_point(i as u32, j);
                 match block {
                     BlockState::Empty => {}
                     BlockState::Tetromino(shape, rotation, mino_id) => {
-                        self.draw(canvas, shape, rotation, mino_id, point, MinoType::Normal)?;
+                        self.draw(canvas, shape, rotation, mino_id, point, MinoType::Normal, level)?;
                     }
                     BlockState::Ghost(shape, rotation, mino_id) => {
-                        self.draw(canvas, shape, rotation, mino_id, point, ghost_type)?;
+                        self.draw(canvas, shape, rotation, mino_id, point, ghost_type, level)?;
                     }
                     BlockState::Stack(shape, rotation, mino_id) => {
-                        self.draw(canvas, shape, rotation, mino_id, point, MinoType::Stack)?;
+                        self.draw(canvas, shape, rotation, mino_id, point, MinoType::Stack, level)?;
                     }
                     BlockState::Garbage => {
-                        self.draw_garbage(canvas, point)?;
+                        self.draw_garbage(canvas, point, level)?;
                     }
                 }
             }
@@ -463,8 +632,9 @@ impl<'a> TetrominoSpriteSheet<'a> {
         mino_id: u32,
         dest: Point,
         mino_type: MinoType,
+        level: u32,
     ) -> Result<(), String> {
-        let tetromino = self.tetrominos.get(&shape).unwrap();
+        let tetromino = self.palette(level).tetrominos.get(&shape).unwrap();
         let snip = tetromino.snips[mino_id as usize];
         let dest = self.mino_rect(dest);
         let texture = tetromino.texture(mino_type);
@@ -485,8 +655,9 @@ impl<'a> TetrominoSpriteSheet<'a> {
         texture_creator: &'b TextureCreator<WindowContext>,
         mino_type: MinoType,
     ) -> Result<FlatTetrominoSpriteSheet<'b>, String> {
+        let palette = self.palette(0);
         let sizes = TetrominoShape::ALL.map(|s| {
-            let tetromino = self.tetrominos.get(&s).unwrap();
+            let tetromino = palette.tetrominos.get(&s).unwrap();
             (tetromino.width, tetromino.height)
         });
         let width = sizes.map(|(w, _)| w).into_iter().sum::<u32>();
@@ -499,7 +670,7 @@ impl<'a> TetrominoSpriteSheet<'a> {
                 c.clear_0();
                 
                 let mut x = 0;
-                for (shape, tetromino) in self.tetrominos.iter() {
+                for (shape, tetromino) in palette.tetrominos.iter() {
                     let rect = Rect::new(x, 0, tetromino.width, tetromino.height);
                     snips.insert(*shape, rect);
                     c.copy(tetromino.texture(mino_type), None, rect).unwrap();
@@ -511,3 +682,37 @@ impl<'a> TetrominoSpriteSheet<'a> {
         Ok(FlatTetrominoSpriteSheet { texture, snips })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glyphs_are_distinct() {
+        for a in TetrominoShape::ALL {
+            for b in TetrominoShape::ALL {
+                if a != b {
+                    assert_ne!(glyph_cells(a), glyph_cells(b), "{:?} {:?}", a, b);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn glyphs_fit_inside_the_mino_outline() {
+        for shape in TetrominoShape::ALL {
+            for (x, y, w, h) in glyph_cells(shape) {
+                assert!(*x >= 1 && *y >= 1, "{:?}", shape);
+                assert!(*x as u32 + w < GLYPH_GRID && *y as u32 + h < GLYPH_GRID, "{:?}", shape);
+            }
+        }
+    }
+
+    #[test]
+    fn glyphs_scale_to_the_mino() {
+        let rects = glyph_rects(TetrominoShape::I, Rect::new(100, 200, 48, 48));
+        assert_eq!(rects, vec![Rect::new(118, 218, 12, 12)]);
+        let rects = glyph_rects(TetrominoShape::I, Rect::new(0, 0, 4, 4));
+        assert_eq!(rects, vec![Rect::new(3, 3, 2, 2)]);
+    }
+}
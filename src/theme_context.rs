@@ -1,4 +1,9 @@
-use crate::config::{Config, GameConfig, MatchThemes, VideoConfig};
+use crate::animation::transition::draw_transition;
+use crate::config::{
+    Config, CrtConfig, GameConfig, MatchThemes, ThemeSwitch, ThemeTransition, TransitionConfig,
+    VideoConfig,
+};
+use crate::crt::CrtFilter;
 use crate::game::tetromino::Minos;
 use crate::scale::Scale;
 use crate::theme::all::AllThemes;
@@ -6,11 +11,57 @@ use crate::theme::Theme;
 use sdl2::rect::Rect;
 use sdl2::render::{BlendMode, Texture, TextureCreator, WindowCanvas};
 
+use rand::seq::SliceRandom;
+use rand::thread_rng;
 use sdl2::video::WindowContext;
 use std::time::Duration;
 use crate::theme::helper::TextureFactory;
 
-const THEME_FADE_DURATION: Duration = Duration::from_millis(1000);
+/// Decides when to switch to the next theme in the playlist
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PlaylistTimer {
+    switch: ThemeSwitch,
+    level: u32,
+    lines: u32,
+    duration: Duration,
+}
+
+impl PlaylistTimer {
+    pub fn new(switch: ThemeSwitch, level: u32, lines: u32) -> Self {
+        Self {
+            switch,
+            level,
+            lines,
+            duration: Duration::ZERO,
+        }
+    }
+
+    /// Returns true if the theme should switch now
+    pub fn update(&mut self, delta: Duration, level: u32, lines: u32) -> bool {
+        match self.switch {
+            ThemeSwitch::Levels(levels) => {
+                if level >= self.level + levels.max(1) {
+                    self.level = level;
+                    return true;
+                }
+            }
+            ThemeSwitch::Lines(count) => {
+                if lines >= self.lines + count.max(1) {
+                    self.lines = lines;
+                    return true;
+                }
+            }
+            ThemeSwitch::Minutes(minutes) => {
+                self.duration += delta;
+                if self.duration >= Duration::from_secs(60 * minutes.max(1) as u64) {
+                    self.duration = Duration::ZERO;
+                    return true;
+                }
+            }
+        }
+        false
+    }
+}
 
 pub struct PlayerTextures<'a> {
     pub background: Texture<'a>,
@@ -66,10 +117,17 @@ pub struct ScaledTheme<'a> {
     board_source_snip: Rect,
     player_themes: Vec<ThemedPlayer>,
     scale: Scale,
+    crt: Option<CrtConfig>,
 }
 
 impl<'a> ScaledTheme<'a> {
-    fn new(theme: &'a Theme, players: u32, window_size: (u32, u32), video_config: VideoConfig) -> Self {
+    fn new(
+        theme: &'a Theme,
+        players: u32,
+        window_size: (u32, u32),
+        video_config: &VideoConfig,
+        crt: Option<CrtConfig>,
+    ) -> Self {
         let scale = Scale::new(
             players,
             theme.background_size(),
@@ -91,6 +149,7 @@ impl<'a> ScaledTheme<'a> {
             board_source_snip,
             player_themes,
             scale,
+            crt,
         }
     }
 
@@ -110,10 +169,16 @@ impl<'a> ScaledTheme<'a> {
 }
 
 pub struct ThemeContext<'a> {
-    current: usize,
+    /// index of the current theme of each player
+    current: Vec<usize>,
+    /// themes cycled through by each player
+    playlists: Vec<Vec<usize>>,
     themes: Vec<ScaledTheme<'a>>,
-    fade_buffer: Texture<'a>,
-    fade_duration: Option<Duration>,
+    transition_buffer: Texture<'a>,
+    transition_config: TransitionConfig,
+    reduced_motion: bool,
+    transition: Option<(Duration, ThemeTransition)>,
+    crt_filter: CrtFilter<'a>,
 }
 
 impl<'a> ThemeContext<'a> {
@@ -121,29 +186,50 @@ impl<'a> ThemeContext<'a> {
         all_themes: &'a AllThemes,
         texture_creator: &'a TextureCreator<WindowContext>,
         game_config: GameConfig,
-        config: Config,
+        config: &Config,
         window_size: (u32, u32),
+        playlist: Vec<usize>,
+        crt_filters: Vec<Option<CrtConfig>>,
     ) -> Result<Self, String> {
         let (window_width, window_height) = window_size;
 
-        let fade_buffer = texture_creator.create_texture_target_blended(window_width, window_height)?;
+        let transition_buffer = texture_creator.create_texture_target_blended(window_width, window_height)?;
 
-        let current = match game_config.themes {
-            MatchThemes::All | MatchThemes::GameBoy => 0,
-            MatchThemes::Nes => 1,
-            MatchThemes::Snes => 2,
-            MatchThemes::Modern => 3,
+        let all_playlist = (0..all_themes.all().len()).collect::<Vec<usize>>();
+        let mut playlist = if playlist.is_empty() {
+            all_playlist.clone()
+        } else {
+            playlist
         };
+        if config.playlist.shuffle {
+            playlist.shuffle(&mut thread_rng());
+        }
+
+        // players on all themes run through the playlist, otherwise every theme is cycled from the selected one
+        let (current, playlists) = (1..=game_config.players)
+            .map(|player| match game_config.player_themes(player) {
+                MatchThemes::All => (playlist[0], playlist.clone()),
+                themes => (themes.index() - 1, all_playlist.clone()),
+            })
+            .unzip();
 
         Ok(Self {
             current,
+            playlists,
             themes: all_themes
                 .all()
                 .iter()
-                .map(|theme| ScaledTheme::new(theme, game_config.players, window_size, config.video))
+                .enumerate()
+                .map(|(index, theme)| {
+                    let crt = crt_filters.get(index).copied().flatten();
+                    ScaledTheme::new(theme, game_config.players, window_size, &config.video, crt)
+                })
                 .collect(),
-            fade_buffer,
-            fade_duration: None,
+            transition_buffer,
+            transition_config: config.video.theme_transition,
+            reduced_motion: config.video.accessibility.reduced_motion,
+            transition: None,
+            crt_filter: CrtFilter::new(texture_creator, window_size)?,
         })
     }
 
@@ -164,32 +250,34 @@ impl<'a> ThemeContext<'a> {
         (width, height)
     }
 
-    pub fn theme(&self) -> &Theme<'a> {
-        self.themes[self.current].theme
+    pub fn player_theme(&self, player: u32) -> &Theme<'a> {
+        self.player(player).theme
     }
 
     pub fn player_line_snip(&self, player: u32, j: u32) -> Rect {
-        let theme = &self.themes[self.current];
-        let player = theme.player_themes.get(player as usize - 1).unwrap();
+        let theme = self.player(player);
+        let themed_player = theme.player_themes.get(player as usize - 1).unwrap();
         theme.scale.scale_and_offset_rect(
             theme.theme.geometry().line_snip(j),
-            player.board_snip.x(),
-            player.board_snip.y(),
+            themed_player.board_snip.x(),
+            themed_player.board_snip.y(),
         )
     }
 
     pub fn player_mino_snips(&self, player: u32, minos: Minos) -> [Rect; 4] {
-        let theme = &self.themes[self.current];
-        let player = theme.player_themes.get(player as usize - 1).unwrap();
+        let theme = self.player(player);
+        let themed_player = theme.player_themes.get(player as usize - 1).unwrap();
         theme.theme.geometry().mino_rects(minos).map(|r| {
-            theme
-                .scale
-                .scale_and_offset_rect(r, player.board_snip.x(), player.board_snip.y())
+            theme.scale.scale_and_offset_rect(
+                r,
+                themed_player.board_snip.x(),
+                themed_player.board_snip.y(),
+            )
         })
     }
 
     pub fn player_board_snip(&self, player: u32) -> Rect {
-        let theme = &self.themes[self.current];
+        let theme = self.player(player);
         theme
             .player_themes
             .get(player as usize - 1)
@@ -197,20 +285,48 @@ impl<'a> ThemeContext<'a> {
             .board_snip
     }
 
-    pub fn current(&self) -> &ScaledTheme {
-        &self.themes[self.current]
+    pub fn player_window(&self, player: u32) -> Rect {
+        self.player(player).scale.player_window(player)
     }
 
-    pub fn next(&mut self) {
-        self.current = (self.current + 1) % self.themes.len();
+    /// The current scaled theme of a player
+    pub fn player(&self, player: u32) -> &ScaledTheme<'a> {
+        &self.themes[self.current[player as usize - 1]]
     }
 
-    pub fn start_fade(&mut self, canvas: &mut WindowCanvas) -> Result<(), String> {
-        self.fade_duration = Some(Duration::ZERO);
+    /// Cycle the theme of a single player
+    pub fn next(&mut self, player: u32) {
+        let playlist = &self.playlists[player as usize - 1];
+        let current = &mut self.current[player as usize - 1];
+        let position = playlist
+            .iter()
+            .position(|i| i == current)
+            .map(|i| (i + 1) % playlist.len())
+            .unwrap_or(0);
+        *current = playlist[position];
+    }
+
+    pub fn next_all(&mut self) {
+        for player in 1..=self.current.len() as u32 {
+            self.next(player);
+        }
+    }
+
+    /// Snapshot the canvas to transition from the previous theme to the current theme of player 1
+    pub fn start_transition(&mut self, canvas: &mut WindowCanvas) -> Result<(), String> {
+        let transition = if self.reduced_motion {
+            ThemeTransition::Fade
+        } else {
+            self.transition_config
+                .transition
+                .unwrap_or(self.player_theme(1).transition())
+                .resolve()
+        };
+        self.transition = Some((Duration::ZERO, transition));
 
-        let query = self.fade_buffer.query();
+        let query = self.transition_buffer.query();
         let pixels = canvas.read_pixels(None, query.format)?;
-        self.fade_buffer
+        self.transition_buffer
             .update(
                 None,
                 pixels.as_slice(),
@@ -219,12 +335,14 @@ impl<'a> ThemeContext<'a> {
             .map_err(|e| e.to_string())
     }
 
-    pub fn is_fading(&self) -> bool {
-        self.fade_duration.is_some()
+    pub fn is_transitioning(&self) -> bool {
+        self.transition.is_some()
     }
 
     pub fn render_bg_particles(&self) -> bool {
-        self.current().theme.particle_color().is_some()
+        self.current
+            .iter()
+            .any(|&i| self.themes[i].theme.particle_color().is_some())
     }
 
     pub fn draw_current(
@@ -234,14 +352,15 @@ impl<'a> ThemeContext<'a> {
         delta: Duration,
         offsets: Vec<(f64, f64)>,
     ) -> Result<(), String> {
-        let current = self.current();
         for (texture, texture_mode) in texture_refs.iter_mut() {
             match texture_mode {
                 TextureMode::PlayerBackground(pid) => {
+                    let current = self.player(*pid);
                     let player = current.player_themes[*pid as usize - 1];
                     canvas.copy(texture, current.bg_source_snip, player.bg_snip)?;
                 }
                 TextureMode::PlayerBoard(pid) => {
+                    let current = self.player(*pid);
                     let (offset_x, offset_y) = offsets[*pid as usize - 1];
                     let player = current.player_themes[*pid as usize - 1];
                     let dst = current.scale.offset_proportional_to_block_size(
@@ -254,23 +373,63 @@ impl<'a> ThemeContext<'a> {
             }
         }
 
-        // check if we should be fading out the previous theme
-        match self.fade_duration {
-            None => {}
-            Some(duration) => {
-                let duration = duration + delta;
-                if duration > THEME_FADE_DURATION {
-                    self.fade_duration = None;
-                } else {
-                    let alpha = 255.0 * duration.as_millis() as f64
-                        / THEME_FADE_DURATION.as_millis() as f64;
-                    self.fade_buffer.set_alpha_mod(255 - alpha as u8);
-                    canvas.copy(&self.fade_buffer, None, None)?;
-                    self.fade_duration = Some(duration);
-                }
+        // check if we should be transitioning from the previous theme
+        if let Some((duration, transition)) = self.transition {
+            let duration = duration + delta;
+            let transition_duration = Duration::from_millis(self.transition_config.duration_ms);
+            if duration > transition_duration {
+                self.transition = None;
+            } else {
+                let progress = duration.as_secs_f64() / transition_duration.as_secs_f64();
+                draw_transition(canvas, &mut self.transition_buffer, transition, progress)?;
+                self.transition = Some((duration, transition));
             }
         }
 
         Ok(())
     }
+
+    /// Post process the window of each player with the crt filter of their current theme
+    pub fn draw_crt_filters(&mut self, canvas: &mut WindowCanvas) -> Result<(), String> {
+        for player in 1..=self.current.len() as u32 {
+            let theme = self.player(player);
+            if let Some(crt) = theme.crt {
+                let window = theme.scale.player_window(player);
+                let pixel_size = theme.scale.pixel_size();
+                self.crt_filter.apply(canvas, window, pixel_size, crt)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn playlist_timer_levels() {
+        let mut timer = PlaylistTimer::new(ThemeSwitch::Levels(2), 5, 0);
+        assert!(!timer.update(Duration::ZERO, 6, 10));
+        assert!(timer.update(Duration::ZERO, 7, 20));
+        assert!(!timer.update(Duration::ZERO, 8, 30));
+        assert!(timer.update(Duration::ZERO, 9, 40));
+    }
+
+    #[test]
+    fn playlist_timer_lines() {
+        let mut timer = PlaylistTimer::new(ThemeSwitch::Lines(10), 0, 0);
+        assert!(!timer.update(Duration::ZERO, 0, 9));
+        assert!(timer.update(Duration::ZERO, 1, 12));
+        assert!(!timer.update(Duration::ZERO, 1, 21));
+        assert!(timer.update(Duration::ZERO, 2, 22));
+    }
+
+    #[test]
+    fn playlist_timer_minutes() {
+        let mut timer = PlaylistTimer::new(ThemeSwitch::Minutes(1), 0, 0);
+        assert!(!timer.update(Duration::from_secs(59), 0, 0));
+        assert!(timer.update(Duration::from_secs(1), 0, 0));
+        assert!(!timer.update(Duration::from_secs(30), 0, 0));
+    }
 }
@@ -0,0 +1,123 @@
+use crate::config::UiConfig;
+use sdl2::pixels::Color;
+
+const MIN_FONT_SIZE: u32 = 8;
+const MIN_UI_SCALE: f64 = 0.25;
+
+/// Sizes & colours shared by the menus, pause & high score screens
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UiStyle {
+    scale: f64,
+    high_contrast: bool,
+}
+
+impl UiStyle {
+    pub fn new(config: UiConfig) -> Self {
+        Self {
+            scale: config.scale.max(MIN_UI_SCALE),
+            high_contrast: config.high_contrast,
+        }
+    }
+
+    /// Font size scaled from a fraction of the window width
+    pub fn font_size(&self, window_width: u32, divisor: u32) -> u32 {
+        let size = window_width as f64 / divisor as f64 * self.scale;
+        (size.round() as u32).max(MIN_FONT_SIZE)
+    }
+
+    pub fn text_color(&self) -> Color {
+        Color::WHITE
+    }
+
+    pub fn selected_text_color(&self) -> Color {
+        Color::BLACK
+    }
+
+    pub fn selected_color(&self) -> Color {
+        if self.high_contrast {
+            Color::YELLOW
+        } else {
+            Color::WHITE
+        }
+    }
+
+    pub fn select_list_color(&self) -> Color {
+        if self.high_contrast {
+            Color::RGB(0x40, 0x40, 0x40)
+        } else {
+            Color::RGBA(0xff, 0xff, 0xff, 0x80)
+        }
+    }
+
+    /// Drawn behind menus to hide the particles, none to show them
+    pub fn panel_color(&self) -> Option<Color> {
+        if self.high_contrast {
+            Some(Color::BLACK)
+        } else {
+            None
+        }
+    }
+
+    pub fn caret_color(&self) -> Color {
+        if self.high_contrast {
+            Color::YELLOW
+        } else {
+            Color::RED
+        }
+    }
+
+    /// Dims the paused match behind the pause menu
+    pub fn paused_color(&self) -> Color {
+        if self.high_contrast {
+            Color::RGBA(0, 0, 0, 0xf0)
+        } else {
+            Color::RGBA(0, 0, 0, 0xc0)
+        }
+    }
+}
+
+/// Scroll offset that keeps the selected span of content visible in the viewport,
+/// moving as little as possible from the previous offset
+pub fn scroll_to(previous: u32, selected_top: u32, selected_bottom: u32, viewport: u32, content: u32) -> u32 {
+    if content <= viewport {
+        return 0;
+    }
+    let offset = if selected_top < previous {
+        selected_top
+    } else if selected_bottom > previous + viewport {
+        selected_bottom - viewport
+    } else {
+        previous
+    };
+    offset.min(content - viewport)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn font_size_scales() {
+        let style = UiStyle::new(UiConfig { scale: 1.5, high_contrast: false });
+        assert_eq!(style.font_size(1920, 32), 90);
+        let style = UiStyle::new(UiConfig { scale: 0.1, high_contrast: false });
+        assert_eq!(style.font_size(320, 32), MIN_FONT_SIZE);
+    }
+
+    #[test]
+    fn scroll_fits() {
+        assert_eq!(scroll_to(0, 80, 100, 200, 150), 0);
+    }
+
+    #[test]
+    fn scroll_follows_selection() {
+        // already visible
+        assert_eq!(scroll_to(50, 100, 120, 100, 500), 50);
+        // below the viewport
+        assert_eq!(scroll_to(0, 180, 200, 100, 500), 100);
+        // above the viewport
+        assert_eq!(scroll_to(300, 100, 120, 100, 500), 100);
+        // never past the end
+        assert_eq!(scroll_to(0, 480, 520, 100, 500), 400);
+    }
+}
@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Modified time & size of every file under a path
+type Snapshot = HashMap<PathBuf, (SystemTime, u64)>;
+
+/// Which of the watched paths have changed since the last poll
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Changes {
+    pub config: bool,
+    pub themes: bool,
+}
+
+/// Polls the config file & theme directories for changes, the files are small & few so polling is cheap enough
+pub struct Watcher {
+    config_paths: Vec<PathBuf>,
+    theme_paths: Vec<PathBuf>,
+    config: Snapshot,
+    themes: Snapshot,
+    last_poll: Instant,
+}
+
+impl Watcher {
+    pub fn new(config_path: PathBuf, theme_paths: Vec<PathBuf>) -> Self {
+        let config_paths = vec![config_path];
+        Self {
+            config: snapshot(&config_paths),
+            themes: snapshot(&theme_paths),
+            config_paths,
+            theme_paths,
+            last_poll: Instant::now(),
+        }
+    }
+
+    /// Changes since the last poll, nothing is checked more often than the poll interval
+    pub fn poll(&mut self) -> Changes {
+        if self.last_poll.elapsed() < POLL_INTERVAL {
+            return Changes::default();
+        }
+        self.last_poll = Instant::now();
+        Changes {
+            config: update(&mut self.config, &self.config_paths),
+            themes: update(&mut self.themes, &self.theme_paths),
+        }
+    }
+
+    /// Takes the config file as it is now, so a change the game wrote itself is not reloaded
+    pub fn sync_config(&mut self) {
+        self.config = snapshot(&self.config_paths);
+    }
+}
+
+fn update(previous: &mut Snapshot, paths: &[PathBuf]) -> bool {
+    let next = snapshot(paths);
+    if next == *previous {
+        return false;
+    }
+    *previous = next;
+    true
+}
+
+/// Missing paths are skipped so that creating them is a change
+fn snapshot(paths: &[PathBuf]) -> Snapshot {
+    let mut result = HashMap::new();
+    for path in paths {
+        add_to_snapshot(&mut result, path);
+    }
+    result
+}
+
+fn add_to_snapshot(snapshot: &mut Snapshot, path: &Path) {
+    let Ok(metadata) = fs::metadata(path) else {
+        return;
+    };
+    if metadata.is_dir() {
+        if let Ok(entries) = fs::read_dir(path) {
+            for entry in entries.filter_map(|entry| entry.ok()) {
+                add_to_snapshot(snapshot, &entry.path());
+            }
+        }
+    } else if let Ok(modified) = metadata.modified() {
+        snapshot.insert(path.to_path_buf(), (modified, metadata.len()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_changes_when_files_are_added_changed_or_removed() {
+        let dir = std::env::temp_dir().join(format!("rustris-watch-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let dir = vec![dir];
+
+        let mut previous = snapshot(&dir);
+        assert!(previous.is_empty());
+        assert!(!update(&mut previous, &dir));
+
+        fs::create_dir_all(dir[0].join("pack")).unwrap();
+        fs::write(dir[0].join("pack").join("theme.yml"), "a").unwrap();
+        assert!(update(&mut previous, &dir));
+        assert!(!update(&mut previous, &dir));
+
+        fs::write(dir[0].join("pack").join("theme.yml"), "ab").unwrap();
+        assert!(update(&mut previous, &dir));
+
+        fs::remove_dir_all(&dir[0]).unwrap();
+        assert!(update(&mut previous, &dir));
+        assert!(previous.is_empty());
+    }
+}